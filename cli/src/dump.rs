@@ -0,0 +1,143 @@
+use anchor_lang::AccountDeserialize;
+use anchor_spl::token_interface::TokenAccount;
+use anyhow::{Context, Result};
+use coin_war_client::{accounts, pda, payout, ALL_POOLS};
+use serde_json::{json, Value};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+
+// JSON views of the program accounts. The account structs come from the program and don't derive Serialize, so
+// every field is listed here; pubkeys are printed in base58.
+
+fn account_data(rpc: &RpcClient, address: &Pubkey, name: &str) -> Result<Vec<u8>> {
+    rpc.get_account_data(address).with_context(|| format!("{} {} not found", name, address))
+}
+
+pub fn config(rpc: &RpcClient) -> Result<Value> {
+    let address = pda::config();
+    let config = accounts::config(&account_data(rpc, &address, "config")?)?;
+    let mints: Vec<Value> = config
+        .mints
+        .iter()
+        .map(|allowed| {
+            json!({
+                "mint": allowed.mint.to_string(),
+                "decimals": allowed.decimals,
+                "minimum_deposit": allowed.minimum_deposit,
+                "deposits": allowed.deposits,
+                "invested": allowed.invested,
+            })
+        })
+        .collect();
+    let lock_tiers: Vec<Value> =
+        config.lock_tiers.iter().map(|tier| json!({ "games": tier.games, "multiplier": tier.multiplier })).collect();
+    Ok(json!({
+        "address": address.to_string(),
+        "admin": config.admin.to_string(),
+        "paused": config.paused,
+        "mints": mints,
+        "lock_tiers": lock_tiers,
+        "settled_until": config.settled_until,
+        "version": config.version,
+    }))
+}
+
+pub fn pools(rpc: &RpcClient) -> Result<Value> {
+    let mut pools = Vec::new();
+    for pool_name in ALL_POOLS {
+        let address = pda::pool(pool_name);
+        let pool = accounts::pool(&account_data(rpc, &address, "pool")?)?;
+        pools.push(json!({
+            "address": address.to_string(),
+            "name": pool.name,
+            "is_initialized": pool.is_initialized,
+            "last_update_timestamp": pool.last_update_timestamp,
+            "total_deposit": pool.total_deposit,
+            "user_count": pool.user_count,
+            "average_prediction": pool.average_prediction,
+            "invested": pool.invested,
+            "version": pool.version,
+            "pool_data": pool.pool_data.to_string(),
+            "share_mint": pool.share_mint.to_string(),
+            "share_rate": pool.exchange_rate(),
+            "pending_prize": pool.pending_prize,
+            "pending_withdrawals": pool.pending_withdrawals,
+            "lock_boost": pool.lock_boost,
+            "won_game_id": pool.won_game_id,
+        }));
+    }
+
+    let config = accounts::config(&account_data(rpc, &pda::config(), "config")?)?;
+    let mut pool_wallets = Vec::new();
+    for allowed in &config.mints {
+        let address = pda::pool_wallet(&allowed.mint);
+        let data = account_data(rpc, &address, "pool wallet")?;
+        let pool_wallet = TokenAccount::try_deserialize(&mut data.as_slice())?;
+        pool_wallets.push(json!({
+            "address": address.to_string(),
+            "mint": pool_wallet.mint.to_string(),
+            "amount": pool_wallet.amount,
+        }));
+    }
+    Ok(json!({
+        "pools": pools,
+        "pool_wallets": pool_wallets,
+    }))
+}
+
+pub fn game(rpc: &RpcClient, game_id: u64) -> Result<Value> {
+    let address = pda::game(game_id);
+    let game = accounts::game(&account_data(rpc, &address, "game")?)?;
+    Ok(json!({
+        "address": address.to_string(),
+        "game_id": game.game_id,
+        "start_time": game.start_time,
+        "end_time": game.end_time,
+        "winning_pool": game.winning_pool,
+        "winning_amount": game.winning_amount,
+        "total_prize": game.total_prize,
+        "total_paid": game.total_paid,
+        "remaining_prize": payout::remaining_prize(&game),
+        "sponsored_prize": game.sponsored_prize,
+        "sponsor_count": game.sponsor_count,
+        "compounded_prize": game.compounded_prize,
+        "winning_weight": game.winning_weight,
+        "harvested_mints": game.harvested_mints,
+        "prize_mint": game.prize_mint.to_string(),
+        "version": game.version,
+    }))
+}
+
+pub fn user(rpc: &RpcClient, wallet: &Pubkey) -> Result<Value> {
+    let address = pda::user(wallet);
+    let user = accounts::user(&account_data(rpc, &address, "user")?)?;
+    Ok(json!({
+        "address": address.to_string(),
+        "wallet": wallet.to_string(),
+        "user_wallet": pda::user_wallet(wallet).to_string(),
+        "pool": user.pool,
+        "last_prediction": user.last_prediction,
+        "balance": user.balance,
+        "last_active": user.last_active,
+        "game_history_count": user.game_history_count,
+        "current_average_balance": user.current_average_balance,
+        "current_weighted_balance": user.current_weighted_balance,
+        "current_weighted_days": user.current_weighted_days,
+        "txn_count": user.txn_count,
+        "last_paid_game_id": user.last_paid_game_id,
+        "mint": user.mint.to_string(),
+        "share_rate": user.share_rate(),
+        "shares": user.shares,
+        "delegated_balance": user.delegated_balance,
+        "pending_withdrawal": user.pending_withdrawal,
+        "withdrawal_pool": user.withdrawal_pool,
+        "withdrawal_release_time": user.withdrawal_release_time,
+        "locked_amount": user.locked_amount,
+        "lock_multiplier": user.lock_multiplier,
+        "lock_release_time": user.lock_release_time,
+        "auto_compound": user.auto_compound,
+        "settled_weight": user.settled_weight,
+        "settled_game_id": user.settled_game_id,
+        "version": user.version,
+    }))
+}
@@ -0,0 +1,336 @@
+/* Admin CLI for operating coin-war against a cluster, a local validator by default. Every command is signed and
+ * paid for by the --keypair wallet, which has to be the config admin for add-mint, set-minimum-deposit,
+ * set-lock-tier, pause, unpause, cancel-game, distribute-prize and withdraw-treasury. Commands sending a transaction
+ * print its signature, dump commands print accounts as JSON.
+ */
+
+use std::collections::BTreeMap;
+
+use std::path::PathBuf;
+
+use anchor_lang::AccountDeserialize;
+use anchor_spl::token_interface::TokenAccount;
+use anyhow::{anyhow, Context, Result};
+use clap::{Parser, Subcommand};
+use coin_war_client::{accounts, instruction, pda, Custody, PoolData, ALL_POOLS, BNB, ETHEREUM, ID, POLYGON, SOLANA};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+
+mod dump;
+
+// Wallets per pay_winners_batch transaction. Each wallet adds two accounts and a key to the instruction, so much
+// more than this won't fit in a transaction.
+const DEFAULT_BATCH_SIZE: usize = 8;
+
+#[derive(Parser)]
+#[clap(name = "coin-war-cli", about = "Operate the coin-war program")]
+struct Cli {
+    /// RPC url of the cluster
+    #[clap(long, default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// Keypair file signing and paying for every transaction [default: ~/.config/solana/id.json]
+    #[clap(long)]
+    keypair: Option<PathBuf>,
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create the program config, making the keypair the admin
+    InitializeConfig,
+    /// Accept deposits in mint and create its pool wallet
+    AddMint {
+        mint: Pubkey,
+        /// In base units of the mint
+        #[clap(long)]
+        minimum_deposit: u64,
+    },
+    /// Change the minimum deposit of an allowed mint, in base units of the mint
+    SetMinimumDeposit {
+        mint: Pubkey,
+        minimum_deposit: u64,
+    },
+    /// Set the prize weight multiplier of deposits locked for games games, zero stops offering that lock length
+    SetLockTier {
+        games: u8,
+        multiplier: f64,
+    },
+    /// Create all four pools, each with its PoolData account and share mint
+    CreatePools,
+    /// Create the share mint of every pool that has none, for pools created before shares
+    CreateShareMints,
+    StartGame {
+        game_id: u64,
+    },
+    /// Record the winning pool of a finished game, its prize is what was funded and harvested for it
+    SettleGame {
+        game_id: u64,
+        #[clap(parse(try_from_str = parse_pool))]
+        winning_pool: u8,
+    },
+    /// Record the totals of all four pools at the end of a game
+    SnapshotPools {
+        game_id: u64,
+    },
+    /// Close an unsettled game without prize so its id can be started again
+    CancelGame {
+        game_id: u64,
+    },
+    /// Pay one winner of a settled game
+    PayWinner {
+        game_id: u64,
+        #[clap(parse(try_from_str = parse_pool))]
+        pool: u8,
+        wallet: Pubkey,
+    },
+    /// Pay winners of a settled game in batches. Wallets that were already paid are skipped by the program.
+    PayWinners {
+        game_id: u64,
+        #[clap(parse(try_from_str = parse_pool))]
+        pool: u8,
+        #[clap(required = true)]
+        wallets: Vec<Pubkey>,
+        #[clap(long, default_value_t = DEFAULT_BATCH_SIZE)]
+        batch_size: usize,
+    },
+    /// Drop the released deposit lock of a wallet and its prize weight, e.g. before paying out a game
+    Unlock {
+        wallet: Pubkey,
+    },
+    /// Hand the prize of a settled game to its winning pool through the exchange rate of its shares instead of paying
+    /// it out
+    DistributePrize {
+        game_id: u64,
+        #[clap(parse(try_from_str = parse_pool))]
+        pool: u8,
+    },
+    /// Reject deposits until unpaused
+    Pause,
+    Unpause,
+    /// Send tokens the pool wallet of the destination's mint holds beyond the deposits in that mint, in base units
+    WithdrawTreasury {
+        amount: u64,
+        destination: Pubkey,
+    },
+    /// Bring accounts created by an earlier program version to the current layout
+    Migrate {
+        #[clap(subcommand)]
+        account: MigrateCommand,
+    },
+    /// Print program accounts as JSON
+    Dump {
+        #[clap(subcommand)]
+        account: DumpCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum MigrateCommand {
+    Config,
+    /// All four pools
+    Pools,
+    Game { game_id: u64 },
+    User { wallet: Pubkey },
+}
+
+#[derive(Subcommand)]
+enum DumpCommand {
+    Config,
+    /// All four pools and the pool wallets of the allowed mints
+    Pools,
+    Game { game_id: u64 },
+    User { wallet: Pubkey },
+}
+
+// Pools can be given by code or by name
+fn parse_pool(value: &str) -> std::result::Result<u8, String> {
+    let pool_name = match value.to_lowercase().as_str() {
+        "solana" => SOLANA,
+        "bnb" => BNB,
+        "polygon" => POLYGON,
+        "ethereum" => ETHEREUM,
+        code => code.parse().map_err(|_| format!("unknown pool {}", value))?,
+    };
+    if !ALL_POOLS.contains(&pool_name) {
+        return Err(format!("unknown pool {}", value));
+    }
+    Ok(pool_name)
+}
+
+fn default_keypair_path() -> Result<PathBuf> {
+    let home = std::env::var("HOME").context("HOME is not set, pass --keypair")?;
+    Ok(PathBuf::from(home).join(".config/solana/id.json"))
+}
+
+fn send(rpc: &RpcClient, payer: &Keypair, instructions: &[Instruction]) -> Result<()> {
+    send_signed(rpc, payer, instructions, &[])
+}
+
+// Share mints are new keypair accounts, created by the program with the pool as mint authority
+fn create_share_mints(rpc: &RpcClient, payer: &Keypair) -> Result<()> {
+    for pool_name in ALL_POOLS {
+        let pool = accounts::pool(&rpc.get_account_data(&pda::pool(pool_name))?)?;
+        if pool.share_mint != Pubkey::default() {
+            continue;
+        }
+        let share_mint = Keypair::new();
+        let instruction = instruction::create_share_mint(&payer.pubkey(), pool_name, &share_mint.pubkey());
+        send_signed(rpc, payer, &[instruction], &[&share_mint])?;
+    }
+    Ok(())
+}
+
+// Send instructions that need signers besides the payer, e.g. for accounts they create
+fn send_signed(rpc: &RpcClient, payer: &Keypair, instructions: &[Instruction], signers: &[&Keypair]) -> Result<()> {
+    let mut all_signers = vec![payer];
+    all_signers.extend_from_slice(signers);
+    let blockhash = rpc.get_latest_blockhash()?;
+    let transaction = Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &all_signers, blockhash);
+    let signature = rpc.send_and_confirm_transaction(&transaction)?;
+    println!("{}", signature);
+    Ok(())
+}
+
+fn token_account_mint(rpc: &RpcClient, address: &Pubkey) -> Result<Pubkey> {
+    let data = rpc.get_account_data(address).with_context(|| format!("token account {} not found", address))?;
+    Ok(TokenAccount::try_deserialize(&mut data.as_slice())?.mint)
+}
+
+// SPL Token or Token-2022 program, whichever owns mint
+fn token_program(rpc: &RpcClient, mint: &Pubkey) -> Result<Pubkey> {
+    Ok(rpc.get_account(mint).with_context(|| format!("mint {} not found", mint))?.owner)
+}
+
+// Users are paid from the pool wallet of the mint their balance is held in, into their user wallet if they have one
+// and otherwise into their associated token account. Users from before the mint was recorded all have a user wallet.
+fn user_custody(rpc: &RpcClient, wallet: &Pubkey) -> Result<(Pubkey, Custody)> {
+    let user_wallet = pda::user_wallet(wallet);
+    if rpc.get_account_with_commitment(&user_wallet, rpc.commitment())?.value.is_some() {
+        return Ok((token_account_mint(rpc, &user_wallet)?, Custody::UserWallet));
+    }
+    let user = accounts::user(&rpc.get_account_data(&pda::user(wallet))?)?;
+    Ok((user.mint, Custody::AssociatedTokenAccount))
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let keypair_path = match cli.keypair {
+        Some(path) => path,
+        None => default_keypair_path()?,
+    };
+    let payer = read_keypair_file(&keypair_path)
+        .map_err(|error| anyhow!("can't read keypair {}: {}", keypair_path.display(), error))?;
+    let admin = payer.pubkey();
+    let rpc = RpcClient::new_with_commitment(cli.url, CommitmentConfig::confirmed());
+
+    match cli.command {
+        Command::InitializeConfig => send(&rpc, &payer, &[instruction::initialize_config(&admin)]),
+        Command::AddMint { mint, minimum_deposit } => {
+            let token_program = token_program(&rpc, &mint)?;
+            send(&rpc, &payer, &[instruction::add_mint(&admin, &mint, &token_program, minimum_deposit)])
+        }
+        Command::SetMinimumDeposit { mint, minimum_deposit } => {
+            send(&rpc, &payer, &[instruction::set_minimum_deposit(&admin, &mint, minimum_deposit)])
+        }
+        Command::SetLockTier { games, multiplier } => {
+            send(&rpc, &payer, &[instruction::set_lock_tier(&admin, games, multiplier)])
+        }
+        Command::CreatePools => {
+            let instructions: Vec<Instruction> =
+                ALL_POOLS.iter().map(|&pool_name| instruction::create_pool(&admin, pool_name)).collect();
+            send(&rpc, &payer, &instructions)?;
+
+            // PoolData is too big for the program to create, so it is created here and attached to the pool
+            let len = PoolData::LEN;
+            let rent = rpc.get_minimum_balance_for_rent_exemption(len)?;
+            for pool_name in ALL_POOLS {
+                let pool_data = Keypair::new();
+                let instructions = [
+                    system_instruction::create_account(&admin, &pool_data.pubkey(), rent, len as u64, &ID),
+                    instruction::create_pool_data(&admin, pool_name, &pool_data.pubkey()),
+                ];
+                send_signed(&rpc, &payer, &instructions, &[&pool_data])?;
+            }
+            create_share_mints(&rpc, &payer)
+        }
+        Command::CreateShareMints => create_share_mints(&rpc, &payer),
+        Command::StartGame { game_id } => send(&rpc, &payer, &[instruction::start_game(&admin, game_id)]),
+        Command::SettleGame { game_id, winning_pool } => {
+            send(&rpc, &payer, &[instruction::settle_game(&admin, game_id, winning_pool)])
+        }
+        Command::SnapshotPools { game_id } => {
+            let mut instructions = Vec::new();
+            for pool_name in ALL_POOLS {
+                let pool = accounts::pool(&rpc.get_account_data(&pda::pool(pool_name))?)?;
+                instructions.push(instruction::snapshot_pool(&admin, game_id, pool_name, &pool.pool_data));
+            }
+            send(&rpc, &payer, &instructions)
+        }
+        Command::CancelGame { game_id } => send(&rpc, &payer, &[instruction::cancel_game(&admin, game_id)]),
+        Command::PayWinner { game_id, pool, wallet } => {
+            let (mint, custody) = user_custody(&rpc, &wallet)?;
+            let token_program = token_program(&rpc, &mint)?;
+            let instruction =
+                instruction::pay_winning_pool_user(&admin, &mint, &token_program, custody, &wallet, pool, game_id);
+            send(&rpc, &payer, &[instruction])
+        }
+        Command::PayWinners { game_id, pool, wallets, batch_size } => {
+            // a batch is paid from a single pool wallet, so wallets are batched by mint
+            let mut wallets_by_mint: BTreeMap<Pubkey, Vec<(Pubkey, Custody)>> = BTreeMap::new();
+            for wallet in wallets {
+                let (mint, custody) = user_custody(&rpc, &wallet)?;
+                wallets_by_mint.entry(mint).or_default().push((wallet, custody));
+            }
+            for (mint, wallets) in wallets_by_mint {
+                let token_program = token_program(&rpc, &mint)?;
+                for batch in wallets.chunks(batch_size.max(1)) {
+                    let instruction =
+                        instruction::pay_winners_batch(&admin, &mint, &token_program, batch, pool, game_id);
+                    send(&rpc, &payer, &[instruction])?;
+                }
+            }
+            Ok(())
+        }
+        Command::Unlock { wallet } => {
+            let user = accounts::user(&rpc.get_account_data(&pda::user(&wallet))?)?;
+            send(&rpc, &payer, &[instruction::unlock_deposit(&admin, &wallet, user.pool)])
+        }
+        Command::DistributePrize { game_id, pool } => {
+            send(&rpc, &payer, &[instruction::distribute_prize(&admin, game_id, pool)])
+        }
+        Command::Pause => send(&rpc, &payer, &[instruction::set_paused(&admin, true)]),
+        Command::Unpause => send(&rpc, &payer, &[instruction::set_paused(&admin, false)]),
+        Command::WithdrawTreasury { amount, destination } => {
+            let mint = token_account_mint(&rpc, &destination)?;
+            let token_program = token_program(&rpc, &mint)?;
+            send(&rpc, &payer, &[instruction::withdraw_treasury(&admin, &mint, &token_program, &destination, amount)])
+        }
+        Command::Migrate { account } => {
+            let instructions = match account {
+                MigrateCommand::Config => vec![instruction::migrate_config(&admin)],
+                MigrateCommand::Pools => {
+                    ALL_POOLS.iter().map(|&pool_name| instruction::migrate_pool(&admin, pool_name)).collect()
+                }
+                MigrateCommand::Game { game_id } => vec![instruction::migrate_game(&admin, game_id)],
+                MigrateCommand::User { wallet } => vec![instruction::migrate_user(&admin, &wallet)],
+            };
+            send(&rpc, &payer, &instructions)
+        }
+        Command::Dump { account } => {
+            let json = match account {
+                DumpCommand::Config => dump::config(&rpc)?,
+                DumpCommand::Pools => dump::pools(&rpc)?,
+                DumpCommand::Game { game_id } => dump::game(&rpc, game_id)?,
+                DumpCommand::User { wallet } => dump::user(&rpc, &wallet)?,
+            };
+            println!("{}", serde_json::to_string_pretty(&json)?);
+            Ok(())
+        }
+    }
+}
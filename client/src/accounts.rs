@@ -0,0 +1,35 @@
+use anchor_lang::{AccountDeserialize, Result};
+
+use crate::{Config, Game, Pool, PoolData, Transaction, User};
+
+// Deserialize raw account data, checking the account discriminator
+
+pub fn config(data: &[u8]) -> Result<Config> {
+    deserialize(data)
+}
+
+pub fn pool(data: &[u8]) -> Result<Pool> {
+    deserialize(data)
+}
+
+// PoolData is boxed, it is about 150KB
+pub fn pool_data(data: &[u8]) -> Result<Box<PoolData>> {
+    deserialize(data).map(Box::new)
+}
+
+pub fn user(data: &[u8]) -> Result<User> {
+    deserialize(data)
+}
+
+pub fn game(data: &[u8]) -> Result<Game> {
+    deserialize(data)
+}
+
+// Transaction records are never migrated, so they are read in whatever layout they were created in
+pub fn transaction(data: &[u8]) -> Result<Transaction> {
+    coin_war::migrate::upgrade_transaction(data)
+}
+
+pub fn deserialize<T: AccountDeserialize>(mut data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut data)
+}
@@ -0,0 +1,774 @@
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::{associated_token, token};
+use coin_war::custody::Custody;
+use coin_war::{accounts, instruction};
+
+use crate::pda;
+
+/* Builders for every coin-war instruction. Users deposit from and get paid into their user wallet or their own
+ * associated token account, chosen by custody, see pda::user_token_account, and mint is the mint their balance is held
+ * in. token_program is the program owning mint, the SPL Token or the Token-2022 program. Users can also deposit and
+ * withdraw native SOL while their balance is in wSOL. Deposits and withdrawals mint and burn shares of the pool in
+ * the wallet's associated token account of the pool's share mint, see pda::user_share_account. Delegates approved
+ * by a user deposit for and withdraw from its position out of their own associated token accounts, usually signing
+ * through CPI. The caller signs with the owner, sponsor, user wallet or delegate passed in first.
+ */
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: coin_war::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+// Accounts the program takes as optional, left out while they are Pubkey::default(), like the Pool::pool_data of a
+// pool that doesn't have one yet
+fn optional(address: &Pubkey) -> Option<Pubkey> {
+    (*address != Pubkey::default()).then_some(*address)
+}
+
+pub fn initialize_config(admin: &Pubkey) -> Instruction {
+    let accounts = accounts::InitializeConfig {
+        admin: *admin,
+        config: pda::config(),
+        program_data: pda::program_data(),
+        system_program: system_program::ID,
+    };
+    build(accounts, instruction::InitializeConfig {})
+}
+
+pub fn set_paused(admin: &Pubkey, paused: bool) -> Instruction {
+    let accounts = accounts::SetPaused {
+        admin: *admin,
+        config: pda::config(),
+    };
+    build(accounts, instruction::SetPaused { paused })
+}
+
+// destination is a token account of mint, amount is in its base units
+pub fn withdraw_treasury(
+    admin: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    destination: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let accounts = accounts::WithdrawTreasury {
+        admin: *admin,
+        config: pda::config(),
+        pool_token_account: pda::pool_wallet(mint),
+        destination: *destination,
+        token_program: *token_program,
+        mint_address: *mint,
+    };
+    build(accounts, instruction::WithdrawTreasury { amount })
+}
+
+// Accept deposits in mint and create its pool wallet, minimum_deposit is in base units of mint
+pub fn add_mint(admin: &Pubkey, mint: &Pubkey, token_program: &Pubkey, minimum_deposit: u64) -> Instruction {
+    let accounts = accounts::AddMint {
+        admin: *admin,
+        config: pda::config(),
+        pool_token_account: pda::pool_wallet(mint),
+        mint_address: *mint,
+        token_program: *token_program,
+        system_program: system_program::ID,
+        rent: sysvar::rent::ID,
+    };
+    build(accounts, instruction::AddMint { minimum_deposit })
+}
+
+pub fn set_minimum_deposit(admin: &Pubkey, mint: &Pubkey, minimum_deposit: u64) -> Instruction {
+    let accounts = accounts::SetMinimumDeposit {
+        admin: *admin,
+        config: pda::config(),
+    };
+    build(accounts, instruction::SetMinimumDeposit { mint: *mint, minimum_deposit })
+}
+
+// A multiplier of zero stops offering the lock length, see coin_war::lockup
+pub fn set_lock_tier(admin: &Pubkey, games: u8, multiplier: f64) -> Instruction {
+    let accounts = accounts::SetLockTier {
+        admin: *admin,
+        config: pda::config(),
+    };
+    build(accounts, instruction::SetLockTier { games, multiplier })
+}
+
+pub fn create_pool(admin: &Pubkey, pool_name: u8) -> Instruction {
+    let accounts = accounts::CreatePool {
+        admin: *admin,
+        config: pda::config(),
+        pool: pda::pool(pool_name),
+        system_program: system_program::ID,
+    };
+    build(accounts, instruction::CreatePool { pool_name })
+}
+
+// pool_data is a new account created with PoolData::LEN bytes owned by the program, in the same transaction
+pub fn create_pool_data(admin: &Pubkey, pool_name: u8, pool_data: &Pubkey) -> Instruction {
+    let accounts = accounts::CreatePoolData {
+        admin: *admin,
+        config: pda::config(),
+        pool: pda::pool(pool_name),
+        pool_data: *pool_data,
+    };
+    build(accounts, instruction::CreatePoolData { pool_name })
+}
+
+// share_mint is a new keypair, signing the same transaction
+pub fn create_share_mint(admin: &Pubkey, pool_name: u8, share_mint: &Pubkey) -> Instruction {
+    let accounts = accounts::CreateShareMint {
+        admin: *admin,
+        config: pda::config(),
+        pool: pda::pool(pool_name),
+        share_mint: *share_mint,
+        token_program: token::ID,
+        system_program: system_program::ID,
+        rent: sysvar::rent::ID,
+    };
+    build(accounts, instruction::CreateShareMint { _pool_name: pool_name })
+}
+
+// pool_data is the pool's Pool::pool_data
+pub fn snapshot_pool(admin: &Pubkey, game_id: u64, pool_name: u8, pool_data: &Pubkey) -> Instruction {
+    let accounts = accounts::SnapshotPool {
+        admin: *admin,
+        config: pda::config(),
+        game: pda::game(game_id),
+        pool: pda::pool(pool_name),
+        pool_data: *pool_data,
+    };
+    build(accounts, instruction::SnapshotPool { game_id, _pool_name: pool_name })
+}
+
+pub fn start_game(admin: &Pubkey, game_id: u64) -> Instruction {
+    let accounts = accounts::StartGame {
+        admin: *admin,
+        config: pda::config(),
+        game: pda::game(game_id),
+        system_program: system_program::ID,
+    };
+    build(accounts, instruction::StartGame { game_id })
+}
+
+pub fn cancel_game(admin: &Pubkey, game_id: u64) -> Instruction {
+    let accounts = accounts::CancelGame {
+        admin: *admin,
+        config: pda::config(),
+        game: pda::game(game_id),
+    };
+    build(accounts, instruction::CancelGame { game_id })
+}
+
+pub fn settle_game(admin: &Pubkey, game_id: u64, winning_pool: u8) -> Instruction {
+    let accounts = accounts::SettleGame {
+        admin: *admin,
+        config: pda::config(),
+        game: pda::game(game_id),
+        pool: pda::pool(winning_pool),
+    };
+    build(accounts, instruction::SettleGame { game_id, winning_pool })
+}
+
+// sponsor_count is the current Game::sponsor_count, which numbers the new Sponsorship account
+pub fn fund_prize(
+    sponsor: &Pubkey,
+    sponsor_token_account: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    game_id: u64,
+    sponsor_count: u64,
+    amount: u64,
+) -> Instruction {
+    let accounts = accounts::FundPrize {
+        sponsor: *sponsor,
+        config: pda::config(),
+        game: pda::game(game_id),
+        sponsorship: pda::sponsorship(game_id, sponsor_count),
+        sponsor_token_account: *sponsor_token_account,
+        pool_token_account: pda::pool_wallet(mint),
+        token_program: *token_program,
+        mint_address: *mint,
+        system_program: system_program::ID,
+    };
+    build(accounts, instruction::FundPrize { game_id, amount })
+}
+
+fn manage_yield_accounts(admin: &Pubkey, mint: &Pubkey, pool_name: u8) -> accounts::ManageYield {
+    accounts::ManageYield {
+        admin: *admin,
+        config: pda::config(),
+        pool: pda::pool(pool_name),
+        pool_token_account: pda::pool_wallet(mint),
+        reserve: pda::lending_reserve(mint),
+        reserve_vault: pda::lending_reserve_vault(mint),
+        obligation: pda::lending_obligation(mint),
+        lending_program: mock_lending::ID,
+        token_program: token::ID,
+        mint_address: *mint,
+    }
+}
+
+pub fn deposit_idle_funds(admin: &Pubkey, mint: &Pubkey, pool_name: u8, amount: u64) -> Instruction {
+    let accounts = manage_yield_accounts(admin, mint, pool_name);
+    build(accounts, instruction::DepositIdleFunds { _pool_name: pool_name, amount })
+}
+
+pub fn withdraw_idle_funds(admin: &Pubkey, mint: &Pubkey, pool_name: u8, amount: u64) -> Instruction {
+    let accounts = manage_yield_accounts(admin, mint, pool_name);
+    build(accounts, instruction::WithdrawIdleFunds { _pool_name: pool_name, amount })
+}
+
+pub fn harvest_yield(admin: &Pubkey, mint: &Pubkey, game_id: u64) -> Instruction {
+    let accounts = accounts::HarvestYield {
+        admin: *admin,
+        config: pda::config(),
+        game: pda::game(game_id),
+        pool_token_account: pda::pool_wallet(mint),
+        reserve: pda::lending_reserve(mint),
+        reserve_vault: pda::lending_reserve_vault(mint),
+        obligation: pda::lending_obligation(mint),
+        lending_program: mock_lending::ID,
+        token_program: token::ID,
+        mint_address: *mint,
+    };
+    build(accounts, instruction::HarvestYield { _game_id: game_id })
+}
+
+pub fn create_user(wallet: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Instruction {
+    let accounts = accounts::CreateUser {
+        initializer: *wallet,
+        config: pda::config(),
+        user: pda::user(wallet),
+        user_token_account: pda::user_wallet(wallet),
+        token_program: *token_program,
+        mint_address: *mint,
+        system_program: system_program::ID,
+        rent: sysvar::rent::ID,
+    };
+    build(accounts, instruction::CreateUser {})
+}
+
+// For users depositing from their associated token account, who need no user wallet
+pub fn create_user_without_wallet(wallet: &Pubkey) -> Instruction {
+    let accounts = accounts::CreateUserWithoutWallet {
+        initializer: *wallet,
+        user: pda::user(wallet),
+        system_program: system_program::ID,
+    };
+    build(accounts, instruction::CreateUserWithoutWallet {})
+}
+
+// Migrations can be paid for by any wallet, see the migrate module of the program
+pub fn migrate_config(payer: &Pubkey) -> Instruction {
+    let accounts = accounts::MigrateConfig {
+        payer: *payer,
+        config: pda::config(),
+        system_program: system_program::ID,
+    };
+    build(accounts, instruction::MigrateConfig {})
+}
+
+pub fn migrate_user(payer: &Pubkey, wallet: &Pubkey) -> Instruction {
+    let accounts = accounts::MigrateUser {
+        payer: *payer,
+        user: pda::user(wallet),
+        system_program: system_program::ID,
+    };
+    build(accounts, instruction::MigrateUser { _user_key: *wallet })
+}
+
+pub fn migrate_pool(payer: &Pubkey, pool_name: u8) -> Instruction {
+    let accounts = accounts::MigratePool {
+        payer: *payer,
+        pool: pda::pool(pool_name),
+        system_program: system_program::ID,
+    };
+    build(accounts, instruction::MigratePool { _pool_name: pool_name })
+}
+
+pub fn migrate_game(payer: &Pubkey, game_id: u64) -> Instruction {
+    let accounts = accounts::MigrateGame {
+        payer: *payer,
+        game: pda::game(game_id),
+        system_program: system_program::ID,
+    };
+    build(accounts, instruction::MigrateGame { _game_id: game_id })
+}
+
+// Predictions and prices are given in the order of the Pools enum
+pub fn select_winning_pool(pool_names: Vec<u8>, pool_predictions: Vec<f64>, pool_coin_prices: Vec<f64>) -> Instruction {
+    let accounts = accounts::SelectWinningPool { clock: sysvar::clock::ID };
+    build(accounts, instruction::SelectWinningPool { pool_names, pool_predictions, pool_coin_prices })
+}
+
+pub fn pay_winning_pool_user(
+    owner: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    custody: Custody,
+    wallet: &Pubkey,
+    pool_name: u8,
+    game_id: u64,
+) -> Instruction {
+    let accounts = accounts::PayWinner {
+        owner: *owner,
+        config: pda::config(),
+        game: pda::game(game_id),
+        user: pda::user(wallet),
+        user_token_account: pda::user_token_account(wallet, mint, token_program, custody),
+        pool: pda::pool(pool_name),
+        pool_token_account: pda::pool_wallet(mint),
+        token_program: *token_program,
+        mint_address: *mint,
+        system_program: system_program::ID,
+    };
+    build(accounts, instruction::PayWinningPoolUser { user_key: *wallet, pool_name, game_id })
+}
+
+// Pays every wallet in one transaction, passing a (User, user token account) pair per wallet as remaining accounts
+pub fn pay_winners_batch(
+    owner: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    wallets: &[(Pubkey, Custody)],
+    pool_name: u8,
+    game_id: u64,
+) -> Instruction {
+    let accounts = accounts::PayWinnersBatch {
+        owner: *owner,
+        config: pda::config(),
+        game: pda::game(game_id),
+        pool: pda::pool(pool_name),
+        pool_token_account: pda::pool_wallet(mint),
+        token_program: *token_program,
+        mint_address: *mint,
+        system_program: system_program::ID,
+    };
+    let user_keys = wallets.iter().map(|(wallet, _)| *wallet).collect();
+    let mut instruction = build(accounts, instruction::PayWinnersBatch { game_id, user_keys, pool_name });
+    for (wallet, custody) in wallets {
+        instruction.accounts.push(AccountMeta::new(pda::user(wallet), false));
+        let user_token_account = pda::user_token_account(wallet, mint, token_program, *custody);
+        instruction.accounts.push(AccountMeta::new(user_token_account, false));
+    }
+    instruction
+}
+
+pub fn distribute_prize(admin: &Pubkey, game_id: u64, pool_name: u8) -> Instruction {
+    let accounts = accounts::DistributePrize {
+        admin: *admin,
+        config: pda::config(),
+        game: pda::game(game_id),
+        pool: pda::pool(pool_name),
+    };
+    build(accounts, instruction::DistributePrize { game_id, pool_name })
+}
+
+pub fn create_distributor(
+    admin: &Pubkey,
+    game_id: u64,
+    merkle_root: [u8; 32],
+    max_total_claim: u64,
+    max_num_nodes: u64,
+) -> Instruction {
+    let accounts = accounts::CreateDistributor {
+        admin: *admin,
+        config: pda::config(),
+        game: pda::game(game_id),
+        distributor: pda::distributor(game_id),
+        system_program: system_program::ID,
+    };
+    build(accounts, instruction::CreateDistributor { game_id, merkle_root, max_total_claim, max_num_nodes })
+}
+
+pub fn claim_with_proof(
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    custody: Custody,
+    game_id: u64,
+    pool_name: u8,
+    index: u64,
+    amount: u64,
+    proof: Vec<[u8; 32]>,
+) -> Instruction {
+    let accounts = accounts::ClaimWithProof {
+        claimant: *wallet,
+        config: pda::config(),
+        user: pda::user(wallet),
+        user_token_account: pda::user_token_account(wallet, mint, token_program, custody),
+        game: pda::game(game_id),
+        distributor: pda::distributor(game_id),
+        pool_token_account: pda::pool_wallet(mint),
+        token_program: *token_program,
+        mint_address: *mint,
+    };
+    build(accounts, instruction::ClaimWithProof { game_id, pool_name, index, amount, proof })
+}
+
+pub fn make_prediction(wallet: &Pubkey, pool_name: u8, prediction: f64) -> Instruction {
+    let accounts = accounts::MakePrediction {
+        owner: *wallet,
+        user: pda::user(wallet),
+        pool: pda::pool(pool_name),
+    };
+    build(accounts, instruction::MakePrediction { pool_name, prediction })
+}
+
+pub fn set_payout_preference(wallet: &Pubkey, auto_compound: bool) -> Instruction {
+    let accounts = accounts::SetPayoutPreference { owner: *wallet, user: pda::user(wallet) };
+    build(accounts, instruction::SetPayoutPreference { auto_compound })
+}
+
+// share_mint is the pool's Pool::share_mint
+pub fn issue_shares(wallet: &Pubkey, pool_name: u8, share_mint: &Pubkey) -> Instruction {
+    let accounts = accounts::IssueShares {
+        initializer: *wallet,
+        user: pda::user(wallet),
+        pool: pda::pool(pool_name),
+        share_mint: *share_mint,
+        user_share_account: pda::user_share_account(wallet, share_mint),
+        token_program: token::ID,
+    };
+    build(accounts, instruction::IssueShares { pool_name })
+}
+
+// txn_count is the current User::txn_count, which numbers the new Transaction record
+// pool_data and share_mint are the pool's Pool::pool_data and Pool::share_mint
+pub fn withdraw(
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    custody: Custody,
+    pool_name: u8,
+    pool_data: &Pubkey,
+    share_mint: &Pubkey,
+    amount: f64,
+    txn_count: u64,
+) -> Instruction {
+    let accounts = accounts::Withdraw {
+        initializer: *wallet,
+        config: pda::config(),
+        user: pda::user(wallet),
+        user_token_account: pda::user_token_account(wallet, mint, token_program, custody),
+        pool: pda::pool(pool_name),
+        pool_data: optional(pool_data),
+        pool_token_account: pda::pool_wallet(mint),
+        share_mint: *share_mint,
+        user_share_account: pda::user_share_account(wallet, share_mint),
+        transaction: pda::tx(wallet, pool_name, txn_count),
+        token_program: *token_program,
+        share_token_program: token::ID,
+        mint_address: *mint,
+        system_program: system_program::ID,
+        associated_token_program: associated_token::ID,
+        rent: sysvar::rent::ID,
+    };
+    build(accounts, instruction::Withdraw { amount, pool_name })
+}
+
+// pool_data and share_mint are the pool's Pool::pool_data and Pool::share_mint
+pub fn deposit(
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    custody: Custody,
+    pool_name: u8,
+    pool_data: &Pubkey,
+    share_mint: &Pubkey,
+    amount: f64,
+    prediction: f64,
+) -> Instruction {
+    let accounts = accounts::Deposit {
+        initializer: *wallet,
+        config: pda::config(),
+        user: pda::user(wallet),
+        user_token_account: pda::user_token_account(wallet, mint, token_program, custody),
+        pool: pda::pool(pool_name),
+        pool_data: *pool_data,
+        pool_token_account: pda::pool_wallet(mint),
+        share_mint: *share_mint,
+        user_share_account: pda::user_share_account(wallet, share_mint),
+        token_program: *token_program,
+        share_token_program: token::ID,
+        mint_address: *mint,
+        system_program: system_program::ID,
+    };
+    build(accounts, instruction::Deposit { amount, pool_name, prediction })
+}
+
+// Deposit native SOL from the wallet, for users without a balance or with their balance in wSOL
+pub fn deposit_sol(
+    wallet: &Pubkey,
+    pool_name: u8,
+    pool_data: &Pubkey,
+    share_mint: &Pubkey,
+    amount: f64,
+    prediction: f64,
+) -> Instruction {
+    let accounts = accounts::DepositSol {
+        initializer: *wallet,
+        config: pda::config(),
+        user: pda::user(wallet),
+        pool: pda::pool(pool_name),
+        pool_data: *pool_data,
+        pool_token_account: pda::pool_wallet(&token::spl_token::native_mint::ID),
+        share_mint: *share_mint,
+        user_share_account: pda::user_share_account(wallet, share_mint),
+        token_program: token::ID,
+        system_program: system_program::ID,
+    };
+    build(accounts, instruction::DepositSol { amount, pool_name, prediction })
+}
+
+// Withdraw as native SOL to the wallet, txn_count is the user's User::txn_count
+pub fn withdraw_sol(
+    wallet: &Pubkey,
+    pool_name: u8,
+    pool_data: &Pubkey,
+    share_mint: &Pubkey,
+    amount: f64,
+    txn_count: u64,
+) -> Instruction {
+    let accounts = accounts::WithdrawSol {
+        initializer: *wallet,
+        config: pda::config(),
+        user: pda::user(wallet),
+        pool: pda::pool(pool_name),
+        pool_data: optional(pool_data),
+        pool_token_account: pda::pool_wallet(&token::spl_token::native_mint::ID),
+        share_mint: *share_mint,
+        user_share_account: pda::user_share_account(wallet, share_mint),
+        unwrap_account: pda::unwrap_account(wallet),
+        transaction: pda::tx(wallet, pool_name, txn_count),
+        mint_address: token::spl_token::native_mint::ID,
+        token_program: token::ID,
+        system_program: system_program::ID,
+        rent: sysvar::rent::ID,
+    };
+    build(accounts, instruction::WithdrawSol { amount, pool_name })
+}
+
+// Gift deposit out of the donor's associated token account of mint into the position of beneficiary, whose User
+// account is created if needed. The beneficiary's share account must exist, see pda::user_share_account.
+// txn_count is the beneficiary's User::txn_count, 0 for a new user.
+pub fn deposit_for_user(
+    donor: &Pubkey,
+    beneficiary: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    pool_name: u8,
+    pool_data: &Pubkey,
+    share_mint: &Pubkey,
+    amount: f64,
+    prediction: f64,
+    txn_count: u64,
+) -> Instruction {
+    let accounts = accounts::DepositForUser {
+        donor: *donor,
+        config: pda::config(),
+        user: pda::user(beneficiary),
+        donor_token_account: pda::user_token_account(donor, mint, token_program, Custody::AssociatedTokenAccount),
+        pool: pda::pool(pool_name),
+        pool_data: *pool_data,
+        pool_token_account: pda::pool_wallet(mint),
+        share_mint: *share_mint,
+        beneficiary_share_account: pda::user_share_account(beneficiary, share_mint),
+        transaction: pda::tx(beneficiary, pool_name, txn_count),
+        token_program: *token_program,
+        share_token_program: token::ID,
+        mint_address: *mint,
+        system_program: system_program::ID,
+        rent: sysvar::rent::ID,
+    };
+    build(accounts, instruction::DepositForUser { beneficiary: *beneficiary, amount, pool_name, prediction })
+}
+
+// Let delegate deposit for the wallet and withdraw what it deposited, see coin_war::delegation
+pub fn approve_delegate(wallet: &Pubkey, delegate: &Pubkey) -> Instruction {
+    let accounts = accounts::ApproveDelegate {
+        initializer: *wallet,
+        user: pda::user(wallet),
+        delegation: pda::delegation(wallet, delegate),
+        system_program: system_program::ID,
+    };
+    build(accounts, instruction::ApproveDelegate { delegate: *delegate })
+}
+
+pub fn revoke_delegate(wallet: &Pubkey, delegate: &Pubkey) -> Instruction {
+    let accounts = accounts::RevokeDelegate {
+        initializer: *wallet,
+        user: pda::user(wallet),
+        delegation: pda::delegation(wallet, delegate),
+    };
+    build(accounts, instruction::RevokeDelegate { _delegate: *delegate })
+}
+
+// Deposit out of the delegate's associated token account of mint into the position of beneficiary, the shares go to
+// the delegate's associated token account of share_mint
+pub fn deposit_for(
+    delegate: &Pubkey,
+    beneficiary: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    pool_name: u8,
+    pool_data: &Pubkey,
+    share_mint: &Pubkey,
+    amount: f64,
+    prediction: f64,
+) -> Instruction {
+    let accounts = accounts::DepositFor {
+        delegate: *delegate,
+        config: pda::config(),
+        user: pda::user(beneficiary),
+        delegation: pda::delegation(beneficiary, delegate),
+        delegate_token_account: pda::user_token_account(delegate, mint, token_program, Custody::AssociatedTokenAccount),
+        pool: pda::pool(pool_name),
+        pool_data: *pool_data,
+        pool_token_account: pda::pool_wallet(mint),
+        share_mint: *share_mint,
+        delegate_share_account: pda::user_share_account(delegate, share_mint),
+        token_program: *token_program,
+        share_token_program: token::ID,
+        mint_address: *mint,
+    };
+    build(accounts, instruction::DepositFor { beneficiary: *beneficiary, amount, pool_name, prediction })
+}
+
+// Withdraw what the delegate deposited for beneficiary into destination, any token account of mint. payer pays for
+// the Transaction record, txn_count being the beneficiary's User::txn_count.
+pub fn withdraw_to(
+    delegate: &Pubkey,
+    payer: &Pubkey,
+    beneficiary: &Pubkey,
+    destination: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    pool_name: u8,
+    pool_data: &Pubkey,
+    share_mint: &Pubkey,
+    amount: f64,
+    txn_count: u64,
+) -> Instruction {
+    let accounts = accounts::WithdrawTo {
+        delegate: *delegate,
+        payer: *payer,
+        config: pda::config(),
+        user: pda::user(beneficiary),
+        delegation: pda::delegation(beneficiary, delegate),
+        destination: *destination,
+        pool: pda::pool(pool_name),
+        pool_data: optional(pool_data),
+        pool_token_account: pda::pool_wallet(mint),
+        share_mint: *share_mint,
+        delegate_share_account: pda::user_share_account(delegate, share_mint),
+        transaction: pda::tx(beneficiary, pool_name, txn_count),
+        token_program: *token_program,
+        share_token_program: token::ID,
+        mint_address: *mint,
+        system_program: system_program::ID,
+        rent: sysvar::rent::ID,
+    };
+    build(accounts, instruction::WithdrawTo { beneficiary: *beneficiary, amount, pool_name })
+}
+
+// Queue amount of the balance to be paid out when game_id ends, see coin_war::accounting::apply_withdrawal_request
+pub fn request_withdrawal(
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    game_id: u64,
+    pool_name: u8,
+    pool_data: &Pubkey,
+    share_mint: &Pubkey,
+    amount: f64,
+) -> Instruction {
+    let accounts = accounts::RequestWithdrawal {
+        initializer: *wallet,
+        config: pda::config(),
+        user: pda::user(wallet),
+        game: pda::game(game_id),
+        pool: pda::pool(pool_name),
+        pool_data: optional(pool_data),
+        share_mint: *share_mint,
+        user_share_account: pda::user_share_account(wallet, share_mint),
+        share_token_program: token::ID,
+        mint_address: *mint,
+    };
+    build(accounts, instruction::RequestWithdrawal { _game_id: game_id, amount, pool_name })
+}
+
+pub fn cancel_withdrawal_request(
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    pool_name: u8,
+    pool_data: &Pubkey,
+    share_mint: &Pubkey,
+    prediction: f64,
+) -> Instruction {
+    let accounts = accounts::CancelWithdrawalRequest {
+        initializer: *wallet,
+        config: pda::config(),
+        user: pda::user(wallet),
+        pool: pda::pool(pool_name),
+        pool_data: *pool_data,
+        share_mint: *share_mint,
+        user_share_account: pda::user_share_account(wallet, share_mint),
+        share_token_program: token::ID,
+        mint_address: *mint,
+    };
+    build(accounts, instruction::CancelWithdrawalRequest { pool_name, prediction })
+}
+
+// pool_name is the User::withdrawal_pool of the request
+pub fn complete_withdrawal(
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    custody: Custody,
+    pool_name: u8,
+    txn_count: u64,
+) -> Instruction {
+    let accounts = accounts::CompleteWithdrawal {
+        initializer: *wallet,
+        config: pda::config(),
+        user: pda::user(wallet),
+        user_token_account: pda::user_token_account(wallet, mint, token_program, custody),
+        pool: pda::pool(pool_name),
+        pool_token_account: pda::pool_wallet(mint),
+        transaction: pda::tx(wallet, pool_name, txn_count),
+        token_program: *token_program,
+        mint_address: *mint,
+        system_program: system_program::ID,
+        rent: sysvar::rent::ID,
+    };
+    build(accounts, instruction::CompleteWithdrawal { pool_name })
+}
+
+// Lock amount of the balance for games games counting game_id, the game in progress
+pub fn lock_deposit(wallet: &Pubkey, game_id: u64, amount: f64, pool_name: u8, games: u8) -> Instruction {
+    let accounts = accounts::LockDeposit {
+        initializer: *wallet,
+        config: pda::config(),
+        user: pda::user(wallet),
+        game: pda::game(game_id),
+        pool: pda::pool(pool_name),
+    };
+    build(accounts, instruction::LockDeposit { _game_id: game_id, amount, pool_name, games })
+}
+
+// Anyone can sign, pool_name is the pool the wallet is in
+pub fn unlock_deposit(signer: &Pubkey, wallet: &Pubkey, pool_name: u8) -> Instruction {
+    let accounts = accounts::UnlockDeposit {
+        signer: *signer,
+        config: pda::config(),
+        user: pda::user(wallet),
+        pool: pda::pool(pool_name),
+    };
+    build(accounts, instruction::UnlockDeposit { user_key: *wallet, pool_name })
+}
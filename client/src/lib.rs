@@ -0,0 +1,18 @@
+/* Client for the coin-war program - PDA derivation, instruction builders, account deserializers and offline payout
+ * calculations, so integrators don't have to build instructions from coin_war.json by hand.
+ */
+
+pub mod accounts;
+pub mod instruction;
+pub mod payout;
+pub mod pda;
+
+pub use coin_war::custody::Custody;
+pub use coin_war::{Config, Game, Pool, PoolData, PoolSnapshot, Transaction, User, ID};
+
+// Pool codes, in the order of the Pools enum in the program
+pub const SOLANA: u8 = 1;
+pub const BNB: u8 = 2;
+pub const POLYGON: u8 = 3;
+pub const ETHEREUM: u8 = 4;
+pub const ALL_POOLS: [u8; 4] = [SOLANA, BNB, POLYGON, ETHEREUM];
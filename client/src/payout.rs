@@ -0,0 +1,22 @@
+use coin_war::{accounting, math};
+
+use crate::{Game, Pool, User};
+
+// Prize the user gets for game when paid with pay_winning_pool_user or pay_winners_batch, in AMOUNT_DECIMALS units.
+// Zero if the game is not settled yet, the user had no balance in the winning pool when it was settled or was already
+// paid for this game. With auto_compound it is added to the balance instead of paid out.
+pub fn expected_payout(user: &User, pool: &Pool, game: &Game) -> u64 {
+    if game.winning_pool == 0 || game.winning_pool != pool.name {
+        return 0;
+    }
+    if user.last_paid_game_id >= game.game_id {
+        return 0;
+    }
+    let weight = accounting::user_weight(user, pool, game);
+    math::prize_share(weight, accounting::winning_weight(pool, game), game.total_prize) as u64
+}
+
+// Part of the prize that has not been paid out yet
+pub fn remaining_prize(game: &Game) -> f64 {
+    game.total_prize - game.total_paid
+}
@@ -0,0 +1,102 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use coin_war::custody::Custody;
+
+// Addresses of the program accounts, derived with the same seeds as the program
+
+pub fn config() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &coin_war::ID).0
+}
+
+// ProgramData account of the deployed program, which holds its upgrade authority
+pub fn program_data() -> Pubkey {
+    Pubkey::find_program_address(&[coin_war::ID.as_ref()], &bpf_loader_upgradeable::ID).0
+}
+
+pub fn pool(pool_name: u8) -> Pubkey {
+    Pubkey::find_program_address(&[&[pool_name]], &coin_war::ID).0
+}
+
+// Each allowed mint has a pool wallet holding the deposits of all four pools made in that mint, as well as prize
+pub fn pool_wallet(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"pool_wallet", mint.as_ref()], &coin_war::ID).0
+}
+
+// User account of a wallet
+pub fn user(wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"user", wallet.as_ref()], &coin_war::ID).0
+}
+
+// Token account owned by the user account, created by create_user
+pub fn user_wallet(wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"user_wallet", user(wallet).as_ref()], &coin_war::ID).0
+}
+
+// Token account deposits are taken from and payouts are sent to, the user wallet or the wallet's own associated token
+// account of mint
+pub fn user_token_account(wallet: &Pubkey, mint: &Pubkey, token_program: &Pubkey, custody: Custody) -> Pubkey {
+    match custody {
+        Custody::UserWallet => user_wallet(wallet),
+        Custody::AssociatedTokenAccount => get_associated_token_address_with_program_id(wallet, mint, token_program),
+    }
+}
+
+// Token account of the wallet holding its shares of a pool, share_mint being the pool's Pool::share_mint
+pub fn user_share_account(wallet: &Pubkey, share_mint: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(wallet, share_mint, &anchor_spl::token::ID)
+}
+
+// Lets delegate act on the position of the wallet's user account, see coin_war::delegation
+pub fn delegation(wallet: &Pubkey, delegate: &Pubkey) -> Pubkey {
+    coin_war::delegation::delegation_address(&user(wallet), delegate)
+}
+
+// Token account that wSOL passes through on withdraw_sol, closed within the same instruction
+pub fn unwrap_account(wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"unwrap", user(wallet).as_ref()], &coin_war::ID).0
+}
+
+// Transaction record created by the txn_count-th withdrawal of a user
+pub fn tx(wallet: &Pubkey, pool_name: u8, txn_count: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"tx", user(wallet).as_ref(), pool(pool_name).as_ref(), &txn_count.to_be_bytes()],
+        &coin_war::ID,
+    )
+    .0
+}
+
+pub fn game(game_id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"game", &game_id.to_be_bytes()], &coin_war::ID).0
+}
+
+pub fn sponsorship(game_id: u64, sponsor_count: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"sponsorship", &game_id.to_be_bytes(), &sponsor_count.to_be_bytes()],
+        &coin_war::ID,
+    )
+    .0
+}
+
+pub fn distributor(game_id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"distributor", &game_id.to_be_bytes()], &coin_war::ID).0
+}
+
+// Lending program accounts used as the yield source for a mint
+
+pub fn lending_reserve(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"reserve", mint.as_ref()], &mock_lending::ID).0
+}
+
+pub fn lending_reserve_vault(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"reserve_vault", mint.as_ref()], &mock_lending::ID).0
+}
+
+// Obligation of the mint's pool wallet in the lending reserve
+pub fn lending_obligation(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"obligation", lending_reserve(mint).as_ref(), pool_wallet(mint).as_ref()],
+        &mock_lending::ID,
+    )
+    .0
+}
@@ -0,0 +1,321 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountSerialize, InstructionData};
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use coin_war_client::{accounts, instruction, payout, pda, Custody, Game, Pool, User, SOLANA};
+
+fn pool(total_deposit: f64) -> Pool {
+    Pool {
+        is_initialized: true,
+        last_update_timestamp: 0,
+        total_deposit,
+        user_count: 3,
+        name: SOLANA,
+        average_prediction: 30.0,
+        invested: 0.0,
+        version: 1,
+        pool_data: Pubkey::default(),
+        share_mint: Pubkey::default(),
+        share_rate: 0.0,
+        pending_prize: 0.0,
+        pending_withdrawals: 0.0,
+        lock_boost: 0.0,
+        won_game_id: 0,
+    }
+}
+
+fn user(pool: u8, balance: f64) -> User {
+    User {
+        pool,
+        last_prediction: 30.0,
+        balance,
+        last_active: 0,
+        game_history_count: 0,
+        current_average_balance: balance,
+        current_weighted_balance: balance * 5.0,
+        current_weighted_days: 5,
+        txn_count: 0,
+        last_paid_game_id: 0,
+        version: 1,
+        participant_slot: 0,
+        mint: Pubkey::default(),
+        share_rate: 0.0,
+        shares: 0,
+        delegated_balance: 0.0,
+        pending_withdrawal: 0.0,
+        withdrawal_pool: 0,
+        withdrawal_release_time: 0,
+        locked_amount: 0.0,
+        lock_multiplier: 0.0,
+        lock_release_time: 0,
+        auto_compound: false,
+        settled_weight: 0.0,
+        settled_game_id: 0,
+    }
+}
+
+fn game(winning_pool: u8) -> Game {
+    Game {
+        game_id: 1,
+        start_time: 0,
+        end_time: 0,
+        winning_pool,
+        winning_amount: 0.0,
+        total_prize: 1_000.0,
+        total_paid: 0.0,
+        sponsored_prize: 0.0,
+        sponsor_count: 0,
+        version: 1,
+        compounded_prize: 0.0,
+        winning_weight: 0.0,
+        harvested_mints: 0,
+        prize_mint: Pubkey::default(),
+    }
+}
+
+#[test]
+fn user_wallet_is_derived_from_the_user_account() {
+    let wallet = Pubkey::new_unique();
+    let (user_wallet, _) =
+        Pubkey::find_program_address(&[b"user_wallet", pda::user(&wallet).as_ref()], &coin_war::ID);
+    assert_eq!(pda::user_wallet(&wallet), user_wallet);
+    assert_ne!(pda::game(1), pda::game(2));
+}
+
+#[test]
+fn batch_payout_passes_a_pair_per_wallet() {
+    let owner = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let wallets = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let custody = [(wallets[0], Custody::UserWallet), (wallets[1], Custody::AssociatedTokenAccount)];
+    let ix = instruction::pay_winners_batch(&owner, &mint, &anchor_spl::token::ID, &custody, SOLANA, 1);
+
+    let remaining = &ix.accounts[ix.accounts.len() - 4..];
+    assert_eq!(remaining[0].pubkey, pda::user(&wallets[0]));
+    assert_eq!(remaining[1].pubkey, pda::user_wallet(&wallets[0]));
+    let associated_token_account =
+        get_associated_token_address_with_program_id(&wallets[1], &mint, &anchor_spl::token::ID);
+    assert_eq!(remaining[3].pubkey, associated_token_account);
+    assert!(remaining.iter().all(|meta| meta.is_writable && !meta.is_signer));
+
+    let data = coin_war::instruction::PayWinnersBatch { game_id: 1, user_keys: wallets.to_vec(), pool_name: SOLANA };
+    assert_eq!(ix.data, data.data());
+}
+
+#[test]
+fn accounts_round_trip() {
+    let mut data = Vec::new();
+    user(SOLANA, 300.0).try_serialize(&mut data).unwrap();
+    let decoded = accounts::user(&data).unwrap();
+    assert_eq!(decoded.balance, 300.0);
+    // the discriminator is checked
+    assert!(accounts::pool(&data).is_err());
+}
+
+#[test]
+fn expected_payout_matches_the_program() {
+    let pool = pool(1_000.0);
+    assert_eq!(payout::expected_payout(&user(SOLANA, 300.0), &pool, &game(SOLANA)), 300);
+
+    // not settled, not the winning pool, or not in the pool
+    assert_eq!(payout::expected_payout(&user(SOLANA, 300.0), &pool, &game(0)), 0);
+    assert_eq!(payout::expected_payout(&user(SOLANA, 300.0), &pool, &game(2)), 0);
+    assert_eq!(payout::expected_payout(&user(2, 300.0), &pool, &game(SOLANA)), 0);
+
+    // already paid
+    let mut paid = user(SOLANA, 300.0);
+    paid.last_paid_game_id = 1;
+    assert_eq!(payout::expected_payout(&paid, &pool, &game(SOLANA)), 0);
+}
+
+#[test]
+fn treasury_withdrawal_uses_the_pool_wallet_of_the_mint() {
+    let admin = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let ix = instruction::withdraw_treasury(&admin, &mint, &anchor_spl::token_2022::ID, &Pubkey::new_unique(), 50);
+    assert_eq!(ix.accounts[1].pubkey, pda::config());
+    assert_eq!(ix.accounts[2].pubkey, pda::pool_wallet(&mint));
+    assert_eq!(ix.accounts[4].pubkey, anchor_spl::token_2022::ID);
+    assert_ne!(pda::pool_wallet(&mint), pda::pool_wallet(&Pubkey::new_unique()));
+}
+
+#[test]
+fn sol_withdrawals_unwrap_through_the_user_unwrap_account() {
+    let wallet = Pubkey::new_unique();
+    let native_mint = anchor_spl::token::spl_token::native_mint::ID;
+    let ix = instruction::withdraw_sol(&wallet, SOLANA, &Pubkey::new_unique(), &Pubkey::new_unique(), 10.0, 3);
+    assert_eq!(ix.accounts[5].pubkey, pda::pool_wallet(&native_mint));
+    assert_eq!(ix.accounts[8].pubkey, pda::unwrap_account(&wallet));
+    assert_eq!(ix.accounts[9].pubkey, pda::tx(&wallet, SOLANA, 3));
+}
+
+#[test]
+fn users_without_a_user_wallet_use_their_associated_token_account() {
+    let wallet = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let token_program = anchor_spl::token_2022::ID;
+    let associated_token_account = get_associated_token_address_with_program_id(&wallet, &mint, &token_program);
+    let user_token_account = pda::user_token_account(&wallet, &mint, &token_program, Custody::AssociatedTokenAccount);
+    assert_eq!(user_token_account, associated_token_account);
+    assert_eq!(pda::user_token_account(&wallet, &mint, &token_program, Custody::UserWallet), pda::user_wallet(&wallet));
+
+    let custody = Custody::AssociatedTokenAccount;
+    let ix = instruction::deposit(&wallet, &mint, &token_program, custody, SOLANA, &mint, &mint, 5.0, 30.0);
+    assert_eq!(ix.accounts[3].pubkey, associated_token_account);
+    assert!(ix.accounts[0].is_signer);
+
+    let ix = instruction::create_user_without_wallet(&wallet);
+    assert_eq!(
+        ix.accounts.iter().map(|meta| meta.pubkey).collect::<Vec<_>>(),
+        [wallet, pda::user(&wallet), anchor_lang::system_program::ID]
+    );
+}
+
+#[test]
+fn shares_are_held_in_the_associated_token_account_of_the_share_mint() {
+    let wallet = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let share_mint = Pubkey::new_unique();
+    let user_share_account = get_associated_token_address_with_program_id(&wallet, &share_mint, &anchor_spl::token::ID);
+    assert_eq!(pda::user_share_account(&wallet, &share_mint), user_share_account);
+
+    // the shares are in SPL Token whatever program owns the deposited mint
+    let token_program = anchor_spl::token_2022::ID;
+    let custody = Custody::UserWallet;
+    let ix = instruction::deposit(&wallet, &mint, &token_program, custody, SOLANA, &mint, &share_mint, 5.0, 30.0);
+    assert_eq!(ix.accounts[7].pubkey, share_mint);
+    assert_eq!(ix.accounts[8].pubkey, user_share_account);
+    assert_eq!(ix.accounts[9].pubkey, token_program);
+    assert_eq!(ix.accounts[10].pubkey, anchor_spl::token::ID);
+}
+
+#[test]
+fn expected_payout_counts_the_distributed_prize() {
+    // 500 distributed to a pool of 1000 raises the rate to 1.5, the user's 300 is worth 450 of the 1500
+    let mut pool = pool(1_000.0);
+    pool.share_rate = 1.5;
+    pool.pending_prize = 500.0;
+    assert_eq!(payout::expected_payout(&user(SOLANA, 300.0), &pool, &game(SOLANA)), 300);
+
+    // caught up with the rate, nothing changes
+    let mut caught_up = user(SOLANA, 450.0);
+    caught_up.share_rate = 1.5;
+    pool.total_deposit = 1_150.0;
+    pool.pending_prize = 350.0;
+    assert_eq!(payout::expected_payout(&caught_up, &pool, &game(SOLANA)), 300);
+}
+
+#[test]
+fn expected_payout_counts_the_lock_boost() {
+    // 250 of the user's 300 locked at twice the weight, 550 of the pool's 1250
+    let mut pool = pool(1_000.0);
+    pool.lock_boost = 250.0;
+    let mut locked = user(SOLANA, 300.0);
+    locked.locked_amount = 250.0;
+    locked.lock_multiplier = 2.0;
+    assert_eq!(payout::expected_payout(&locked, &pool, &game(SOLANA)), 440);
+    assert_eq!(payout::expected_payout(&user(SOLANA, 500.0), &pool, &game(SOLANA)), 400);
+}
+
+#[test]
+fn expected_payout_leaves_out_compounded_prizes() {
+    // 300 of the prize compounded into a pool of 1000, the 200 paid next still gets its share of the 1000
+    let pool = pool(1_300.0);
+    let mut game = game(SOLANA);
+    game.total_paid = 300.0;
+    game.compounded_prize = 300.0;
+    assert_eq!(payout::expected_payout(&user(SOLANA, 200.0), &pool, &game), 200);
+
+    let wallet = Pubkey::new_unique();
+    let ix = instruction::set_payout_preference(&wallet, true);
+    assert_eq!(ix.accounts[1].pubkey, pda::user(&wallet));
+    assert!(ix.accounts[0].is_signer && ix.accounts[1].is_writable);
+    assert_eq!(ix.data, coin_war::instruction::SetPayoutPreference { auto_compound: true }.data());
+}
+
+#[test]
+fn delegates_act_on_the_beneficiarys_position_from_their_own_accounts() {
+    let delegate = Pubkey::new_unique();
+    let beneficiary = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let share_mint = Pubkey::new_unique();
+    let token_program = anchor_spl::token::ID;
+    let delegation = pda::delegation(&beneficiary, &delegate);
+    assert_ne!(delegation, pda::delegation(&delegate, &beneficiary));
+
+    let ix = instruction::deposit_for(
+        &delegate,
+        &beneficiary,
+        &mint,
+        &token_program,
+        SOLANA,
+        &Pubkey::new_unique(),
+        &share_mint,
+        5.0,
+        30.0,
+    );
+    assert!(ix.accounts[0].is_signer);
+    assert_eq!(ix.accounts[2].pubkey, pda::user(&beneficiary));
+    assert_eq!(ix.accounts[3].pubkey, delegation);
+    let delegate_token_account = get_associated_token_address_with_program_id(&delegate, &mint, &token_program);
+    assert_eq!(ix.accounts[4].pubkey, delegate_token_account);
+    assert_eq!(ix.accounts[9].pubkey, pda::user_share_account(&delegate, &share_mint));
+
+    let destination = Pubkey::new_unique();
+    let ix = instruction::withdraw_to(
+        &delegate,
+        &beneficiary,
+        &beneficiary,
+        &destination,
+        &mint,
+        &token_program,
+        SOLANA,
+        &Pubkey::new_unique(),
+        &share_mint,
+        5.0,
+        2,
+    );
+    assert_eq!(ix.accounts[5].pubkey, destination);
+    assert_eq!(ix.accounts[11].pubkey, pda::tx(&beneficiary, SOLANA, 2));
+}
+
+#[test]
+fn gift_deposits_are_paid_by_the_donor_and_credited_to_the_beneficiary() {
+    let donor = Pubkey::new_unique();
+    let beneficiary = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let share_mint = Pubkey::new_unique();
+    let token_program = anchor_spl::token::ID;
+    let ix = instruction::deposit_for_user(
+        &donor,
+        &beneficiary,
+        &mint,
+        &token_program,
+        SOLANA,
+        &Pubkey::new_unique(),
+        &share_mint,
+        5.0,
+        30.0,
+        0,
+    );
+    assert!(ix.accounts[0].is_signer && ix.accounts[0].is_writable);
+    assert_eq!(ix.accounts[2].pubkey, pda::user(&beneficiary));
+    assert_eq!(ix.accounts[3].pubkey, get_associated_token_address_with_program_id(&donor, &mint, &token_program));
+    assert_eq!(ix.accounts[8].pubkey, pda::user_share_account(&beneficiary, &share_mint));
+    assert_eq!(ix.accounts[9].pubkey, pda::tx(&beneficiary, SOLANA, 0));
+}
+
+#[test]
+fn withdrawal_requests_name_the_game_they_wait_for() {
+    let wallet = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let share_mint = Pubkey::new_unique();
+    let ix = instruction::request_withdrawal(&wallet, &mint, 3, SOLANA, &Pubkey::new_unique(), &share_mint, 5.0);
+    assert_eq!(ix.accounts[3].pubkey, pda::game(3));
+    assert_eq!(ix.accounts[7].pubkey, pda::user_share_account(&wallet, &share_mint));
+
+    let token_program = anchor_spl::token::ID;
+    let ix = instruction::complete_withdrawal(&wallet, &mint, &token_program, Custody::UserWallet, SOLANA, 4);
+    assert!(ix.accounts[0].is_signer && ix.accounts[0].is_writable);
+    assert_eq!(ix.accounts[5].pubkey, pda::pool_wallet(&mint));
+    assert_eq!(ix.accounts[6].pubkey, pda::tx(&wallet, SOLANA, 4));
+}
@@ -0,0 +1,65 @@
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::Result;
+use coin_war_client::{accounts, pda, Pool, User, ALL_POOLS};
+use solana_client::rpc_client::RpcClient;
+
+use crate::store::{Position, Store};
+
+// A field where the state rebuilt from events differs from the account on chain. Users are identified by wallet,
+// pools by the address of the Pool account.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Discrepancy {
+    pub account: String,
+    pub field: String,
+    pub indexed: f64,
+    pub on_chain: f64,
+}
+
+fn compare(discrepancies: &mut Vec<Discrepancy>, account: &str, field: &str, indexed: f64, on_chain: f64) {
+    if indexed != on_chain {
+        discrepancies.push(Discrepancy { account: account.to_string(), field: field.to_string(), indexed, on_chain });
+    }
+}
+
+pub fn check_user(position: &Position, user: &User) -> Vec<Discrepancy> {
+    let mut discrepancies = Vec::new();
+    compare(&mut discrepancies, &position.wallet, "balance", position.balance, user.balance);
+    // a user that withdrew everything keeps its last pool on chain
+    if position.balance > 0.0 {
+        compare(&mut discrepancies, &position.wallet, "pool", position.pool as f64, user.pool as f64);
+    }
+    discrepancies
+}
+
+// positions holds every indexed wallet, the ones in other pools are skipped
+pub fn check_pool(positions: &[Position], pool: &Pool) -> Vec<Discrepancy> {
+    let members: Vec<&Position> =
+        positions.iter().filter(|position| position.pool == pool.name && position.balance > 0.0).collect();
+    let total_deposit: f64 = members.iter().map(|position| position.balance).sum();
+
+    let account = pda::pool(pool.name).to_string();
+    let mut discrepancies = Vec::new();
+    compare(&mut discrepancies, &account, "total_deposit", total_deposit, pool.total_deposit);
+    compare(&mut discrepancies, &account, "user_count", members.len() as f64, pool.user_count as f64);
+    discrepancies
+}
+
+// Compare every indexed position and all four pools with their accounts
+pub fn run(rpc: &RpcClient, store: &Store) -> Result<Vec<Discrepancy>> {
+    let positions = store.positions()?;
+    let mut discrepancies = Vec::new();
+
+    for position in &positions {
+        let wallet = Pubkey::from_str(&position.wallet)?;
+        let user = accounts::user(&rpc.get_account_data(&pda::user(&wallet))?)?;
+        discrepancies.extend(check_user(position, &user));
+    }
+    for pool_name in ALL_POOLS {
+        let pool = accounts::pool(&rpc.get_account_data(&pda::pool(pool_name))?)?;
+        discrepancies.extend(check_pool(&positions, &pool));
+    }
+
+    Ok(discrepancies)
+}
@@ -0,0 +1,73 @@
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use coin_war::{
+    DepositEvent, DepositLockedEvent, DepositUnlockedEvent, GameCancelledEvent, GameSettledEvent, GameStartedEvent,
+    GiftEvent, PredictionEvent, PrizeAccruedEvent, PrizeCompoundedEvent, PrizeDistributedEvent, PrizePaidEvent,
+    WithdrawEvent, WithdrawalCancelledEvent, WithdrawalCompletedEvent, WithdrawalRequestedEvent,
+};
+
+// Anchor logs every emitted event as base64 of the event discriminator followed by the borsh encoded event
+const PROGRAM_DATA: &str = "Program data: ";
+
+pub enum Event {
+    Deposit(DepositEvent),
+    Withdraw(WithdrawEvent),
+    Prediction(PredictionEvent),
+    GameStarted(GameStartedEvent),
+    GameSettled(GameSettledEvent),
+    GameCancelled(GameCancelledEvent),
+    PrizePaid(PrizePaidEvent),
+    PrizeCompounded(PrizeCompoundedEvent),
+    PrizeDistributed(PrizeDistributedEvent),
+    PrizeAccrued(PrizeAccruedEvent),
+    Gift(GiftEvent),
+    WithdrawalRequested(WithdrawalRequestedEvent),
+    WithdrawalCancelled(WithdrawalCancelledEvent),
+    WithdrawalCompleted(WithdrawalCompletedEvent),
+    DepositLocked(DepositLockedEvent),
+    DepositUnlocked(DepositUnlockedEvent),
+}
+
+fn decode<T: AnchorDeserialize + Discriminator>(discriminator: &[u8], mut data: &[u8]) -> Option<T> {
+    if discriminator != T::discriminator() {
+        return None;
+    }
+    T::deserialize(&mut data).ok()
+}
+
+// Decode a single log line. Anything that isn't a coin-war event, e.g. program log messages or events of other
+// programs invoked in the same transaction, is skipped.
+pub fn parse_log(log: &str) -> Option<Event> {
+    let encoded = log.trim().strip_prefix(PROGRAM_DATA)?;
+    let bytes = base64::decode(encoded).ok()?;
+    if bytes.len() < 8 {
+        return None;
+    }
+    let (discriminator, data) = bytes.split_at(8);
+
+    decode(discriminator, data)
+        .map(Event::Deposit)
+        .or_else(|| decode(discriminator, data).map(Event::Withdraw))
+        .or_else(|| decode(discriminator, data).map(Event::Prediction))
+        .or_else(|| decode(discriminator, data).map(Event::GameStarted))
+        .or_else(|| decode(discriminator, data).map(Event::GameSettled))
+        .or_else(|| decode(discriminator, data).map(Event::GameCancelled))
+        .or_else(|| decode(discriminator, data).map(Event::PrizePaid))
+        .or_else(|| decode(discriminator, data).map(Event::PrizeCompounded))
+        .or_else(|| decode(discriminator, data).map(Event::PrizeDistributed))
+        .or_else(|| decode(discriminator, data).map(Event::PrizeAccrued))
+        .or_else(|| decode(discriminator, data).map(Event::Gift))
+        .or_else(|| decode(discriminator, data).map(Event::WithdrawalRequested))
+        .or_else(|| decode(discriminator, data).map(Event::WithdrawalCancelled))
+        .or_else(|| decode(discriminator, data).map(Event::WithdrawalCompleted))
+        .or_else(|| decode(discriminator, data).map(Event::DepositLocked))
+        .or_else(|| decode(discriminator, data).map(Event::DepositUnlocked))
+}
+
+pub fn parse_logs(logs: &[String]) -> Vec<Event> {
+    logs.iter().filter_map(|log| parse_log(log)).collect()
+}
+
+// The log line the program writes for event, e.g. to record fixtures
+pub fn to_log(event: &impl anchor_lang::Event) -> String {
+    format!("{}{}", PROGRAM_DATA, base64::encode(event.data()))
+}
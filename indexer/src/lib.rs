@@ -0,0 +1,17 @@
+/* Indexer for the coin-war program. Events emitted by the program are read from a validator RPC or a recorded log
+ * file and replayed into SQLite, giving per-user deposits, withdrawals, predictions and prizes per game without
+ * scanning every Transaction PDA. The rebuilt positions can be checked against the on-chain User and Pool accounts
+ * to find events the indexer missed.
+ */
+
+pub mod check;
+pub mod events;
+pub mod source;
+pub mod store;
+
+// Logs of one successful coin-war transaction
+pub struct LoggedTransaction {
+    pub signature: String,
+    pub slot: u64,
+    pub logs: Vec<String>,
+}
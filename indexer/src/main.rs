@@ -0,0 +1,89 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use coin_war_indexer::store::Store;
+use coin_war_indexer::{check, source, LoggedTransaction};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+
+#[derive(Parser)]
+#[clap(name = "coin-war-indexer", about = "Rebuild coin-war game history into SQLite")]
+struct Cli {
+    /// SQLite database, created if missing
+    #[clap(long, default_value = "coin-war.sqlite")]
+    db: PathBuf,
+    /// RPC url of the cluster
+    #[clap(long, default_value = "http://127.0.0.1:8899")]
+    url: String,
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Index the program transactions since the last sync
+    Sync {
+        /// Keep polling for new transactions every this many seconds
+        #[clap(long)]
+        poll: Option<u64>,
+    },
+    /// Index a log file recorded with `solana logs`
+    Import { file: PathBuf },
+    /// Compare the indexed positions with the User and Pool accounts
+    Check,
+}
+
+fn index(store: &mut Store, transactions: &[LoggedTransaction]) -> Result<()> {
+    let mut indexed = 0;
+    for transaction in transactions {
+        if store.apply(transaction)? {
+            indexed += 1;
+        }
+    }
+    println!("indexed {} of {} transactions", indexed, transactions.len());
+    Ok(())
+}
+
+fn sync(rpc: &RpcClient, store: &mut Store) -> Result<()> {
+    let until = store.last_signature()?;
+    let transactions = source::fetch_transactions(rpc, until.as_deref())?;
+    index(store, &transactions)?;
+    if let Some(newest) = transactions.last() {
+        store.set_last_signature(&newest.signature)?;
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let mut store = Store::open(&cli.db)?;
+    let rpc = RpcClient::new_with_commitment(cli.url, CommitmentConfig::confirmed());
+
+    match cli.command {
+        Command::Sync { poll: None } => sync(&rpc, &mut store),
+        Command::Sync { poll: Some(seconds) } => loop {
+            sync(&rpc, &mut store)?;
+            thread::sleep(Duration::from_secs(seconds));
+        },
+        Command::Import { file } => {
+            let transactions = source::read_log_file(BufReader::new(File::open(file)?))?;
+            index(&mut store, &transactions)
+        }
+        Command::Check => {
+            let discrepancies = check::run(&rpc, &store)?;
+            for discrepancy in &discrepancies {
+                println!(
+                    "{} {}: indexed {}, on chain {}",
+                    discrepancy.account, discrepancy.field, discrepancy.indexed, discrepancy.on_chain
+                );
+            }
+            println!("{} discrepancies", discrepancies.len());
+            store.record_discrepancies(&discrepancies)
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::io::BufRead;
+use std::str::FromStr;
+
+use anyhow::Result;
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_sdk::signature::Signature;
+use solana_transaction_status::UiTransactionEncoding;
+
+use crate::LoggedTransaction;
+
+/* Where transactions come from: the RPC of a validator, or a file recorded with `solana logs`, which prints
+ *
+ *   Transaction executed in slot 1234:
+ *     Signature: 5j7s...
+ *     Status: Ok
+ *     Log Messages:
+ *       Program data: ...
+ *
+ * for every transaction. Failed transactions are dropped since their events never took effect.
+ */
+
+pub fn read_log_file(reader: impl BufRead) -> Result<Vec<LoggedTransaction>> {
+    let mut transactions = Vec::new();
+    let mut current: Option<LoggedTransaction> = None;
+    let mut slot = 0;
+
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if let Some(executed_in) = line.strip_prefix("Transaction executed in slot ") {
+            transactions.extend(current.take());
+            slot = executed_in.trim_end_matches(':').parse()?;
+        } else if let Some(signature) = line.strip_prefix("Signature: ") {
+            transactions.extend(current.take());
+            current = Some(LoggedTransaction { signature: signature.to_string(), slot, logs: Vec::new() });
+        } else if let Some(status) = line.strip_prefix("Status: ") {
+            if status != "Ok" {
+                current = None;
+            }
+        } else if let Some(transaction) = current.as_mut() {
+            transaction.logs.push(line.to_string());
+        }
+    }
+    transactions.extend(current);
+
+    Ok(transactions)
+}
+
+// Successful coin-war transactions newer than until, oldest first
+pub fn fetch_transactions(rpc: &RpcClient, until: Option<&str>) -> Result<Vec<LoggedTransaction>> {
+    let until = until.map(Signature::from_str).transpose()?;
+
+    // Signatures come newest first, a page at a time
+    let mut statuses = Vec::new();
+    let mut before = None;
+    loop {
+        let config = GetConfirmedSignaturesForAddress2Config { before, until, limit: None, commitment: None };
+        let page = rpc.get_signatures_for_address_with_config(&coin_war::ID, config)?;
+        match page.last() {
+            Some(oldest) => before = Some(Signature::from_str(&oldest.signature)?),
+            None => break,
+        }
+        statuses.extend(page);
+    }
+
+    let mut transactions = Vec::new();
+    for status in statuses.into_iter().rev() {
+        if status.err.is_some() {
+            continue;
+        }
+        let signature = Signature::from_str(&status.signature)?;
+        let transaction = rpc.get_transaction(&signature, UiTransactionEncoding::Json)?;
+        let logs = transaction.transaction.meta.and_then(|meta| meta.log_messages.into()).unwrap_or_default();
+        transactions.push(LoggedTransaction { signature: status.signature, slot: transaction.slot, logs });
+    }
+
+    Ok(transactions)
+}
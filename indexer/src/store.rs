@@ -0,0 +1,450 @@
+use std::path::Path;
+
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::check::Discrepancy;
+use crate::events::{self, Event};
+use crate::LoggedTransaction;
+
+/* SQLite store of everything the indexer has seen. Event tables are append only and keyed by (signature,
+ * event_index), so replaying the same transaction twice is a no-op. positions holds the balance of every wallet
+ * rebuilt from the deposit, withdrawal, prize accrual and compounded prize amounts, which check compares to the User
+ * accounts.
+ */
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS transactions (
+    signature TEXT PRIMARY KEY,
+    slot INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS deposits (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    wallet TEXT NOT NULL,
+    pool INTEGER NOT NULL,
+    amount REAL NOT NULL,
+    prediction REAL NOT NULL,
+    balance REAL NOT NULL,
+    timestamp INTEGER NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE TABLE IF NOT EXISTS withdrawals (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    wallet TEXT NOT NULL,
+    pool INTEGER NOT NULL,
+    amount REAL NOT NULL,
+    balance REAL NOT NULL,
+    timestamp INTEGER NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE TABLE IF NOT EXISTS predictions (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    wallet TEXT NOT NULL,
+    pool INTEGER NOT NULL,
+    prediction REAL NOT NULL,
+    timestamp INTEGER NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE TABLE IF NOT EXISTS prizes (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    game_id INTEGER NOT NULL,
+    wallet TEXT NOT NULL,
+    pool INTEGER NOT NULL,
+    amount INTEGER NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE TABLE IF NOT EXISTS distributions (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    game_id INTEGER NOT NULL,
+    pool INTEGER NOT NULL,
+    amount REAL NOT NULL,
+    exchange_rate REAL NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE TABLE IF NOT EXISTS accruals (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    wallet TEXT NOT NULL,
+    pool INTEGER NOT NULL,
+    amount REAL NOT NULL,
+    balance REAL NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE TABLE IF NOT EXISTS gifts (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    donor TEXT NOT NULL,
+    wallet TEXT NOT NULL,
+    pool INTEGER NOT NULL,
+    amount REAL NOT NULL,
+    timestamp INTEGER NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+-- kind is requested, cancelled or completed
+CREATE TABLE IF NOT EXISTS withdrawal_requests (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    wallet TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    pool INTEGER NOT NULL,
+    amount REAL NOT NULL,
+    timestamp INTEGER NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+-- unlocks have no games, multiplier or release_time
+CREATE TABLE IF NOT EXISTS locks (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    wallet TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    pool INTEGER NOT NULL,
+    amount REAL NOT NULL,
+    games INTEGER,
+    multiplier REAL,
+    release_time INTEGER,
+    timestamp INTEGER NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE TABLE IF NOT EXISTS games (
+    game_id INTEGER PRIMARY KEY,
+    start_time INTEGER NOT NULL,
+    end_time INTEGER NOT NULL,
+    winning_pool INTEGER NOT NULL DEFAULT 0,
+    total_prize REAL NOT NULL DEFAULT 0,
+    cancelled INTEGER NOT NULL DEFAULT 0
+);
+CREATE TABLE IF NOT EXISTS positions (
+    wallet TEXT PRIMARY KEY,
+    pool INTEGER NOT NULL,
+    balance REAL NOT NULL,
+    prediction REAL NOT NULL
+);
+CREATE TABLE IF NOT EXISTS discrepancies (
+    account TEXT NOT NULL,
+    field TEXT NOT NULL,
+    indexed REAL NOT NULL,
+    on_chain REAL NOT NULL,
+    PRIMARY KEY (account, field)
+);
+CREATE TABLE IF NOT EXISTS sync_state (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+
+-- Everything a wallet did, by the game it happened in. Activity is matched to games by time, prizes by game id.
+CREATE VIEW IF NOT EXISTS user_game_activity AS
+SELECT games.game_id, activity.wallet, activity.kind, activity.pool, activity.amount, activity.prediction,
+    activity.timestamp
+FROM games JOIN (
+    SELECT 'deposit' AS kind, wallet, pool, amount, prediction, timestamp FROM deposits
+    UNION ALL SELECT 'withdrawal', wallet, pool, amount, NULL, timestamp FROM withdrawals
+    UNION ALL SELECT 'prediction', wallet, pool, NULL, prediction, timestamp FROM predictions
+) AS activity ON activity.timestamp >= games.start_time AND activity.timestamp < games.end_time
+WHERE games.cancelled = 0
+UNION ALL
+SELECT game_id, wallet, 'prize', pool, amount, NULL, NULL FROM prizes;
+";
+
+const LAST_SIGNATURE: &str = "last_signature";
+
+// Balance of a wallet rebuilt from its events
+#[derive(Clone, Debug, PartialEq)]
+pub struct Position {
+    pub wallet: String,
+    // 0 once the wallet has withdrawn everything
+    pub pool: u8,
+    pub balance: f64,
+    pub prediction: f64,
+}
+
+pub struct Store {
+    connection: Connection,
+}
+
+impl Store {
+    pub fn open(path: impl AsRef<Path>) -> Result<Store> {
+        Store::with_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Store> {
+        Store::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Store> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Store { connection })
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    // Newest transaction fetched from the RPC, where the next sync continues from
+    pub fn last_signature(&self) -> Result<Option<String>> {
+        let value = self
+            .connection
+            .query_row("SELECT value FROM sync_state WHERE key = ?1", params![LAST_SIGNATURE], |row| row.get(0))
+            .optional()?;
+        Ok(value)
+    }
+
+    pub fn set_last_signature(&self, signature: &str) -> Result<()> {
+        self.connection.execute(
+            "INSERT INTO sync_state (key, value) VALUES (?1, ?2) ON CONFLICT (key) DO UPDATE SET value = ?2",
+            params![LAST_SIGNATURE, signature],
+        )?;
+        Ok(())
+    }
+
+    // Record the events of transaction. Returns false if it was already indexed.
+    pub fn apply(&mut self, transaction: &LoggedTransaction) -> Result<bool> {
+        let db = self.connection.transaction()?;
+        let inserted = db.execute(
+            "INSERT OR IGNORE INTO transactions (signature, slot) VALUES (?1, ?2)",
+            params![transaction.signature, transaction.slot],
+        )?;
+        if inserted == 0 {
+            return Ok(false);
+        }
+
+        let signature = &transaction.signature;
+        for (index, event) in events::parse_logs(&transaction.logs).iter().enumerate() {
+            match event {
+                Event::Deposit(deposit) => {
+                    let wallet = deposit.user.to_string();
+                    db.execute(
+                        "INSERT INTO deposits VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                        params![
+                            signature,
+                            index,
+                            wallet,
+                            deposit.pool,
+                            deposit.amount,
+                            deposit.prediction,
+                            deposit.balance,
+                            deposit.timestamp
+                        ],
+                    )?;
+                    db.execute(
+                        "INSERT INTO positions (wallet, pool, balance, prediction) VALUES (?1, ?2, ?3, ?4)
+                         ON CONFLICT (wallet) DO UPDATE SET pool = ?2, balance = balance + ?3, prediction = ?4",
+                        params![wallet, deposit.pool, deposit.amount, deposit.prediction],
+                    )?;
+                }
+                Event::Withdraw(withdraw) => {
+                    let wallet = withdraw.user.to_string();
+                    db.execute(
+                        "INSERT INTO withdrawals VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                        params![
+                            signature,
+                            index,
+                            wallet,
+                            withdraw.pool,
+                            withdraw.amount,
+                            withdraw.balance,
+                            withdraw.timestamp
+                        ],
+                    )?;
+                    // the program drops the prediction on withdrawal and the pool once the balance is gone
+                    db.execute(
+                        "INSERT INTO positions (wallet, pool, balance, prediction) VALUES (?1, ?2, -?3, 0)
+                         ON CONFLICT (wallet) DO UPDATE SET balance = balance - ?3, prediction = 0",
+                        params![wallet, withdraw.pool, withdraw.amount],
+                    )?;
+                    db.execute("UPDATE positions SET pool = 0 WHERE wallet = ?1 AND balance <= 0", params![wallet])?;
+                }
+                Event::Prediction(prediction) => {
+                    db.execute(
+                        "INSERT INTO predictions VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                        params![
+                            signature,
+                            index,
+                            prediction.user.to_string(),
+                            prediction.pool,
+                            prediction.prediction,
+                            prediction.timestamp
+                        ],
+                    )?;
+                    db.execute(
+                        "UPDATE positions SET prediction = ?2 WHERE wallet = ?1",
+                        params![prediction.user.to_string(), prediction.prediction],
+                    )?;
+                }
+                Event::GameStarted(game) => {
+                    // a cancelled game id can be started again
+                    db.execute(
+                        "INSERT OR REPLACE INTO games (game_id, start_time, end_time) VALUES (?1, ?2, ?3)",
+                        params![game.game_id, game.start_time, game.end_time],
+                    )?;
+                }
+                Event::GameSettled(game) => {
+                    db.execute(
+                        "UPDATE games SET winning_pool = ?2, total_prize = ?3 WHERE game_id = ?1",
+                        params![game.game_id, game.winning_pool, game.total_prize],
+                    )?;
+                }
+                Event::GameCancelled(game) => {
+                    db.execute("UPDATE games SET cancelled = 1 WHERE game_id = ?1", params![game.game_id])?;
+                }
+                Event::PrizePaid(prize) => {
+                    db.execute(
+                        "INSERT INTO prizes VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                        params![signature, index, prize.game_id, prize.user.to_string(), prize.pool, prize.amount],
+                    )?;
+                }
+                // a compounded prize is recorded like a paid one but stays in the position
+                Event::PrizeCompounded(prize) => {
+                    let wallet = prize.user.to_string();
+                    db.execute(
+                        "INSERT INTO prizes VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                        params![signature, index, prize.game_id, wallet, prize.pool, prize.amount as u64],
+                    )?;
+                    db.execute(
+                        "UPDATE positions SET balance = balance + ?2 WHERE wallet = ?1",
+                        params![wallet, prize.amount],
+                    )?;
+                }
+                Event::PrizeDistributed(distribution) => {
+                    db.execute(
+                        "INSERT INTO distributions VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                        params![
+                            signature,
+                            index,
+                            distribution.game_id,
+                            distribution.pool,
+                            distribution.amount,
+                            distribution.exchange_rate
+                        ],
+                    )?;
+                }
+                Event::PrizeAccrued(accrual) => {
+                    let wallet = accrual.user.to_string();
+                    db.execute(
+                        "INSERT INTO accruals VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                        params![signature, index, wallet, accrual.pool, accrual.amount, accrual.balance],
+                    )?;
+                    // always followed by the deposit or withdrawal it was brought up to date for
+                    db.execute(
+                        "UPDATE positions SET balance = balance + ?2 WHERE wallet = ?1",
+                        params![wallet, accrual.amount],
+                    )?;
+                }
+                // the position is updated by the deposit event emitted with it
+                Event::Gift(gift) => {
+                    db.execute(
+                        "INSERT INTO gifts VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                        params![
+                            signature,
+                            index,
+                            gift.donor.to_string(),
+                            gift.user.to_string(),
+                            gift.pool,
+                            gift.amount,
+                            gift.timestamp
+                        ],
+                    )?;
+                }
+                // a requested withdrawal leaves the position like a withdrawal, the tokens follow on completion
+                Event::WithdrawalRequested(request) => {
+                    let wallet = request.user.to_string();
+                    db.execute(
+                        "INSERT INTO withdrawal_requests VALUES (?1, ?2, ?3, 'requested', ?4, ?5, ?6)",
+                        params![signature, index, wallet, request.pool, request.amount, request.timestamp],
+                    )?;
+                    db.execute(
+                        "UPDATE positions SET balance = balance - ?2, prediction = 0 WHERE wallet = ?1",
+                        params![wallet, request.amount],
+                    )?;
+                    db.execute("UPDATE positions SET pool = 0 WHERE wallet = ?1 AND balance <= 0", params![wallet])?;
+                }
+                Event::WithdrawalCancelled(cancel) => {
+                    let wallet = cancel.user.to_string();
+                    db.execute(
+                        "INSERT INTO withdrawal_requests VALUES (?1, ?2, ?3, 'cancelled', ?4, ?5, ?6)",
+                        params![signature, index, wallet, cancel.pool, cancel.amount, cancel.timestamp],
+                    )?;
+                    db.execute(
+                        "UPDATE positions SET pool = ?2, balance = balance + ?3, prediction = ?4 WHERE wallet = ?1",
+                        params![wallet, cancel.pool, cancel.amount, cancel.prediction],
+                    )?;
+                }
+                Event::WithdrawalCompleted(completion) => {
+                    db.execute(
+                        "INSERT INTO withdrawal_requests VALUES (?1, ?2, ?3, 'completed', ?4, ?5, ?6)",
+                        params![
+                            signature,
+                            index,
+                            completion.user.to_string(),
+                            completion.pool,
+                            completion.amount,
+                            completion.timestamp
+                        ],
+                    )?;
+                }
+                // locks leave the balance as it is
+                Event::DepositLocked(lock) => {
+                    db.execute(
+                        "INSERT INTO locks VALUES (?1, ?2, ?3, 'locked', ?4, ?5, ?6, ?7, ?8, ?9)",
+                        params![
+                            signature,
+                            index,
+                            lock.user.to_string(),
+                            lock.pool,
+                            lock.amount,
+                            lock.games,
+                            lock.multiplier,
+                            lock.release_time,
+                            lock.timestamp
+                        ],
+                    )?;
+                }
+                Event::DepositUnlocked(unlock) => {
+                    db.execute(
+                        "INSERT INTO locks VALUES (?1, ?2, ?3, 'unlocked', ?4, ?5, NULL, NULL, NULL, ?6)",
+                        params![
+                            signature,
+                            index,
+                            unlock.user.to_string(),
+                            unlock.pool,
+                            unlock.amount,
+                            unlock.timestamp
+                        ],
+                    )?;
+                }
+            }
+        }
+        db.commit()?;
+
+        Ok(true)
+    }
+
+    pub fn positions(&self) -> Result<Vec<Position>> {
+        let mut statement =
+            self.connection.prepare("SELECT wallet, pool, balance, prediction FROM positions ORDER BY wallet")?;
+        let positions = statement
+            .query_map([], |row| {
+                Ok(Position { wallet: row.get(0)?, pool: row.get(1)?, balance: row.get(2)?, prediction: row.get(3)? })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(positions)
+    }
+
+    // Replace the discrepancies found by the previous check
+    pub fn record_discrepancies(&mut self, discrepancies: &[Discrepancy]) -> Result<()> {
+        let db = self.connection.transaction()?;
+        db.execute("DELETE FROM discrepancies", [])?;
+        for discrepancy in discrepancies {
+            db.execute(
+                "INSERT INTO discrepancies VALUES (?1, ?2, ?3, ?4)",
+                params![discrepancy.account, discrepancy.field, discrepancy.indexed, discrepancy.on_chain],
+            )?;
+        }
+        db.commit()?;
+        Ok(())
+    }
+}
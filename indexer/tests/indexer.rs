@@ -0,0 +1,271 @@
+use anchor_lang::prelude::Pubkey;
+use coin_war::{
+    DepositEvent, DepositLockedEvent, DepositUnlockedEvent, GameSettledEvent, GameStartedEvent, GiftEvent,
+    PrizeAccruedEvent, PrizeCompoundedEvent, PrizePaidEvent, WithdrawEvent, WithdrawalCancelledEvent,
+    WithdrawalCompletedEvent, WithdrawalRequestedEvent,
+};
+use coin_war_client::{Pool, User, BNB, SOLANA};
+use coin_war_indexer::events::{self, Event};
+use coin_war_indexer::store::{Position, Store};
+use coin_war_indexer::{check, source, LoggedTransaction};
+
+fn deposit(user: Pubkey, pool: u8, amount: f64, balance: f64, timestamp: i64) -> String {
+    events::to_log(&DepositEvent { user, pool, amount, prediction: 30.0, balance, timestamp })
+}
+
+fn withdraw(user: Pubkey, pool: u8, amount: f64, balance: f64, timestamp: i64) -> String {
+    events::to_log(&WithdrawEvent { user, pool, amount, balance, timestamp })
+}
+
+fn transaction(signature: &str, logs: Vec<String>) -> LoggedTransaction {
+    LoggedTransaction { signature: signature.to_string(), slot: 1, logs }
+}
+
+fn position(wallet: Pubkey, pool: u8, balance: f64) -> Position {
+    Position { wallet: wallet.to_string(), pool, balance, prediction: 30.0 }
+}
+
+#[test]
+fn only_coin_war_events_are_parsed() {
+    let user = Pubkey::new_unique();
+    let logs = vec![
+        "Program log: Instruction: Deposit".to_string(),
+        deposit(user, SOLANA, 100.0, 100.0, 10),
+        "Program data: bm90IGFuIGV2ZW50".to_string(),
+    ];
+    let parsed = events::parse_logs(&logs);
+    assert_eq!(parsed.len(), 1);
+    match &parsed[0] {
+        Event::Deposit(event) => assert_eq!((event.user, event.amount), (user, 100.0)),
+        _ => panic!("expected a deposit"),
+    }
+}
+
+#[test]
+fn positions_are_rebuilt_from_events() {
+    let mut store = Store::open_in_memory().unwrap();
+    let alice = Pubkey::new_unique();
+    let bob = Pubkey::new_unique();
+
+    store.apply(&transaction("1", vec![deposit(alice, SOLANA, 300.0, 300.0, 10)])).unwrap();
+    store
+        .apply(&transaction("2", vec![deposit(bob, BNB, 100.0, 100.0, 11), deposit(alice, SOLANA, 50.0, 350.0, 12)]))
+        .unwrap();
+    store.apply(&transaction("3", vec![withdraw(bob, BNB, 100.0, 0.0, 13)])).unwrap();
+
+    // replaying a transaction is a no-op
+    assert!(!store.apply(&transaction("1", vec![deposit(alice, SOLANA, 300.0, 300.0, 10)])).unwrap());
+
+    let positions = store.positions().unwrap();
+    assert!(positions.contains(&position(alice, SOLANA, 350.0)));
+    assert!(positions.contains(&Position { wallet: bob.to_string(), pool: 0, balance: 0.0, prediction: 0.0 }));
+}
+
+#[test]
+fn accrued_prize_is_added_to_positions() {
+    let mut store = Store::open_in_memory().unwrap();
+    let alice = Pubkey::new_unique();
+    store.apply(&transaction("1", vec![deposit(alice, SOLANA, 300.0, 300.0, 10)])).unwrap();
+
+    // alice catches up with a distributed prize of 150 on the next withdrawal
+    let accrued = events::to_log(&PrizeAccruedEvent { user: alice, pool: SOLANA, amount: 150.0, balance: 450.0 });
+    store.apply(&transaction("2", vec![accrued, withdraw(alice, SOLANA, 50.0, 400.0, 20)])).unwrap();
+
+    let expected = Position { wallet: alice.to_string(), pool: SOLANA, balance: 400.0, prediction: 0.0 };
+    assert_eq!(store.positions().unwrap(), vec![expected]);
+}
+
+#[test]
+fn gifts_are_credited_to_the_beneficiary_and_record_the_donor() {
+    let mut store = Store::open_in_memory().unwrap();
+    let donor = Pubkey::new_unique();
+    let alice = Pubkey::new_unique();
+    let gift = events::to_log(&GiftEvent { donor, user: alice, pool: SOLANA, amount: 100.0, timestamp: 10 });
+    store.apply(&transaction("1", vec![deposit(alice, SOLANA, 100.0, 100.0, 10), gift])).unwrap();
+
+    assert_eq!(store.positions().unwrap(), vec![position(alice, SOLANA, 100.0)]);
+    let recorded: (String, String, f64) = store
+        .connection()
+        .query_row("SELECT donor, wallet, amount FROM gifts", [], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .unwrap();
+    assert_eq!(recorded, (donor.to_string(), alice.to_string(), 100.0));
+}
+
+#[test]
+fn requested_withdrawals_leave_the_position_until_cancelled() {
+    let mut store = Store::open_in_memory().unwrap();
+    let alice = Pubkey::new_unique();
+    let requested = |amount, balance| {
+        events::to_log(&WithdrawalRequestedEvent {
+            user: alice,
+            pool: SOLANA,
+            amount,
+            balance,
+            release_time: 100,
+            timestamp: 10,
+        })
+    };
+    let logs = vec![deposit(alice, SOLANA, 300.0, 300.0, 10), requested(100.0, 200.0)];
+    store.apply(&transaction("1", logs)).unwrap();
+    assert_eq!(store.positions().unwrap(), vec![Position { prediction: 0.0, ..position(alice, SOLANA, 200.0) }]);
+
+    let cancelled = events::to_log(&WithdrawalCancelledEvent {
+        user: alice,
+        pool: SOLANA,
+        amount: 100.0,
+        prediction: 30.0,
+        balance: 300.0,
+        timestamp: 20,
+    });
+    let logs = vec![cancelled, requested(300.0, 0.0)];
+    store.apply(&transaction("2", logs)).unwrap();
+    assert_eq!(store.positions().unwrap(), vec![Position { prediction: 0.0, ..position(alice, 0, 0.0) }]);
+
+    let completed = WithdrawalCompletedEvent { user: alice, pool: SOLANA, amount: 300.0, timestamp: 100 };
+    store.apply(&transaction("3", vec![events::to_log(&completed)])).unwrap();
+    assert_eq!(store.positions().unwrap(), vec![Position { prediction: 0.0, ..position(alice, 0, 0.0) }]);
+    let kinds: u32 = store
+        .connection()
+        .query_row("SELECT COUNT(DISTINCT kind) FROM withdrawal_requests", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(kinds, 3);
+}
+
+#[test]
+fn locks_are_recorded_without_changing_the_position() {
+    let mut store = Store::open_in_memory().unwrap();
+    let alice = Pubkey::new_unique();
+    let locked = events::to_log(&DepositLockedEvent {
+        user: alice,
+        pool: SOLANA,
+        amount: 200.0,
+        games: 4,
+        multiplier: 1.5,
+        release_time: 100,
+        timestamp: 10,
+    });
+    let unlocked = events::to_log(&DepositUnlockedEvent { user: alice, pool: SOLANA, amount: 200.0, timestamp: 100 });
+    store.apply(&transaction("1", vec![deposit(alice, SOLANA, 300.0, 300.0, 10), locked, unlocked])).unwrap();
+
+    assert_eq!(store.positions().unwrap(), vec![position(alice, SOLANA, 300.0)]);
+    let multiplier: f64 = store
+        .connection()
+        .query_row("SELECT multiplier FROM locks WHERE kind = 'locked'", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(multiplier, 1.5);
+}
+
+#[test]
+fn compounded_prizes_are_prizes_that_stay_in_the_position() {
+    let mut store = Store::open_in_memory().unwrap();
+    let alice = Pubkey::new_unique();
+    let compounded =
+        events::to_log(&PrizeCompoundedEvent { game_id: 1, user: alice, pool: SOLANA, amount: 40.0, balance: 340.0 });
+    store.apply(&transaction("1", vec![deposit(alice, SOLANA, 300.0, 300.0, 10), compounded])).unwrap();
+
+    assert_eq!(store.positions().unwrap(), vec![position(alice, SOLANA, 340.0)]);
+    let prize: i64 =
+        store.connection().query_row("SELECT amount FROM prizes WHERE game_id = 1", [], |row| row.get(0)).unwrap();
+    assert_eq!(prize, 40);
+}
+
+#[test]
+fn activity_is_grouped_by_game() {
+    let mut store = Store::open_in_memory().unwrap();
+    let alice = Pubkey::new_unique();
+    let logs = vec![
+        events::to_log(&GameStartedEvent { game_id: 1, start_time: 0, end_time: 100 }),
+        deposit(alice, SOLANA, 300.0, 300.0, 10),
+        // after the game ended
+        deposit(alice, SOLANA, 100.0, 400.0, 150),
+        events::to_log(&GameSettledEvent { game_id: 1, winning_pool: SOLANA, total_prize: 40.0 }),
+        events::to_log(&PrizePaidEvent { game_id: 1, user: alice, pool: SOLANA, amount: 40 }),
+    ];
+    store.apply(&transaction("1", logs)).unwrap();
+
+    let mut statement = store
+        .connection()
+        .prepare("SELECT kind, amount FROM user_game_activity WHERE game_id = 1 AND wallet = ?1 ORDER BY kind")
+        .unwrap();
+    let activity: Vec<(String, f64)> = statement
+        .query_map([alice.to_string()], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(activity, vec![("deposit".to_string(), 300.0), ("prize".to_string(), 40.0)]);
+}
+
+#[test]
+fn log_files_skip_failed_transactions() {
+    let user = Pubkey::new_unique();
+    let file = format!(
+        "Transaction executed in slot 5:\n  Signature: ok\n  Status: Ok\n  Log Messages:\n    {}\n\
+         Transaction executed in slot 6:\n  Signature: failed\n  Status: Error processing Instruction 0\n  \
+         Log Messages:\n    {}\n",
+        deposit(user, SOLANA, 100.0, 100.0, 10),
+        deposit(user, SOLANA, 100.0, 200.0, 11),
+    );
+    let transactions = source::read_log_file(file.as_bytes()).unwrap();
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(transactions[0].signature, "ok");
+    assert_eq!(transactions[0].slot, 5);
+    assert_eq!(events::parse_logs(&transactions[0].logs).len(), 1);
+}
+
+#[test]
+fn discrepancies_with_the_accounts_are_flagged() {
+    let alice = Pubkey::new_unique();
+    let bob = Pubkey::new_unique();
+    let positions = vec![position(alice, SOLANA, 300.0), position(bob, SOLANA, 100.0)];
+    let pool = Pool {
+        is_initialized: true,
+        last_update_timestamp: 0,
+        total_deposit: 500.0,
+        user_count: 2,
+        name: SOLANA,
+        average_prediction: 30.0,
+        invested: 0.0,
+        version: 1,
+        pool_data: Pubkey::default(),
+        share_mint: Pubkey::default(),
+        share_rate: 0.0,
+        pending_prize: 0.0,
+        pending_withdrawals: 0.0,
+        lock_boost: 0.0,
+        won_game_id: 0,
+    };
+    let discrepancies = check::check_pool(&positions, &pool);
+    assert_eq!(discrepancies.len(), 1);
+    assert_eq!(discrepancies[0].field, "total_deposit");
+    assert_eq!((discrepancies[0].indexed, discrepancies[0].on_chain), (400.0, 500.0));
+
+    let user = User {
+        pool: SOLANA,
+        last_prediction: 30.0,
+        balance: 300.0,
+        last_active: 0,
+        game_history_count: 0,
+        current_average_balance: 300.0,
+        current_weighted_balance: 1_500.0,
+        current_weighted_days: 5,
+        txn_count: 0,
+        last_paid_game_id: 0,
+        version: 1,
+        participant_slot: 0,
+        mint: Pubkey::default(),
+        share_rate: 0.0,
+        shares: 0,
+        delegated_balance: 0.0,
+        pending_withdrawal: 0.0,
+        withdrawal_pool: 0,
+        withdrawal_release_time: 0,
+        locked_amount: 0.0,
+        lock_multiplier: 0.0,
+        lock_release_time: 0,
+        auto_compound: false,
+        settled_weight: 0.0,
+        settled_game_id: 0,
+    };
+    assert!(check::check_user(&positions[0], &user).is_empty());
+    assert_eq!(check::check_user(&positions[1], &user)[0].field, "balance");
+}
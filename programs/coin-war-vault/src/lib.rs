@@ -0,0 +1,191 @@
+use anchor_lang::prelude::*;
+use coin_war::cpi::accounts::{DepositFor, WithdrawTo};
+
+declare_id!("5s1KbeQXggMFp656AiEo7F9ZpaB8zHmC9SnhhaaCqjTb");
+
+/* Coin-war vault - an example of a program acting on coin-war positions as a delegate. The vault PDA is approved
+ * by users with coin_war::approve_delegate, then deposits for them out of its own token accounts and withdraws
+ * back what it deposited, signing the coin-war CPI with the vault seeds. Here only the admin can move funds; a real
+ * vault would put its own strategy and bookkeeping in front of the CPI.
+ */
+
+#[program]
+pub mod coin_war_vault {
+    use super::*;
+
+    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.admin = ctx.accounts.admin.key();
+        vault.bump = *ctx.bumps.get("vault").unwrap();
+
+        Ok(())
+    }
+
+    // Deposit out of the vault's token account into the position of beneficiary
+    pub fn deposit_for(
+        ctx: Context<VaultDeposit>,
+        beneficiary: Pubkey,
+        amount: f64,
+        pool_name: u8,
+        prediction: f64,
+    ) -> Result<()> {
+        let bump = [ctx.accounts.vault.bump];
+        let seeds = [b"vault".as_ref(), bump.as_ref()];
+        let signer_seeds = [seeds.as_slice()];
+        let cpi_accounts = DepositFor {
+            delegate: ctx.accounts.vault.to_account_info(),
+            config: ctx.accounts.config.to_account_info(),
+            user: ctx.accounts.user.to_account_info(),
+            delegation: ctx.accounts.delegation.to_account_info(),
+            delegate_token_account: ctx.accounts.vault_token_account.to_account_info(),
+            pool: ctx.accounts.pool.to_account_info(),
+            pool_data: ctx.accounts.pool_data.to_account_info(),
+            pool_token_account: ctx.accounts.pool_token_account.to_account_info(),
+            share_mint: ctx.accounts.share_mint.to_account_info(),
+            delegate_share_account: ctx.accounts.vault_share_account.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            share_token_program: ctx.accounts.share_token_program.to_account_info(),
+            mint_address: ctx.accounts.mint_address.to_account_info(),
+        };
+        let cpi_ctx =
+            CpiContext::new_with_signer(ctx.accounts.coin_war_program.to_account_info(), cpi_accounts, &signer_seeds);
+        coin_war::cpi::deposit_for(cpi_ctx, beneficiary, amount, pool_name, prediction)
+    }
+
+    // Withdraw what the vault deposited for beneficiary into destination, the admin pays for the Transaction record
+    pub fn withdraw_to(ctx: Context<VaultWithdraw>, beneficiary: Pubkey, amount: f64, pool_name: u8) -> Result<()> {
+        let bump = [ctx.accounts.vault.bump];
+        let seeds = [b"vault".as_ref(), bump.as_ref()];
+        let signer_seeds = [seeds.as_slice()];
+        let cpi_accounts = WithdrawTo {
+            delegate: ctx.accounts.vault.to_account_info(),
+            payer: ctx.accounts.admin.to_account_info(),
+            config: ctx.accounts.config.to_account_info(),
+            user: ctx.accounts.user.to_account_info(),
+            delegation: ctx.accounts.delegation.to_account_info(),
+            destination: ctx.accounts.destination.to_account_info(),
+            pool: ctx.accounts.pool.to_account_info(),
+            pool_data: Some(ctx.accounts.pool_data.to_account_info()),
+            pool_token_account: ctx.accounts.pool_token_account.to_account_info(),
+            share_mint: ctx.accounts.share_mint.to_account_info(),
+            delegate_share_account: ctx.accounts.vault_share_account.to_account_info(),
+            transaction: ctx.accounts.transaction.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            share_token_program: ctx.accounts.share_token_program.to_account_info(),
+            mint_address: ctx.accounts.mint_address.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            rent: ctx.accounts.rent.to_account_info(),
+        };
+        let cpi_ctx =
+            CpiContext::new_with_signer(ctx.accounts.coin_war_program.to_account_info(), cpi_accounts, &signer_seeds);
+        coin_war::cpi::withdraw_to(cpi_ctx, beneficiary, amount, pool_name)
+    }
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(init, payer = admin, space = Vault::LEN, seeds = [b"vault".as_ref()], bump)]
+    pub vault: Account<'info, Vault>,
+    pub system_program: Program<'info, System>,
+}
+
+// The coin-war accounts are checked by coin-war itself
+#[derive(Accounts)]
+pub struct VaultDeposit<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"vault".as_ref()], bump = vault.bump, has_one = admin)]
+    pub vault: Account<'info, Vault>,
+    /// CHECK: checked by coin-war
+    #[account(mut)]
+    pub config: UncheckedAccount<'info>,
+    /// CHECK: checked by coin-war
+    #[account(mut)]
+    pub user: UncheckedAccount<'info>,
+    /// CHECK: checked by coin-war
+    #[account(mut)]
+    pub delegation: UncheckedAccount<'info>,
+    /// CHECK: checked by coin-war, a token account of the vault
+    #[account(mut)]
+    pub vault_token_account: UncheckedAccount<'info>,
+    /// CHECK: checked by coin-war
+    #[account(mut)]
+    pub pool: UncheckedAccount<'info>,
+    /// CHECK: checked by coin-war
+    #[account(mut)]
+    pub pool_data: UncheckedAccount<'info>,
+    /// CHECK: checked by coin-war
+    #[account(mut)]
+    pub pool_token_account: UncheckedAccount<'info>,
+    /// CHECK: checked by coin-war
+    #[account(mut)]
+    pub share_mint: UncheckedAccount<'info>,
+    /// CHECK: checked by coin-war, the vault's token account of the share mint
+    #[account(mut)]
+    pub vault_share_account: UncheckedAccount<'info>,
+    /// CHECK: checked by coin-war
+    pub token_program: UncheckedAccount<'info>,
+    /// CHECK: checked by coin-war
+    pub share_token_program: UncheckedAccount<'info>,
+    /// CHECK: checked by coin-war
+    pub mint_address: UncheckedAccount<'info>,
+    pub coin_war_program: Program<'info, coin_war::program::CoinWar>,
+}
+
+#[derive(Accounts)]
+pub struct VaultWithdraw<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"vault".as_ref()], bump = vault.bump, has_one = admin)]
+    pub vault: Account<'info, Vault>,
+    /// CHECK: checked by coin-war
+    #[account(mut)]
+    pub config: UncheckedAccount<'info>,
+    /// CHECK: checked by coin-war
+    #[account(mut)]
+    pub user: UncheckedAccount<'info>,
+    /// CHECK: checked by coin-war
+    #[account(mut)]
+    pub delegation: UncheckedAccount<'info>,
+    /// CHECK: checked by coin-war, any token account of the mint
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+    /// CHECK: checked by coin-war
+    #[account(mut)]
+    pub pool: UncheckedAccount<'info>,
+    /// CHECK: checked by coin-war
+    #[account(mut)]
+    pub pool_data: UncheckedAccount<'info>,
+    /// CHECK: checked by coin-war
+    #[account(mut)]
+    pub pool_token_account: UncheckedAccount<'info>,
+    /// CHECK: checked by coin-war
+    #[account(mut)]
+    pub share_mint: UncheckedAccount<'info>,
+    /// CHECK: checked by coin-war, the vault's token account of the share mint
+    #[account(mut)]
+    pub vault_share_account: UncheckedAccount<'info>,
+    /// CHECK: created by coin-war
+    #[account(mut)]
+    pub transaction: UncheckedAccount<'info>,
+    /// CHECK: checked by coin-war
+    pub token_program: UncheckedAccount<'info>,
+    /// CHECK: checked by coin-war
+    pub share_token_program: UncheckedAccount<'info>,
+    /// CHECK: checked by coin-war
+    pub mint_address: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+    pub coin_war_program: Program<'info, coin_war::program::CoinWar>,
+}
+
+#[account]
+pub struct Vault {
+    pub admin: Pubkey,
+    pub bump: u8,
+}
+
+impl Vault {
+    pub const LEN: usize = 8 + 32 + 1;
+}
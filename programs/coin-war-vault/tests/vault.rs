@@ -0,0 +1,256 @@
+// Runs on the program-test runtime, with `anchor run test-bpf` or `cargo test -p coin-war-vault --features test-bpf`
+#![cfg(feature = "test-bpf")]
+
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use coin_war::ErrorCode;
+use coin_war_client::{accounts, instruction, pda, SOLANA};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    bpf_loader_upgradeable,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction, system_program, sysvar,
+    transaction::{Transaction, TransactionError},
+};
+
+// coin-war with an allowed mint and the SOLANA pool, a user approving the vault and the vault holding 1000 tokens
+struct TestVault {
+    context: ProgramTestContext,
+    mint: Pubkey,
+    share_mint: Pubkey,
+    pool_data: Pubkey,
+    user: Keypair,
+}
+
+fn vault_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"vault"], &coin_war_vault::ID).0
+}
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let blockhash = context.banks_client.get_latest_blockhash().await?;
+    let transaction =
+        Transaction::new_signed_with_payer(instructions, Some(&context.payer.pubkey()), &all_signers, blockhash);
+    context.banks_client.process_transaction(transaction).await
+}
+
+fn assert_error(result: Result<(), BanksClientError>, error: ErrorCode) {
+    match result.unwrap_err().unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => assert_eq!(code, u32::from(error)),
+        other => panic!("unexpected error {:?}", other),
+    }
+}
+
+async fn token_balance(context: &mut ProgramTestContext, address: Pubkey) -> u64 {
+    let account = context.banks_client.get_account(address).await.unwrap().unwrap();
+    spl_token::state::Account::unpack(&account.data).unwrap().amount
+}
+
+async fn create_account(
+    context: &mut ProgramTestContext,
+    account: &Keypair,
+    len: usize,
+    owner: &Pubkey,
+) -> Instruction {
+    let rent = context.banks_client.get_rent().await.unwrap();
+    system_instruction::create_account(
+        &context.payer.pubkey(),
+        &account.pubkey(),
+        rent.minimum_balance(len),
+        len as u64,
+        owner,
+    )
+}
+
+async fn create_associated_token_account(context: &mut ProgramTestContext, wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+    let address = anchor_spl::associated_token::get_associated_token_address(wallet, mint);
+    let instruction = Instruction {
+        program_id: anchor_spl::associated_token::ID,
+        accounts: vec![
+            AccountMeta::new(context.payer.pubkey(), true),
+            AccountMeta::new(address, false),
+            AccountMeta::new_readonly(*wallet, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        // Create
+        data: vec![0],
+    };
+    process(context, &[instruction], &[]).await.unwrap();
+    address
+}
+
+// coin_war is a processor! program, so give it the ProgramData account it would have on a cluster, with the payer as
+// upgrade authority so it can create the config. Its lamports come out of the payer to keep the bank's capitalization.
+async fn set_upgrade_authority(context: &mut ProgramTestContext) {
+    let payer = context.payer.pubkey();
+    // bincode of UpgradeableLoaderState::ProgramData { slot: 0, upgrade_authority_address: Some(payer) }
+    let mut data = vec![3, 0, 0, 0];
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.push(1);
+    data.extend_from_slice(payer.as_ref());
+    let account = Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: bpf_loader_upgradeable::id(),
+        executable: false,
+        rent_epoch: 0,
+    };
+    let mut payer_account: Account = context.banks_client.get_account(payer).await.unwrap().unwrap();
+    payer_account.lamports -= account.lamports;
+    context.set_account(&payer, &payer_account.into());
+    context.set_account(&pda::program_data(), &account.into());
+}
+
+impl TestVault {
+    async fn start() -> TestVault {
+        let mut program_test = ProgramTest::new("coin_war", coin_war::ID, processor!(coin_war::entry));
+        program_test.add_program("coin_war_vault", coin_war_vault::ID, processor!(coin_war_vault::entry));
+        let mut context = program_test.start_with_context().await;
+        set_upgrade_authority(&mut context).await;
+        let admin = context.payer.pubkey();
+
+        let mint = Keypair::new();
+        let pool_data = Keypair::new();
+        let share_mint = Keypair::new();
+        let instructions = [
+            create_account(&mut context, &mint, spl_token::state::Mint::LEN, &spl_token::id()).await,
+            spl_token::instruction::initialize_mint(
+                &spl_token::id(),
+                &mint.pubkey(),
+                &admin,
+                None,
+                coin_war::AMOUNT_DECIMALS,
+            )
+            .unwrap(),
+            instruction::initialize_config(&admin),
+            instruction::add_mint(&admin, &mint.pubkey(), &spl_token::id(), 1),
+            instruction::create_pool(&admin, SOLANA),
+            create_account(&mut context, &pool_data, coin_war::PoolData::LEN, &coin_war::ID).await,
+            instruction::create_pool_data(&admin, SOLANA, &pool_data.pubkey()),
+            instruction::create_share_mint(&admin, SOLANA, &share_mint.pubkey()),
+        ];
+        process(&mut context, &instructions, &[&mint, &pool_data, &share_mint]).await.unwrap();
+
+        let accounts =
+            coin_war_vault::accounts::Initialize { admin, vault: vault_pda(), system_program: system_program::id() };
+        let instruction = ix(accounts, coin_war_vault::instruction::Initialize {});
+        process(&mut context, &[instruction], &[]).await.unwrap();
+        let vault_token_account = create_associated_token_account(&mut context, &vault_pda(), &mint.pubkey()).await;
+        create_associated_token_account(&mut context, &vault_pda(), &share_mint.pubkey()).await;
+        let instruction =
+            spl_token::instruction::mint_to(&spl_token::id(), &mint.pubkey(), &vault_token_account, &admin, &[], 1_000)
+                .unwrap();
+        process(&mut context, &[instruction], &[]).await.unwrap();
+
+        let user = Keypair::new();
+        let instructions = [
+            system_instruction::transfer(&admin, &user.pubkey(), 1_000_000_000),
+            instruction::create_user_without_wallet(&user.pubkey()),
+            instruction::approve_delegate(&user.pubkey(), &vault_pda()),
+        ];
+        process(&mut context, &instructions, &[&user]).await.unwrap();
+
+        TestVault { context, mint: mint.pubkey(), share_mint: share_mint.pubkey(), pool_data: pool_data.pubkey(), user }
+    }
+
+    async fn deposit_for(&mut self, admin: &Keypair, amount: f64) -> Result<(), BanksClientError> {
+        let vault = vault_pda();
+        let user = self.user.pubkey();
+        let accounts = coin_war_vault::accounts::VaultDeposit {
+            admin: admin.pubkey(),
+            vault,
+            config: pda::config(),
+            user: pda::user(&user),
+            delegation: pda::delegation(&user, &vault),
+            vault_token_account: anchor_spl::associated_token::get_associated_token_address(&vault, &self.mint),
+            pool: pda::pool(SOLANA),
+            pool_data: self.pool_data,
+            pool_token_account: pda::pool_wallet(&self.mint),
+            share_mint: self.share_mint,
+            vault_share_account: pda::user_share_account(&vault, &self.share_mint),
+            token_program: spl_token::id(),
+            share_token_program: spl_token::id(),
+            mint_address: self.mint,
+            coin_war_program: coin_war::ID,
+        };
+        let data =
+            coin_war_vault::instruction::DepositFor { beneficiary: user, amount, pool_name: SOLANA, prediction: 30.0 };
+        process(&mut self.context, &[ix(accounts, data)], &[admin]).await
+    }
+
+    async fn withdraw_to(&mut self, destination: Pubkey, amount: f64) -> Result<(), BanksClientError> {
+        let vault = vault_pda();
+        let user = self.user.pubkey();
+        let txn_count = self.user().await.txn_count;
+        let accounts = coin_war_vault::accounts::VaultWithdraw {
+            admin: self.context.payer.pubkey(),
+            vault,
+            config: pda::config(),
+            user: pda::user(&user),
+            delegation: pda::delegation(&user, &vault),
+            destination,
+            pool: pda::pool(SOLANA),
+            pool_data: self.pool_data,
+            pool_token_account: pda::pool_wallet(&self.mint),
+            share_mint: self.share_mint,
+            vault_share_account: pda::user_share_account(&vault, &self.share_mint),
+            transaction: pda::tx(&user, SOLANA, txn_count),
+            token_program: spl_token::id(),
+            share_token_program: spl_token::id(),
+            mint_address: self.mint,
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+            coin_war_program: coin_war::ID,
+        };
+        let data = coin_war_vault::instruction::WithdrawTo { beneficiary: user, amount, pool_name: SOLANA };
+        process(&mut self.context, &[ix(accounts, data)], &[]).await
+    }
+
+    async fn user(&mut self) -> coin_war::User {
+        let account = self.context.banks_client.get_account(pda::user(&self.user.pubkey())).await.unwrap().unwrap();
+        accounts::user(&account.data).unwrap()
+    }
+}
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction { program_id: coin_war_vault::ID, accounts: accounts.to_account_metas(None), data: data.data() }
+}
+
+#[tokio::test]
+async fn vault_deposits_for_a_user_and_withdraws_through_cpi() {
+    let mut test = TestVault::start().await;
+    let admin = Keypair::from_bytes(&test.context.payer.to_bytes()).unwrap();
+
+    test.deposit_for(&admin, 400.0).await.unwrap();
+    let user = test.user().await;
+    assert_eq!((user.balance, user.delegated_balance, user.shares), (400.0, 400.0, 0));
+    let vault_share_account = pda::user_share_account(&vault_pda(), &test.share_mint);
+    assert_eq!(token_balance(&mut test.context, vault_share_account).await, 400);
+    let delegation = test.context.banks_client.get_account(pda::delegation(&test.user.pubkey(), &vault_pda()));
+    let delegation = delegation.await.unwrap().unwrap();
+    let delegation = coin_war::Delegation::try_deserialize(&mut delegation.data.as_slice()).unwrap();
+    assert_eq!(delegation.deposited, 400.0);
+
+    // only the vault admin moves the vault's funds
+    let stranger = Keypair::new();
+    assert!(test.deposit_for(&stranger, 100.0).await.is_err());
+
+    // coin-war errors come back through the CPI
+    let destination = create_associated_token_account(&mut test.context, &test.user.pubkey(), &test.mint).await;
+    assert_error(test.withdraw_to(destination, 500.0).await, ErrorCode::InsufficientBalance);
+    test.withdraw_to(destination, 400.0).await.unwrap();
+    assert_eq!(token_balance(&mut test.context, destination).await, 400);
+    assert_eq!(token_balance(&mut test.context, vault_share_account).await, 0);
+    let user = test.user().await;
+    assert_eq!((user.balance, user.delegated_balance), (0.0, 0.0));
+}
@@ -0,0 +1,220 @@
+use anchor_lang::prelude::*;
+
+use crate::math::{self, WeightedBalance};
+use crate::{ErrorCode, Game, Pool, User};
+
+/* Pool accounting - the state transitions behind deposit, withdraw and make_prediction. These only touch the User
+ * and Pool accounts and never move tokens, so the instruction handlers call them before doing the transfer and
+ * the same bookkeeping can be exercised off-chain. Every check happens before the first write, so an error leaves
+ * both accounts untouched.
+ */
+
+// Token transfers are done in whole token units, so anything else would let the books drift from the pool wallet
+fn is_whole_amount(amount: f64) -> bool {
+    amount > 0.0 && amount.fract() == 0.0 && amount <= u64::MAX as f64
+}
+
+// Replace the user's prediction in the pool average while the user count goes from count_before to the current one
+fn update_average_prediction(user: &mut User, pool: &mut Pool, count_before: u64, prediction: f64) {
+    pool.average_prediction = math::replace_prediction(
+        pool.average_prediction,
+        count_before,
+        pool.user_count,
+        user.last_prediction,
+        prediction,
+    );
+    user.last_prediction = prediction;
+}
+
+fn set_weighted_balance(user: &mut User, weighted: WeightedBalance) {
+    user.current_average_balance = weighted.average_balance;
+    user.current_weighted_balance = weighted.weighted_balance;
+    user.current_weighted_days = weighted.weighted_days;
+}
+
+fn weighted_balance(user: &User) -> WeightedBalance {
+    WeightedBalance {
+        average_balance: user.current_average_balance,
+        weighted_balance: user.current_weighted_balance,
+        weighted_days: user.current_weighted_days,
+    }
+}
+
+// Add amount to the user's balance in pool. Only allowed to deposit in one pool at a time. The minimum deposit
+// depends on the mint, so the deposit handler checks it. Returns the amount credited, which the pool wallet has to
+// have taken in.
+pub fn apply_deposit(user: &mut User, pool: &mut Pool, amount: f64, prediction: f64) -> Result<f64> {
+    require!(is_whole_amount(amount), ErrorCode::InvalidAmount);
+    require!(prediction.is_finite(), ErrorCode::InvalidPrediction);
+    require!(user.pool == 0 || user.pool == pool.name || user.balance <= 0.0, ErrorCode::MultiplePoolNotAllowed);
+    let is_new_to_pool = user.pool != pool.name || user.balance <= 0.0;
+    checkpoint_weight(user, pool);
+
+    // Update user balance
+    user.balance += amount;
+
+    // Update average balance for user
+    let days_left = 4; // TODO: days left should be number of days left until game is over
+    set_weighted_balance(user, weighted_balance(user).add_deposit(amount, days_left));
+
+    // Update pool balance
+    pool.total_deposit += amount;
+
+    // Update pool count if user not in pool, then replace the previous prediction
+    let count_before = pool.user_count;
+    if is_new_to_pool {
+        user.pool = pool.name;
+        pool.user_count += 1;
+    }
+    update_average_prediction(user, pool, count_before, prediction);
+
+    Ok(amount)
+}
+
+// Take amount out of the user's balance in pool. The prediction is dropped and the user leaves the pool once the
+// balance reaches zero. Returns the amount taken out, which is sent out of the pool wallet unless it is queued.
+pub fn apply_withdraw(user: &mut User, pool: &mut Pool, amount: f64) -> Result<f64> {
+    require!(user.pool == pool.name, ErrorCode::InvalidWithdrawal);
+    require!(is_whole_amount(amount), ErrorCode::InvalidAmount);
+
+    // Check if theres enough money
+    require!(user.balance >= amount, ErrorCode::InsufficientBalance);
+    checkpoint_weight(user, pool);
+
+    // Update user balance
+    user.balance -= amount;
+
+    // Update pool balance
+    pool.total_deposit -= amount;
+
+    // Update pool count if needed, then remove the previous prediction
+    let count_before = pool.user_count;
+    if user.balance <= 0.0 && pool.user_count > 0 {
+        pool.user_count -= 1;
+    }
+    update_average_prediction(user, pool, count_before, 0.0);
+
+    // Update average balance for user (user average balance is reset to current balance)
+    set_weighted_balance(user, WeightedBalance::full_game(user.balance));
+
+    Ok(amount)
+}
+
+// Bring the user's balance in pool up to the pool's exchange rate out of its pending prize, see the shares module.
+// Returns the amount added to the balance.
+pub fn apply_accrual(user: &mut User, pool: &mut Pool) -> f64 {
+    let amount = (user.balance_at_rate(pool) - user.balance).min(pool.pending_prize.floor()).max(0.0);
+    user.balance += amount;
+    pool.total_deposit += amount;
+    pool.pending_prize -= amount;
+    user.share_rate = pool.exchange_rate();
+    amount
+}
+
+// Change the user's prediction for the pool they have a balance in
+pub fn apply_prediction(user: &mut User, pool: &mut Pool, prediction: f64) -> Result<()> {
+    require!(user.pool == pool.name && user.balance > 0.0, ErrorCode::UserNotInPool);
+    require!(prediction.is_finite(), ErrorCode::InvalidPrediction);
+
+    let count = pool.user_count;
+    update_average_prediction(user, pool, count, prediction);
+
+    Ok(())
+}
+
+// Start the user over after being paid for game_id. The balance stays in the pool for the next game, and so does
+// the prediction, which is counted in the pool average until the user makes a new one.
+pub fn reset_after_payout(user: &mut User, game_id: u64) {
+    set_weighted_balance(user, WeightedBalance::full_game(user.balance));
+    user.last_paid_game_id = game_id;
+}
+
+// Add the prize of a user of pool who auto-compounds to the balance instead of paying it out. Balances only hold
+// whole amounts, so the fraction is left in the pool wallet as treasury. Returns the amount added.
+pub fn apply_compound(user: &mut User, pool: &mut Pool, prize: f64) -> Result<f64> {
+    let amount = prize.floor();
+    if amount <= 0.0 {
+        return Ok(0.0);
+    }
+    require!(user.pool == pool.name && user.balance > 0.0, ErrorCode::UserNotInPool);
+    checkpoint_weight(user, pool);
+    user.balance += amount;
+    pool.total_deposit += amount;
+    Ok(amount)
+}
+
+// Whether the prize of the user in pool is compounded, only users with a balance left in the pool, held in the
+// mint the prize is paid in, can
+pub fn compounds(user: &User, pool: &Pool, mint: &Pubkey) -> bool {
+    user.auto_compound && user.pool == pool.name && user.balance > 0.0 && user.check_mint(mint).is_ok()
+}
+
+// Keep the user's prize weight in pool as it was when the pool won its last game, before the first change to it
+// while that game is being paid out. Balances that only come in after the game was settled have no share of its
+// prize, and balances that leave keep theirs.
+pub fn checkpoint_weight(user: &mut User, pool: &Pool) {
+    if pool.won_game_id > user.last_paid_game_id && user.settled_game_id < pool.won_game_id {
+        user.settled_weight = user.prize_weight(pool);
+        user.settled_game_id = pool.won_game_id;
+    }
+}
+
+// Prize weight of the user in the winning pool of game, as it was when the game was settled
+pub fn user_weight(user: &User, pool: &Pool, game: &Game) -> f64 {
+    if user.settled_game_id == game.game_id {
+        user.settled_weight
+    } else {
+        user.prize_weight(pool)
+    }
+}
+
+// Prize weight of the winning pool of game the prize shares are taken out of, recorded by settle_game. Games settled
+// before it was recorded take the pool's current weight, less the prizes compounded into it while being paid out.
+pub fn winning_weight(pool: &Pool, game: &Game) -> f64 {
+    if game.winning_weight > 0.0 {
+        game.winning_weight
+    } else {
+        pool.prize_weight() - game.compounded_prize
+    }
+}
+
+// Move amount of the user's balance out of the active stake of pool into a withdrawal released at release_time, the
+// end of the game in progress. It takes no part in the game from then on, so it has no share of the prize, but is
+// paid out in full by complete_withdrawal. Requests during the same game add up.
+pub fn apply_withdrawal_request(user: &mut User, pool: &mut Pool, amount: f64, release_time: i64) -> Result<()> {
+    let same_request = user.withdrawal_pool == pool.name && user.withdrawal_release_time == release_time;
+    require!(user.pending_withdrawal <= 0.0 || same_request, ErrorCode::WithdrawalPending);
+    apply_withdraw(user, pool, amount)?;
+
+    user.pending_withdrawal += amount;
+    user.withdrawal_pool = pool.name;
+    user.withdrawal_release_time = release_time;
+    pool.pending_withdrawals += amount;
+
+    Ok(())
+}
+
+// Put the user's pending withdrawal back into the active stake of pool with a new prediction, the request dropped
+// the previous one. Returns the amount put back.
+pub fn apply_withdrawal_cancel(user: &mut User, pool: &mut Pool, prediction: f64) -> Result<f64> {
+    require!(user.pending_withdrawal > 0.0 && user.withdrawal_pool == pool.name, ErrorCode::NoWithdrawalRequest);
+    let amount = user.pending_withdrawal;
+    apply_deposit(user, pool, amount, prediction)?;
+
+    user.pending_withdrawal = 0.0;
+    pool.pending_withdrawals -= amount;
+
+    Ok(amount)
+}
+
+// Release the user's pending withdrawal out of pool once its game is over. Returns the amount to pay out.
+pub fn apply_withdrawal_completion(user: &mut User, pool: &mut Pool, now: i64) -> Result<f64> {
+    require!(user.pending_withdrawal > 0.0 && user.withdrawal_pool == pool.name, ErrorCode::NoWithdrawalRequest);
+    require!(now >= user.withdrawal_release_time, ErrorCode::WithdrawalNotReleased);
+    let amount = user.pending_withdrawal;
+
+    user.pending_withdrawal = 0.0;
+    pool.pending_withdrawals -= amount;
+
+    Ok(amount)
+}
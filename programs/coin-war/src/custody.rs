@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::token_interface::TokenAccount;
+
+use crate::{ErrorCode, User};
+
+/* Custody - where the tokens a user deposits come from and where their withdrawals and payouts go. Either the user
+ * wallet, the token account owned by the User PDA that create_user makes, or the wallet's own associated token
+ * account of the mint, in which case the user needs no user wallet at all. Deposits are signed by the User PDA or by
+ * the wallet accordingly. Whichever is used, a user's balance is held in a single mint, recorded in User::mint.
+ */
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Custody {
+    UserWallet,
+    AssociatedTokenAccount,
+}
+
+pub fn user_wallet_address(user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"user_wallet".as_ref(), user.as_ref()], &crate::ID).0
+}
+
+// How token_account holds the tokens of wallet, whose User PDA is user. Any other token account is rejected.
+pub fn custody_of(token_account: &InterfaceAccount<TokenAccount>, wallet: &Pubkey, user: &Pubkey) -> Result<Custody> {
+    let token_program = *token_account.to_account_info().owner;
+    let associated_token_account =
+        get_associated_token_address_with_program_id(wallet, &token_account.mint, &token_program);
+    if token_account.key() == user_wallet_address(user) {
+        Ok(Custody::UserWallet)
+    } else if token_account.key() == associated_token_account && token_account.owner == *wallet {
+        Ok(Custody::AssociatedTokenAccount)
+    } else {
+        err!(ErrorCode::InvalidUserTokenAccount)
+    }
+}
+
+impl User {
+    // Withdrawals and payouts are in the mint the balance is held in. Users who haven't deposited since the mint was
+    // recorded have none, for them it is up to the token accounts as before.
+    pub fn check_mint(&self, mint: &Pubkey) -> Result<()> {
+        require!(self.mint == *mint || self.mint == Pubkey::default(), ErrorCode::WrongMint);
+        Ok(())
+    }
+
+    // Deposits can be in any allowed mint while there is no balance
+    pub fn check_deposit_mint(&self, mint: &Pubkey) -> Result<()> {
+        if self.balance <= 0.0 {
+            return Ok(());
+        }
+        self.check_mint(mint)
+    }
+}
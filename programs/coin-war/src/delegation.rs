@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+
+use crate::shares::shares_for_withdrawal;
+use crate::{Delegation, ErrorCode, User};
+
+/* Delegation - lets another program, usually a vault or aggregator signing with one of its PDAs through CPI, manage
+ * part of a user's position. The user approves the delegate with approve_delegate. The delegate then deposit_for's
+ * the user out of its own token accounts and withdraw_to's back what it deposited, into any token account of the
+ * mint. Delegated deposits count for the user like any other, weighted balance and prizes included, but are kept in
+ * User::delegated_balance: the user can't withdraw them and their shares are minted to the delegate. The delegation
+ * can only be revoked once the delegate has withdrawn everything.
+ */
+
+pub fn delegation_address(user: &Pubkey, delegate: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"delegation".as_ref(), user.as_ref(), delegate.as_ref()], &crate::ID).0
+}
+
+impl User {
+    // Part of the balance the user can withdraw
+    pub fn own_balance(&self) -> f64 {
+        self.balance - self.delegated_balance
+    }
+
+    // Take a withdrawal, already taken off the balance, out of the user's own part of it. Returns the user's shares
+    // to burn.
+    pub fn take_own_withdrawal(&mut self, amount: f64, rate: f64) -> Result<u64> {
+        // what delegates deposited is left for them to withdraw
+        require!(self.own_balance() >= 0.0, ErrorCode::DelegatedBalance);
+        let shares = shares_for_withdrawal(amount, self.own_balance(), rate, self.shares);
+        self.shares -= shares;
+        Ok(shares)
+    }
+}
+
+impl Delegation {
+    pub fn add_deposit(&mut self, user: &mut User, amount: f64, shares: u64) {
+        self.deposited += amount;
+        self.shares += shares;
+        user.delegated_balance += amount;
+    }
+
+    // Take a withdrawal out of what the delegate deposited, returns the delegate's shares to burn
+    pub fn take_withdrawal(&mut self, user: &mut User, amount: f64, rate: f64) -> Result<u64> {
+        require!(amount <= self.deposited, ErrorCode::DelegationExceeded);
+        self.deposited -= amount;
+        user.delegated_balance -= amount;
+        let shares = shares_for_withdrawal(amount, self.deposited, rate, self.shares);
+        self.shares -= shares;
+        Ok(shares)
+    }
+}
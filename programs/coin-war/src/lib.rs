@@ -1,8 +1,13 @@
 use anchor_spl::associated_token::AssociatedToken;
 use solana_program::pubkey::Pubkey;
 use anchor_lang::{prelude::*, solana_program};
-use anchor_spl::token::{TokenAccount, Transfer, Token, Mint};
-use anchor_spl::token;
+use anchor_lang::system_program;
+use solana_program::bpf_loader_upgradeable;
+use anchor_spl::token::{Token, CloseAccount, SyncNative};
+use anchor_spl::token::{self, spl_token::native_mint};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use custody::Custody;
+use space::{DISCRIMINATOR, RESERVED};
 
 declare_id!("6KVxPWYY2Dg3iS7qPMN2CuGyUeUYdJENVhxaGZ74Ko7T");
 // TODO: Create pubkey for owner program and each of the four pool wallets
@@ -19,47 +24,233 @@ declare_id!("6KVxPWYY2Dg3iS7qPMN2CuGyUeUYdJENVhxaGZ74Ko7T");
  */
 
 
-fn to_long<'info>(amount: f64) -> u64 {
-    let mint_decimals = 9;
-    return (amount * f64::powf(10., mint_decimals.into())) as u64;
-}
-
-// utility function to send tokens out of pool wallets
+// utility function to send tokens out of pool wallets. Each mint's pool wallet is its own authority, so the transfer
+// is signed with the pool wallet seeds. Works for SPL Token and Token-2022 mints alike.
 fn transfer_token_out_of_pool<'info>(
-    pool_wallet: &mut Account<'info, TokenAccount>,
+    pool_wallet: &mut InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
     token_program: AccountInfo<'info>,
     destination_wallet: AccountInfo<'info>,
-    authority: AccountInfo<'info>,
-    pool_name: String,
+    pool_wallet_bump: u8,
     amount: u64
 ) -> Result<()> {
-    let inner = vec![b"pool_wallet".as_ref()];
+    let mint_key = pool_wallet.mint;
+    let inner = vec![b"pool_wallet".as_ref(), mint_key.as_ref(), std::slice::from_ref(&pool_wallet_bump)];
     let outer = vec![inner.as_slice()];
 
     // Perform the actual transfer
-    let transfer_instruction = Transfer{
+    let transfer_instruction = TransferChecked{
         from: pool_wallet.to_account_info(),
+        mint: mint.to_account_info(),
         to: destination_wallet,
-        authority: authority.to_account_info(),
+        authority: pool_wallet.to_account_info(),
     };
     let cpi_ctx = CpiContext::new_with_signer(
         token_program.to_account_info(),
         transfer_instruction,
         outer.as_slice(),
     );
-    return anchor_spl::token::transfer(cpi_ctx, amount);
+    return token_interface::transfer_checked(cpi_ctx, amount, mint.decimals);
+}
+
+// Bookkeeping shared by deposit, deposit_sol and deposit_for: the User and Pool balances, the deposits of the mint
+// and the participant list. base_units are the base units of amount that are in the pool wallet. delegation is set
+// for deposits of a delegate, see the delegation module. Returns the shares to mint.
+fn record_deposit<'info>(
+    config: &mut Config,
+    mint: &Pubkey,
+    user: &mut User,
+    pool: &mut Pool,
+    pool_data: &AccountLoader<'info, PoolData>,
+    wallet: Pubkey,
+    amount: f64,
+    base_units: u64,
+    prediction: f64,
+    delegation: Option<&mut Delegation>,
+) -> Result<u64> {
+    // Update user and pool balances, prediction and user count
+    user.check_deposit_mint(mint)?;
+    record_accrual(config, mint, user, pool, wallet)?;
+    let amount = accounting::apply_deposit(user, pool, amount, prediction)?;
+    config.allowed_mint_mut(mint)?.deposits += base_units;
+    user.mint = *mint;
+    let shares = shares::shares_for_deposit(amount, pool.exchange_rate());
+    match delegation {
+        Some(delegation) => delegation.add_deposit(user, amount, shares),
+        None => user.shares += shares,
+    }
+
+    update_participant(user, Some(pool_data), wallet)?;
+    Ok(shares)
+}
+
+// Bookkeeping shared by withdraw, withdraw_sol and withdraw_to, returns the base units of the mint to send out of its
+// pool wallet and the shares to burn. delegation is set for withdrawals of a delegate.
+fn record_withdrawal<'info>(
+    config: &mut Config,
+    mint: &Pubkey,
+    user: &mut User,
+    pool: &mut Pool,
+    pool_data: Option<&AccountLoader<'info, PoolData>>,
+    wallet: Pubkey,
+    amount: f64,
+    delegation: Option<&mut Delegation>,
+) -> Result<(u64, u64)> {
+    // Update user and pool balances, prediction and user count
+    user.check_mint(mint)?;
+    record_accrual(config, mint, user, pool, wallet)?;
+    let now = Clock::get()?.unix_timestamp;
+    let settled_until = config.settled_until;
+    record_unlock(user, pool, wallet, now, settled_until)?;
+    let amount = accounting::apply_withdraw(user, pool, amount)?;
+    let allowed = config.allowed_mint_mut(mint)?;
+    let base_units = allowed.to_base_units(amount)?;
+    allowed.deposits -= base_units;
+    let rate = pool.exchange_rate();
+    let shares = match delegation {
+        Some(delegation) => delegation.take_withdrawal(user, amount, rate)?,
+        None => {
+            let shares = user.take_own_withdrawal(amount, rate)?;
+            user.check_lock(now, settled_until)?;
+            shares
+        }
+    };
+
+    update_participant(user, pool_data, wallet)?;
+    Ok((base_units, shares))
+}
+
+// Users joining the pool are put on its participant list and users leaving it taken off. The list is best effort:
+// users who find it full stay off it and are tried again on their next deposit, and it is only required to take a
+// listed user off.
+fn update_participant<'info>(
+    user: &mut User,
+    pool_data: Option<&AccountLoader<'info, PoolData>>,
+    wallet: Pubkey,
+) -> Result<()> {
+    if user.balance > 0.0 && user.participant_slot == 0 {
+        if let Some(pool_data) = pool_data {
+            user.participant_slot = pool_data.load_mut()?.add_participant(wallet).unwrap_or(0);
+        }
+    } else if user.balance <= 0.0 && user.participant_slot != 0 {
+        let pool_data = pool_data.ok_or(ErrorCode::InvalidPoolData)?;
+        pool_data.load_mut()?.remove_participant(user.participant_slot, wallet)?;
+        user.participant_slot = 0;
+    }
+    Ok(())
+}
+
+// Drop the user's lock in pool once it is released, see the lockup module
+fn record_unlock(user: &mut User, pool: &mut Pool, wallet: Pubkey, now: i64, settled_until: i64) -> Result<()> {
+    if user.lock_released(now, settled_until) && user.pool == pool.name {
+        let amount = lockup::apply_unlock(user, pool, now, settled_until)?;
+        emit!(DepositUnlockedEvent { user: wallet, pool: pool.name, amount, timestamp: now });
+    }
+    Ok(())
+}
+
+// Bring the user's balance up to the pool's exchange rate before it changes, the prize it gains is owed in mint from
+// then on. See the shares module.
+fn record_accrual(config: &mut Config, mint: &Pubkey, user: &mut User, pool: &mut Pool, wallet: Pubkey) -> Result<()> {
+    let amount = accounting::apply_accrual(user, pool);
+    if amount > 0.0 {
+        let allowed = config.allowed_mint_mut(mint)?;
+        allowed.deposits += allowed.payout_units(amount);
+        emit!(PrizeAccruedEvent { user: wallet, pool: pool.name, amount, balance: user.balance });
+    }
+    Ok(())
+}
+
+// Credit the prize of a winning user who auto-compounds to the balance, the tokens stay in the pool wallet as deposits
+fn record_compound(
+    config: &mut Config,
+    mint: &Pubkey,
+    user: &mut User,
+    pool: &mut Pool,
+    game: &mut Game,
+    wallet: Pubkey,
+    prize: f64,
+) -> Result<()> {
+    record_accrual(config, mint, user, pool, wallet)?;
+    let amount = accounting::apply_compound(user, pool, prize)?;
+    let allowed = config.allowed_mint_mut(mint)?;
+    allowed.deposits += allowed.payout_units(amount);
+    game.total_paid += amount;
+    game.compounded_prize += amount;
+    emit!(PrizeCompoundedEvent { game_id: game.game_id, user: wallet, pool: pool.name, amount, balance: user.balance });
+    Ok(())
+}
+
+// Every withdrawal leaves a Transaction record, numbered by the user's txn_count
+fn record_withdrawal_transaction(transaction: &mut Transaction, user: &mut User, amount: f64, timestamp: i64) {
+    transaction.amount = amount;
+    transaction.transaction_type = TransactionType::Withdrawal.to_code();
+    transaction.timestamp = timestamp;
+    transaction.version = migrate::TRANSACTION_VERSION;
+    user.txn_count += 1;
+}
+
+// Record a gift deposit of donor into the position of user
+fn record_gift_transaction(transaction: &mut Transaction, user: &mut User, donor: Pubkey, amount: f64, timestamp: i64) {
+    transaction.amount = amount;
+    transaction.transaction_type = TransactionType::Gift.to_code();
+    transaction.timestamp = timestamp;
+    transaction.version = migrate::TRANSACTION_VERSION;
+    transaction.donor = donor;
+    user.txn_count += 1;
+}
+
+// A user as create_user and create_user_without_wallet leave it
+pub fn init_user(user: &mut User) {
+    user.balance = 0.0;
+    user.current_average_balance = 0.0;
+    user.current_weighted_balance = 0.0;
+    user.current_weighted_days = GAME_DURATION_IN_DAYS;
+    user.last_prediction = 0.0;
+    user.version = migrate::USER_VERSION;
+}
+
+// A pool as create_pool leaves it
+pub fn init_pool(pool: &mut Pool, name: u8, timestamp: i64) {
+    pool.is_initialized = true;
+    pool.last_update_timestamp = timestamp;
+    pool.total_deposit = 0.00;
+    pool.user_count = 0;
+    pool.name = name;
+    pool.average_prediction = 0.0;
+    pool.invested = 0.0;
+    pool.version = migrate::POOL_VERSION;
 }
 
+pub mod accounting;
+pub mod custody;
+pub mod delegation;
+pub mod lockup;
+pub mod math;
+pub mod merkle;
+pub mod migrate;
+pub mod mints;
+pub mod pool_data;
+pub mod shares;
+pub mod space;
+pub mod yield_source;
+
+pub const GAME_DURATION_IN_DAYS: i64 = 5;
+// Deposits, balances and prizes are counted in units of 10^-AMOUNT_DECIMALS tokens whatever the mint, so the pools
+// can take deposits in several mints of the same value. See the mints module.
+pub const AMOUNT_DECIMALS: u8 = 6;
+pub const MAX_MINTS: usize = 4;
+// lock lengths with a prize weight multiplier, see the lockup module
+pub const MAX_LOCK_TIERS: usize = 4;
+
 #[program]
 pub mod coin_war {
     use std::vec;
 
     use super::*;
 
-    const GAME_DURATION_IN_DAYS: i64 = 5;
-    const MINIMUM_DEPOSIT: f64 = 1.00;
     // const INITIAL_POOL_PRIZE: f64 = 100.00; 
-    // const GAME_DURATION_IN_SECS: i64 = GAME_DURATION_IN_DAYS * 24 * 60 * 60;
+    const GAME_DURATION_IN_SECS: i64 = GAME_DURATION_IN_DAYS * 24 * 60 * 60;
     // const JACKPOT_WINNER_PERCENTAGE: u64 = 10;
 
     // Create a pool. This needs to be called once for each of the pools defined in enum Pools.
@@ -67,28 +258,327 @@ pub mod coin_war {
         let pool_enum = Pools::from(pool_name)?;
         require!(ctx.accounts.pool.is_initialized == false, ErrorCode::PoolAlreadyCreated);
         let clock: Clock = Clock::get().unwrap();
+        init_pool(&mut ctx.accounts.pool, pool_enum.to_code(), clock.unix_timestamp);
+
+        Ok(())
+    }
+
+    // Create the program config. The signer has to be the upgrade authority of the program and becomes the admin that
+    // can pause deposits and run the games.
+    pub fn initialize_config(ctx: Context<InitializeConfig>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.paused = false;
+        config.mints = Vec::new();
+        config.version = migrate::CONFIG_VERSION;
+
+        Ok(())
+    }
+
+    // Accept deposits in a mint and create its pool wallet. The decimals are read from the mint account and
+    // minimum_deposit is in base units of the mint.
+    pub fn add_mint(ctx: Context<AddMint>, minimum_deposit: u64) -> Result<()> {
+        let mint = &ctx.accounts.mint_address;
+        ctx.accounts.config.add_mint(mint.key(), mint.decimals, minimum_deposit)
+    }
+
+    pub fn set_minimum_deposit(ctx: Context<SetMinimumDeposit>, mint: Pubkey, minimum_deposit: u64) -> Result<()> {
+        ctx.accounts.config.allowed_mint_mut(&mint)?.minimum_deposit = minimum_deposit;
+
+        Ok(())
+    }
+
+    // Set the prize weight multiplier of deposits locked for games games, zero stops offering that lock length. See
+    // the lockup module.
+    pub fn set_lock_tier(ctx: Context<SetLockTier>, games: u8, multiplier: f64) -> Result<()> {
+        ctx.accounts.config.set_lock_tier(games, multiplier)
+    }
+
+    // Pause or unpause deposits. Withdrawals and payouts keep working so users can always get their funds out.
+    pub fn set_paused(ctx: Context<SetPaused>, paused: bool) -> Result<()> {
+        ctx.accounts.config.paused = paused;
+
+        Ok(())
+    }
+
+    // Send tokens a mint's pool wallet holds beyond the deposits made in that mint, e.g. prize left over after a
+    // game, to destination. amount is in base units of the mint. Deposits can never be taken out this way, but
+    // unpaid prize can, so pay out settled games first.
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        let allowed = ctx.accounts.config.allowed_mint(&ctx.accounts.mint_address.key())?;
+        require!(amount <= allowed.treasury(ctx.accounts.pool_token_account.amount), ErrorCode::InsufficientBalance);
+
+        transfer_token_out_of_pool(
+            &mut ctx.accounts.pool_token_account,
+            &ctx.accounts.mint_address,
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.destination.to_account_info(),
+            *ctx.bumps.get("pool_token_account").unwrap(),
+            amount)
+    }
+
+    // Attach the zero-copy PoolData account to a pool. PoolData is too big to be created by the program, so the
+    // admin creates it with the system program in the same transaction, owned by this program and PoolData::LEN
+    // bytes long. Users already in the pool are listed as participants on their next deposit.
+    pub fn create_pool_data(ctx: Context<CreatePoolData>, pool_name: u8) -> Result<()> {
+        require!(ctx.accounts.pool.pool_data == Pubkey::default(), ErrorCode::PoolDataAlreadyCreated);
+        let mut pool_data = ctx.accounts.pool_data.load_init()?;
+        pool_data.pool = pool_name;
+        ctx.accounts.pool.pool_data = ctx.accounts.pool_data.key();
+
+        Ok(())
+    }
+
+    // Attach the pool's share mint, the receipt token for its deposits. It is created in the same instruction, with
+    // the pool as mint authority. Deposits and withdrawals need it from then on, see the shares module.
+    pub fn create_share_mint(ctx: Context<CreateShareMint>, _pool_name: u8) -> Result<()> {
+        require!(ctx.accounts.pool.share_mint == Pubkey::default(), ErrorCode::ShareMintAlreadyCreated);
+        let pool = &mut ctx.accounts.pool;
+        pool.share_mint = ctx.accounts.share_mint.key();
+        pool.share_rate = pool.exchange_rate();
+
+        Ok(())
+    }
+
+    // Record the pool totals at the end of a game in the pool's snapshot history
+    pub fn snapshot_pool(ctx: Context<SnapshotPool>, game_id: u64, _pool_name: u8) -> Result<()> {
+        let clock: Clock = Clock::get().unwrap();
+        require!(clock.unix_timestamp >= ctx.accounts.game.end_time, ErrorCode::GameNotOver);
+        let pool = &ctx.accounts.pool;
+        ctx.accounts.pool_data.load_mut()?.record_snapshot(PoolSnapshot {
+            game_id,
+            total_deposit: pool.total_deposit,
+            user_count: pool.user_count,
+            average_prediction: pool.average_prediction,
+        })
+    }
+
+    // Start a new game. Game ids start at 1 and are used to track which games a user has been paid out for.
+    pub fn start_game(ctx: Context<StartGame>, game_id: u64) -> Result<()> {
+        require!(game_id > 0, ErrorCode::InvalidGameId);
+        let clock: Clock = Clock::get().unwrap();
+        let game = &mut ctx.accounts.game;
+        game.game_id = game_id;
+        game.start_time = clock.unix_timestamp;
+        game.end_time = clock.unix_timestamp + GAME_DURATION_IN_SECS;
+        game.winning_pool = 0;
+        game.winning_amount = 0.0;
+        game.total_prize = 0.0;
+        game.total_paid = 0.0;
+        game.sponsored_prize = 0.0;
+        game.sponsor_count = 0;
+        game.compounded_prize = 0.0;
+        game.winning_weight = 0.0;
+        game.harvested_mints = 0;
+        game.prize_mint = Pubkey::default();
+        game.version = migrate::GAME_VERSION;
+
+        emit!(GameStartedEvent { game_id, start_time: game.start_time, end_time: game.end_time });
+
+        Ok(())
+    }
+
+    // Cancel a game that has not been settled by closing its account, so the game id can be started again. Games
+    // that already hold sponsored or harvested prize can't be cancelled since that prize is owed to the winners.
+    pub fn cancel_game(ctx: Context<CancelGame>, game_id: u64) -> Result<()> {
+        let game = &ctx.accounts.game;
+        require!(game.winning_pool == 0, ErrorCode::GameAlreadySettled);
+        require!(game.total_prize == 0.0, ErrorCode::GameHasPrize);
+
+        emit!(GameCancelledEvent { game_id });
+
+        Ok(())
+    }
+
+    // Record the winning pool of a finished game. The prize is what fund_prize and harvest_yield put in the pool
+    // wallets for it, payouts for the game are bounded by it. The prize weight of the winning pool is recorded as it
+    // is now, so the prize shares add up whatever happens to the pool while the game is being paid out, see
+    // accounting::checkpoint_weight. Locks ending with the game are only released from now on, so the weight has
+    // their boost, see the lockup module.
+    pub fn settle_game(ctx: Context<SettleGame>, game_id: u64, winning_pool: u8) -> Result<()> {
+        Pools::from(winning_pool)?;
+        let clock: Clock = Clock::get().unwrap();
+        let game = &mut ctx.accounts.game;
+        require!(game.winning_pool == 0, ErrorCode::GameAlreadySettled);
+        require!(clock.unix_timestamp >= game.end_time, ErrorCode::GameNotOver);
         let pool = &mut ctx.accounts.pool;
-        pool.is_initialized = true;
-        pool.last_update_timestamp = clock.unix_timestamp;
-        pool.total_deposit = 0.00;
-        pool.user_count = 0;
-        pool.name = pool_enum.to_code();
-        pool.average_prediction = 0.0;
+        game.winning_pool = winning_pool;
+        game.winning_weight = pool.prize_weight();
+        pool.won_game_id = game_id;
+        let config = &mut ctx.accounts.config;
+        config.settled_until = config.settled_until.max(game.end_time);
+
+        emit!(GameSettledEvent { game_id, winning_pool, total_prize: game.total_prize });
+
+        Ok(())
+    }
+
+    // Let anyone sponsor a game by adding tokens to its prize. Sponsored tokens are not a deposit and are paid out
+    // to the winners like the rest of the prize. Every contribution is recorded in its own Sponsorship account.
+    // amount is in base units of the mint, with a transfer fee the prize grows by what the pool wallet received.
+    pub fn fund_prize(ctx: Context<FundPrize>, game_id: u64, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidPrizeAmount);
+        require!(ctx.accounts.game.winning_pool == 0, ErrorCode::GameAlreadySettled);
+        let allowed = *ctx.accounts.config.allowed_mint(&ctx.accounts.mint_address.key())?;
+        let clock: Clock = Clock::get().unwrap();
+
+        // Transfer amount from sponsor wallet to pool wallet
+        let balance_before = ctx.accounts.pool_token_account.amount;
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.sponsor_token_account.to_account_info(),
+            mint: ctx.accounts.mint_address.to_account_info(),
+            to: ctx.accounts.pool_token_account.to_account_info(),
+            authority: ctx.accounts.sponsor.to_account_info(),
+        };
+        token_interface::transfer_checked(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+            amount,
+            ctx.accounts.mint_address.decimals,
+        )?;
+        ctx.accounts.pool_token_account.reload()?;
+        let received = ctx.accounts.pool_token_account.amount - balance_before;
+
+        let sponsorship = &mut ctx.accounts.sponsorship;
+        sponsorship.game_id = game_id;
+        sponsorship.sponsor = ctx.accounts.sponsor.key();
+        sponsorship.amount = received;
+        sponsorship.timestamp = clock.unix_timestamp;
+        sponsorship.mint = allowed.mint;
+
+        let game = &mut ctx.accounts.game;
+        game.add_prize(&allowed.mint, allowed.to_amount(received))?;
+        game.sponsored_prize += allowed.to_amount(received);
+        game.sponsor_count += 1;
+
+        Ok(())
+    }
+
+    // Lend idle funds sitting in a mint's pool wallet out to the yield source. amount is in base units of the mint.
+    pub fn deposit_idle_funds(ctx: Context<ManageYield>, _pool_name: u8, amount: u64) -> Result<()> {
+        require!(amount <= ctx.accounts.pool_token_account.amount, ErrorCode::InsufficientBalance);
+        let mint = ctx.accounts.mint_address.key();
+        let bump = *ctx.bumps.get("pool_token_account").unwrap();
+        let inner = vec![b"pool_wallet".as_ref(), mint.as_ref(), std::slice::from_ref(&bump)];
+        let outer = vec![inner.as_slice()];
+        ctx.accounts.yield_source()?.deposit(amount, outer.as_slice())?;
+
+        let allowed = ctx.accounts.config.allowed_mint_mut(&mint)?;
+        allowed.invested += amount;
+        ctx.accounts.pool.invested += allowed.to_amount(amount);
 
         Ok(())
     }
 
+    // Bring lent out funds back into the pool wallet, e.g. to cover withdrawals
+    pub fn withdraw_idle_funds(ctx: Context<ManageYield>, _pool_name: u8, amount: u64) -> Result<()> {
+        let mint = ctx.accounts.mint_address.key();
+        let allowed = ctx.accounts.config.allowed_mint_mut(&mint)?;
+        require!(amount <= allowed.invested, ErrorCode::InsufficientBalance);
+        require!(allowed.to_amount(amount) <= ctx.accounts.pool.invested, ErrorCode::InsufficientBalance);
+        allowed.invested -= amount;
+        ctx.accounts.pool.invested -= allowed.to_amount(amount);
+
+        let bump = *ctx.bumps.get("pool_token_account").unwrap();
+        let inner = vec![b"pool_wallet".as_ref(), mint.as_ref(), std::slice::from_ref(&bump)];
+        let outer = vec![inner.as_slice()];
+        ctx.accounts.yield_source()?.withdraw(amount, outer.as_slice())
+    }
+
+    // Harvest the interest the pools earned during the game and add it to the game prize. Has to be called after
+    // the game is over and before it is settled, once per mint. Only yield in the prize mint can go in a funded game.
+    pub fn harvest_yield(ctx: Context<HarvestYield>, _game_id: u64) -> Result<()> {
+        let clock: Clock = Clock::get().unwrap();
+        require!(ctx.accounts.game.winning_pool == 0, ErrorCode::GameAlreadySettled);
+        require!(clock.unix_timestamp >= ctx.accounts.game.end_time, ErrorCode::GameNotOver);
+
+        let mint = ctx.accounts.mint_address.key();
+        let allowed = *ctx.accounts.config.allowed_mint(&mint)?;
+        let harvested_bit = 1 << ctx.accounts.config.mint_index(&mint)?;
+        require!(ctx.accounts.game.harvested_mints & harvested_bit == 0, ErrorCode::AlreadyHarvested);
+        let bump = *ctx.bumps.get("pool_token_account").unwrap();
+        let inner = vec![b"pool_wallet".as_ref(), mint.as_ref(), std::slice::from_ref(&bump)];
+        let outer = vec![inner.as_slice()];
+        let balance_before = ctx.accounts.pool_token_account.amount;
+        ctx.accounts.yield_source()?.harvest(outer.as_slice())?;
+        ctx.accounts.pool_token_account.reload()?;
+        let harvested = ctx.accounts.pool_token_account.amount - balance_before;
+
+        let game = &mut ctx.accounts.game;
+        if harvested > 0 {
+            game.add_prize(&mint, allowed.to_amount(harvested))?;
+        }
+        game.harvested_mints |= harvested_bit;
+
+        Ok(())
+    }
+
+    // Create the User account and its user wallet. The user wallet's mint is the mint the user deposits and gets
+    // paid in, so it has to be an allowed one.
     pub fn create_user(ctx: Context<CreateUser>) -> Result<()> {
-        let user = &mut ctx.accounts.user;
-        user.balance = 0.0;
-        user.current_average_balance = 0.0;
-        user.current_weighted_balance = 0.0;
-        user.current_weighted_days = GAME_DURATION_IN_DAYS;
-        user.last_prediction = 0.0;
+        ctx.accounts.config.allowed_mint(&ctx.accounts.mint_address.key())?;
+        init_user(&mut ctx.accounts.user);
+
+        Ok(())
+    }
+
+    // Create only the User account, for users depositing from and getting paid into their own associated token
+    // account, see the custody module
+    pub fn create_user_without_wallet(ctx: Context<CreateUserWithoutWallet>) -> Result<()> {
+        init_user(&mut ctx.accounts.user);
 
         Ok(())
     }
 
+    // Bring accounts created by an earlier version of the program to the current layout, growing them if needed.
+    // Anyone can migrate an account, the payer covers the rent for the extra space. Migrating an account that is
+    // already current is a no-op.
+    pub fn migrate_user(ctx: Context<MigrateUser>, _user_key: Pubkey) -> Result<()> {
+        let info = ctx.accounts.user.to_account_info();
+        let user = migrate::upgrade_user(&info.try_borrow_data()?)?;
+        migrate::write_upgraded(
+            &info,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &user,
+            User::LEN)
+    }
+
+    pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
+        let info = ctx.accounts.config.to_account_info();
+        let config = migrate::upgrade_config(&info.try_borrow_data()?)?;
+        migrate::write_upgraded(
+            &info,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &config,
+            Config::LEN)
+    }
+
+    pub fn migrate_pool(ctx: Context<MigratePool>, _pool_name: u8) -> Result<()> {
+        let info = ctx.accounts.pool.to_account_info();
+        let pool = migrate::upgrade_pool(&info.try_borrow_data()?)?;
+        migrate::write_upgraded(
+            &info,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &pool,
+            Pool::LEN)
+    }
+
+    // Games created before the reserved space were exactly as big as their layout, so a game running during an
+    // upgrade that adds a field needs this before it can be settled or paid out
+    pub fn migrate_game(ctx: Context<MigrateGame>, _game_id: u64) -> Result<()> {
+        let info = ctx.accounts.game.to_account_info();
+        let game = migrate::upgrade_game(&info.try_borrow_data()?)?;
+        migrate::write_upgraded(
+            &info,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &game,
+            Game::LEN)
+    }
+
     // Tally up total for all the pools, pick the pool with the average prediction closest to the actual prediction
     // Calculate the total prize (interest)
     // Take 80% of total interest as the prize. Pick one winner for 10% of the prize
@@ -138,66 +628,293 @@ pub mod coin_war {
         //     }
         // }
 
-        // choose the pool_predictions with the smallest % delta to pool_coin_prices and mark as winning index
-        let mut winning_index = 0;
-        let mut current_smallest_delta = 100000.000;
-        for i in 0..pool_predictions.len() {
-            let delta = (pool_predictions[i] - pool_coin_prices[i]).abs();
-            if delta < current_smallest_delta {
-                current_smallest_delta = delta;
-                winning_index = i;
-            }
-        }    
-
-        winning_index += 1;
-        let winning_pool: String = Pools::code_to_string(winning_index as u8);
+        // choose the pool_predictions with the smallest % delta to pool_coin_prices as the winning pool
+        let winning_index = math::select_winning_pool(&pool_predictions, &pool_coin_prices);
+        let winning_pool: String = Pools::code_to_string(winning_index);
 
         Ok(winning_pool)
     }   
 
     // Calculate percent of the pool the user balance represents and pay out according
-    // Takes in one user at a time
-    pub fn pay_winning_pool_user(ctx: Context<PayWinner>, user_key: Pubkey, pool_name: String, prize_amount: f64) -> Result<()> {
-        let total_deposit = ctx.accounts.pool.total_deposit.clone();
-        let user_balance = ctx.accounts.user.balance.clone();
-        let percentage_of_pool = user_balance / total_deposit;
-        let prize = percentage_of_pool * prize_amount;
-
-        let result = transfer_token_out_of_pool(
-            &mut ctx.accounts.pool_token_account, 
-            ctx.accounts.token_program.to_account_info(), 
-            ctx.accounts.user_token_account.to_account_info(), 
-            ctx.accounts.owner.to_account_info(), 
-            pool_name, 
-            prize as u64);
-
-        if result.is_ok() {
-            // reset user balances
-            let user = &mut ctx.accounts.user;
-            user.current_average_balance = user.balance.clone();
-            user.current_weighted_balance = user.balance.clone();
-            user.current_weighted_days = GAME_DURATION_IN_DAYS;
-            user.last_prediction = 0.0;
+    // Takes in one user at a time. Each user can only be paid once per game and the total paid out for a game
+    // can never exceed the prize recorded when the game was settled. Users who set_payout_preference to auto-compound
+    // get the prize added to their balance instead, like a deposit without its shares, see issue_shares.
+    pub fn pay_winning_pool_user(
+        mut ctx: Context<PayWinner>,
+        user_key: Pubkey,
+        pool_name: u8,
+        game_id: u64,
+    ) -> Result<()> {
+        let game = &ctx.accounts.game;
+        require!(game.winning_pool != 0, ErrorCode::GameNotSettled);
+        require!(game.winning_pool == ctx.accounts.pool.name, ErrorCode::NotWinningPool);
+        require!(ctx.accounts.user.last_paid_game_id < game.game_id, ErrorCode::AlreadyPaid);
+        custody::custody_of(&ctx.accounts.user_token_account, &user_key, &ctx.accounts.user.key())?;
+        game.check_prize_mint(&ctx.accounts.mint_address.key())?;
+
+        // locked deposits weigh more, see the lockup module
+        let weight = accounting::user_weight(&ctx.accounts.user, &ctx.accounts.pool, game);
+        let prize = math::prize_share(weight, accounting::winning_weight(&ctx.accounts.pool, game), game.total_prize);
+        require!(game.total_paid + prize <= game.total_prize, ErrorCode::PrizeExceeded);
+        let mint = ctx.accounts.mint_address.key();
+
+        if accounting::compounds(&ctx.accounts.user, &ctx.accounts.pool, &mint) {
+            let accounts = &mut ctx.accounts;
+            record_compound(
+                &mut accounts.config,
+                &mint,
+                &mut accounts.user,
+                &mut accounts.pool,
+                &mut accounts.game,
+                user_key,
+                prize)?;
+        } else {
+            let allowed = ctx.accounts.config.allowed_mint(&mint)?;
+            let units = allowed.prize_units(prize, ctx.accounts.pool_token_account.amount)?;
+            let result = transfer_token_out_of_pool(
+                &mut ctx.accounts.pool_token_account, 
+                &ctx.accounts.mint_address,
+                ctx.accounts.token_program.to_account_info(), 
+                ctx.accounts.user_token_account.to_account_info(), 
+                *ctx.bumps.get("pool_token_account").unwrap(), 
+                units);
+
+            require!(result.is_ok(), ErrorCode::PaymentFailed);
+
+            // record the payout against the game
+            ctx.accounts.game.total_paid += prize;
+
+            emit!(PrizePaidEvent { game_id, user: user_key, pool: pool_name, amount: prize as u64 });
         }
 
+        // reset user balances and mark as paid for this game
+        accounting::reset_after_payout(&mut ctx.accounts.user, game_id);
+
         Ok(())
     }
     
-    // Allow user to update prediction (especially when a new game starts)
-    pub fn make_prediction(ctx: Context<MakePrediction>, prediction: f64) -> Result<()> {
-        let user = &mut ctx.accounts.user;
+    // Pay a batch of winning pool users in one transaction. remaining_accounts holds one (User, user token account)
+    // pair per entry in user_keys, in the same order. Users already paid for game_id are skipped so the instruction
+    // can be retried safely if a previous batch partially landed.
+    pub fn pay_winners_batch<'info>(
+        mut ctx: Context<'_, '_, '_, 'info, PayWinnersBatch<'info>>,
+        game_id: u64,
+        user_keys: Vec<Pubkey>,
+        pool_name: u8,
+    ) -> Result<()> {
+        require!(ctx.remaining_accounts.len() == user_keys.len() * 2, ErrorCode::BatchAccountsMismatch);
+        require!(ctx.accounts.game.winning_pool != 0, ErrorCode::GameNotSettled);
+        require!(ctx.accounts.game.winning_pool == pool_name, ErrorCode::NotWinningPool);
+        let prize_amount = ctx.accounts.game.total_prize;
+
+        let total_weight = accounting::winning_weight(&ctx.accounts.pool, &ctx.accounts.game);
+        let mint_key = ctx.accounts.mint_address.key();
+        ctx.accounts.game.check_prize_mint(&mint_key)?;
+        let allowed = *ctx.accounts.config.allowed_mint(&mint_key)?;
+        let pool_wallet_bump = *ctx.bumps.get("pool_token_account").unwrap();
+
+        for (i, user_key) in user_keys.iter().enumerate() {
+            let user_info = &ctx.remaining_accounts[i * 2];
+            let user_token_info = &ctx.remaining_accounts[i * 2 + 1];
+
+            // Make sure the User account is the PDA of the given wallet and that payouts land in one of its token
+            // accounts, see the custody module
+            let (user_pda, _) = Pubkey::find_program_address(&[b"user".as_ref(), user_key.as_ref()], ctx.program_id);
+            require!(user_info.key() == user_pda, ErrorCode::InvalidUserAccount);
+            require!(user_info.is_writable && user_token_info.is_writable, ErrorCode::InvalidUserAccount);
+
+            let mut user: Account<'info, User> = Account::try_from(user_info)?;
+            let user_token_account: InterfaceAccount<'info, TokenAccount> =
+                InterfaceAccount::try_from(user_token_info)?;
+            custody::custody_of(&user_token_account, user_key, &user_pda)?;
+            require!(user_token_account.mint == mint_key, ErrorCode::InvalidUserTokenAccount);
+
+            // Already paid for this game, skip
+            if user.last_paid_game_id >= game_id {
+                continue;
+            }
+
+            let weight = accounting::user_weight(&user, &ctx.accounts.pool, &ctx.accounts.game);
+            let prize = math::prize_share(weight, total_weight, prize_amount);
+            require!(ctx.accounts.game.total_paid + prize <= prize_amount, ErrorCode::PrizeExceeded);
+
+            if accounting::compounds(&user, &ctx.accounts.pool, &mint_key) {
+                let accounts = &mut ctx.accounts;
+                record_compound(
+                    &mut accounts.config,
+                    &mint_key,
+                    &mut user,
+                    &mut accounts.pool,
+                    &mut accounts.game,
+                    *user_key,
+                    prize)?;
+            } else {
+                let units = allowed.prize_units(prize, ctx.accounts.pool_token_account.amount)?;
+                transfer_token_out_of_pool(
+                    &mut ctx.accounts.pool_token_account,
+                    &ctx.accounts.mint_address,
+                    ctx.accounts.token_program.to_account_info(),
+                    user_token_info.clone(),
+                    pool_wallet_bump,
+                    units)?;
+                ctx.accounts.game.total_paid += prize;
+
+                emit!(PrizePaidEvent { game_id, user: *user_key, pool: pool_name, amount: prize as u64 });
+            }
+
+            // reset user balances and mark as paid for this game
+            accounting::reset_after_payout(&mut user, game_id);
+            user.exit(ctx.program_id)?;
+        }
+
+        Ok(())
+    }
+
+    // Hand the whole prize of a game to the winning pool through its exchange rate instead of paying it out. It stays
+    // in the pool wallets and every balance in the pool grows by its prize share, see the shares module. Only for
+    // games none of the prize has been paid out of yet.
+    pub fn distribute_prize(ctx: Context<DistributePrize>, game_id: u64, pool_name: u8) -> Result<()> {
+        let game = &mut ctx.accounts.game;
+        require!(game.winning_pool != 0, ErrorCode::GameNotSettled);
+        require!(game.winning_pool == pool_name, ErrorCode::NotWinningPool);
+        require!(game.total_paid == 0.0, ErrorCode::AlreadyPaid);
+        // the prize goes to the exchange rate of every balance, so they all have to be held in the prize mint
+        let config = &ctx.accounts.config;
+        require!(config.mints.iter().all(|a| game.check_prize_mint(&a.mint).is_ok()), ErrorCode::PrizeMintMismatch);
+
         let pool = &mut ctx.accounts.pool;
-        
-        // Remove previous prediction and update
-        let total_prediction = pool.average_prediction * pool.user_count as f64;
+        pool.distribute(game.total_prize)?;
+        game.total_paid = game.total_prize;
+
+        emit!(PrizeDistributedEvent {
+            game_id,
+            pool: pool_name,
+            amount: game.total_prize,
+            exchange_rate: pool.exchange_rate(),
+        });
+
+        Ok(())
+    }
+
+    // Merkle payout mode for games with too many winners to pay one by one. The admin computes every (user, amount)
+    // off chain and posts the root, then users claim their own prize with claim_with_proof. max_total_claim is
+    // capped by what is left of the prize recorded in the Game account.
+    pub fn create_distributor(
+        ctx: Context<CreateDistributor>,
+        game_id: u64,
+        merkle_root: [u8; 32],
+        max_total_claim: u64,
+        max_num_nodes: u64,
+    ) -> Result<()> {
+        let game = &ctx.accounts.game;
+        require!(game.winning_pool != 0, ErrorCode::GameNotSettled);
+        require!(game.total_paid + max_total_claim as f64 <= game.total_prize, ErrorCode::PrizeExceeded);
+
+        let distributor = &mut ctx.accounts.distributor;
+        distributor.game_id = game_id;
+        distributor.merkle_root = merkle_root;
+        distributor.max_total_claim = max_total_claim;
+        distributor.max_num_nodes = max_num_nodes;
+        distributor.total_amount_claimed = 0;
+        distributor.num_nodes_claimed = 0;
+        distributor.claimed_bitmap = vec![0; Distributor::bitmap_len(max_num_nodes)];
 
-        let new_total_prediction = total_prediction - user.last_prediction + prediction;
-        user.last_prediction = prediction;
-        pool.average_prediction = new_total_prediction / pool.user_count as f64;
+        Ok(())
+    }
+
+    // Claim a prize from a merkle distributor. The leaf is merkle::leaf(index, claimant, amount) and each index can
+    // only be claimed once. Like the prize, amount is in AMOUNT_DECIMALS units.
+    pub fn claim_with_proof(
+        ctx: Context<ClaimWithProof>,
+        game_id: u64,
+        pool_name: u8,
+        index: u64,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let claimant = ctx.accounts.claimant.key();
+        let distributor = &mut ctx.accounts.distributor;
+        // the same checks as create_distributor, for the game the distributor was created for
+        require!(ctx.accounts.game.winning_pool != 0, ErrorCode::GameNotSettled);
+        require!(distributor.game_id == ctx.accounts.game.game_id, ErrorCode::InvalidGameId);
+        require!(index < distributor.max_num_nodes, ErrorCode::InvalidProof);
+        require!(!distributor.is_claimed(index), ErrorCode::AlreadyClaimed);
+        require!(ctx.accounts.game.winning_pool == pool_name, ErrorCode::NotWinningPool);
+        require!(ctx.accounts.user.last_paid_game_id < game_id, ErrorCode::AlreadyPaid);
+        custody::custody_of(&ctx.accounts.user_token_account, &claimant, &ctx.accounts.user.key())?;
+        ctx.accounts.game.check_prize_mint(&ctx.accounts.mint_address.key())?;
+
+        let leaf = merkle::leaf(index, &claimant, amount);
+        require!(merkle::verify_proof(&proof, distributor.merkle_root, leaf), ErrorCode::InvalidProof);
+
+        // Hard caps, both for the root and for the game as a whole
+        require!(distributor.total_amount_claimed + amount <= distributor.max_total_claim, ErrorCode::PrizeExceeded);
+        require!(ctx.accounts.game.total_paid + amount as f64 <= ctx.accounts.game.total_prize, ErrorCode::PrizeExceeded);
+
+        distributor.set_claimed(index);
+        distributor.total_amount_claimed += amount;
+        distributor.num_nodes_claimed += 1;
+
+        let allowed = ctx.accounts.config.allowed_mint(&ctx.accounts.mint_address.key())?;
+        let units = allowed.prize_units(amount as f64, ctx.accounts.pool_token_account.amount)?;
+        transfer_token_out_of_pool(
+            &mut ctx.accounts.pool_token_account,
+            &ctx.accounts.mint_address,
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.user_token_account.to_account_info(),
+            *ctx.bumps.get("pool_token_account").unwrap(),
+            units)?;
+
+        let game = &mut ctx.accounts.game;
+        game.total_paid += amount as f64;
+
+        // reset user balances and mark as paid for this game
+        accounting::reset_after_payout(&mut ctx.accounts.user, game_id);
+
+        emit!(PrizePaidEvent { game_id, user: claimant, pool: pool_name, amount });
+
+        Ok(())
+    }
+
+    // Allow user to update prediction (especially when a new game starts)
+    pub fn make_prediction(ctx: Context<MakePrediction>, pool_name: u8, prediction: f64) -> Result<()> {
+        accounting::apply_prediction(&mut ctx.accounts.user, &mut ctx.accounts.pool, prediction)?;
 
+        let clock: Clock = Clock::get().unwrap();
+        emit!(PredictionEvent {
+            user: ctx.accounts.owner.key(),
+            pool: pool_name,
+            prediction,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Choose between getting prizes paid out to the user's token account and having them added to the balance for the
+    // next game. Prize distribution and merkle claims are not affected.
+    pub fn set_payout_preference(ctx: Context<SetPayoutPreference>, auto_compound: bool) -> Result<()> {
+        ctx.accounts.user.auto_compound = auto_compound;
         Ok(())
     }
 
+    // Mint the shares of a balance deposited before the pool had a share mint, or of compounded prizes
+    pub fn issue_shares(ctx: Context<IssueShares>, pool_name: u8) -> Result<()> {
+        let user = &mut ctx.accounts.user;
+        require!(user.pool == pool_name && user.balance > 0.0, ErrorCode::UserNotInPool);
+        let shares = user.unissued_shares();
+        user.shares += shares;
+
+        shares::mint_shares(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.share_mint.to_account_info(),
+            ctx.accounts.user_share_account.to_account_info(),
+            ctx.accounts.pool.to_account_info(),
+            pool_name,
+            *ctx.bumps.get("pool").unwrap(),
+            shares)
+    }
+
     // Transfer from pool wallet to user wallet
     // Update user balance
     // Update pool balance
@@ -206,56 +923,48 @@ pub mod coin_war {
     // Update average balance for user
     // Create new transaction
     // Only allowed to deposit in one pool
-    pub fn withdraw(ctx: Context<Withdraw>, amount: f64) -> Result<()> {
+    pub fn withdraw(mut ctx: Context<Withdraw>, amount: f64, pool_name: u8) -> Result<()> {
         let clock: Clock = Clock::get().unwrap();
-        let pool = &mut ctx.accounts.pool;
-        let pool_number = pool.name;
-        let pool_name : String = Pools::code_to_string(pool_number);
-
-        // Check if theres enough money
-        let user_balance = ctx.accounts.user.balance.clone();
-        require!(user_balance < amount, ErrorCode::InsufficientBalance); 
+        let user_key = ctx.accounts.user.key();
+        custody::custody_of(&ctx.accounts.user_token_account, &ctx.accounts.initializer.key(), &user_key)?;
+        let accounts = &mut ctx.accounts;
+        let (base_units, shares) = record_withdrawal(
+            &mut accounts.config,
+            &accounts.mint_address.key(),
+            &mut accounts.user,
+            &mut accounts.pool,
+            accounts.pool_data.as_ref(),
+            accounts.initializer.key(),
+            amount,
+            None)?;
+        shares::burn_shares(
+            accounts.share_token_program.to_account_info(),
+            accounts.share_mint.to_account_info(),
+            &accounts.user_share_account,
+            accounts.initializer.to_account_info(),
+            shares)?;
 
         let result = transfer_token_out_of_pool(
             &mut ctx.accounts.pool_token_account, 
+            &ctx.accounts.mint_address,
             ctx.accounts.token_program.to_account_info(), 
             ctx.accounts.user_token_account.to_account_info(), 
-            ctx.accounts.initializer.to_account_info(), 
-            pool_name, 
-            amount as u64);
+            *ctx.bumps.get("pool_token_account").unwrap(), 
+            base_units);
 
         require!(result.is_ok(), ErrorCode::PaymentFailed);
 
-        // Update user balance
+        // Create new transaction
         let user = &mut ctx.accounts.user;
-        user.balance = user.balance - amount;
-
-        // Update pool balance  
-        let pool = &mut ctx.accounts.pool;
-        pool.total_deposit = pool.total_deposit - amount;
-
-        // Remove previous prediction and update
-        let total_prediction = pool.average_prediction * pool.user_count as f64;
-
-        // Update pool count if needed
-        if user.balance <= 0.0 {
-            pool.user_count -= 1;
-        }
+        record_withdrawal_transaction(&mut ctx.accounts.transaction, user, amount, clock.unix_timestamp);
 
-        let new_total_prediction = total_prediction - user.last_prediction;
-        user.last_prediction = 0.0;
-        pool.average_prediction = new_total_prediction / pool.user_count as f64;
-
-        // Update average balance for user (user average balance is reset to current balance)
-        user.current_average_balance = user.balance;
-        user.current_weighted_balance = user.balance * GAME_DURATION_IN_DAYS as f64;
-        user.current_weighted_days = GAME_DURATION_IN_DAYS;
-
-        // Create new transaction
-        let transaction = &mut ctx.accounts.transaction;
-        transaction.amount = amount;
-        transaction.transaction_type = TransactionType::Withdrawal.to_code();
-        transaction.timestamp = clock.unix_timestamp;
+        emit!(WithdrawEvent {
+            user: ctx.accounts.initializer.key(),
+            pool: pool_name,
+            amount,
+            balance: user.balance,
+            timestamp: clock.unix_timestamp,
+        });
 
         Ok(())
     }
@@ -265,63 +974,72 @@ pub mod coin_war {
     // Update prediction
     // Update pool balance
     // Zero out average balance?
-    pub fn deposit(ctx: Context<Deposit>, amount: f64, prediction: f64) -> Result<()> {
-        require!(amount >= MINIMUM_DEPOSIT, ErrorCode::DepositInsufficient);
-        let clock: Clock = Clock::get().unwrap();
-        let user = &mut ctx.accounts.user;
-        let key = user.key();
-
+    pub fn deposit(mut ctx: Context<Deposit>, amount: f64, pool_name: u8, prediction: f64) -> Result<()> {
+        require!(!ctx.accounts.config.paused, ErrorCode::ProgramPaused);
+        let mint = ctx.accounts.mint_address.key();
+        let allowed = *ctx.accounts.config.allowed_mint(&mint)?;
+        let base_units = allowed.deposit_units(amount)?;
+        let initializer_key = ctx.accounts.initializer.key();
+        let bump = *ctx.bumps.get("user").unwrap();
+        let user_key = ctx.accounts.user.key();
+        let custody = custody::custody_of(&ctx.accounts.user_token_account, &initializer_key, &user_key)?;
+
+        // The user wallet is owned by the user PDA, so sign with the user seeds. The associated token account is
+        // the signer's own.
         let inner = vec![
-            b"user_wallet".as_ref(),
-            key.as_ref(),
+            b"user".as_ref(),
+            initializer_key.as_ref(),
+            std::slice::from_ref(&bump),
         ];
         let outer = vec![inner.as_slice()];
+        let (authority, signer_seeds) = match custody {
+            Custody::UserWallet => (ctx.accounts.user.to_account_info(), outer.as_slice()),
+            Custody::AssociatedTokenAccount => (ctx.accounts.initializer.to_account_info(), &[][..]),
+        };
 
-        // Transfer amount from user wallet to pool wallet
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.user_token_account.to_account_info().clone(), // user wallet
-            to: ctx.accounts.pool_token_account.to_account_info().clone(), // pool wallet
-            authority: ctx.accounts.initializer.to_account_info().clone(),
+        // Transfer amount from the user's token account to pool wallet
+        let balance_before = ctx.accounts.pool_token_account.amount;
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.user_token_account.to_account_info(),
+            mint: ctx.accounts.mint_address.to_account_info(),
+            to: ctx.accounts.pool_token_account.to_account_info(), // pool wallet
+            authority,
         };
 
-        token::transfer(
+        token_interface::transfer_checked(
             CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info().clone(), 
+                ctx.accounts.token_program.to_account_info(), 
                 cpi_accounts, 
-                outer.as_slice(),
+                signer_seeds,
             ),
-            amount as u64
+            base_units,
+            ctx.accounts.mint_address.decimals,
         )?;
 
-        // Update user balance
-        let user = &mut ctx.accounts.user;
-        user.balance = user.balance + amount;
-
-        // TODO: Update average balance for user
-        let total_value = user.current_weighted_balance;
-        let days_left = 4; // TODO: days left should be number of days left until game is over
-        let added_value = days_left as f64 * amount;
-        user.current_average_balance = (total_value + added_value) / (days_left as f64 + user.current_weighted_days as f64);
-        user.current_weighted_balance = total_value + added_value;
-        user.current_weighted_days = days_left + user.current_weighted_days;
-
-        // Update pool balance
-        let pool = &mut ctx.accounts.pool;
-        pool.total_deposit = pool.total_deposit + amount;
-
-        
-        // Remove previous prediction and update
-        let total_prediction = pool.average_prediction * pool.user_count as f64;
-
-        // Update pool count if user not in pool
-        if user.pool == 0 || user.pool.ne(&pool.name) {
-            user.pool = pool.name.clone();
-            pool.user_count += 1;
-        }
-
-        let new_total_prediction = total_prediction - user.last_prediction + prediction;
-        user.last_prediction = prediction;
-        pool.average_prediction = new_total_prediction / pool.user_count as f64;
+        // Mints with a transfer fee deliver less than was sent, the user is credited what the pool wallet received
+        ctx.accounts.pool_token_account.reload()?;
+        let (amount, base_units) = allowed.received(ctx.accounts.pool_token_account.amount - balance_before);
+
+        let accounts = &mut ctx.accounts;
+        let shares = record_deposit(
+            &mut accounts.config,
+            &mint,
+            &mut accounts.user,
+            &mut accounts.pool,
+            &accounts.pool_data,
+            initializer_key,
+            amount,
+            base_units,
+            prediction,
+            None)?;
+        shares::mint_shares(
+            accounts.share_token_program.to_account_info(),
+            accounts.share_mint.to_account_info(),
+            accounts.user_share_account.to_account_info(),
+            accounts.pool.to_account_info(),
+            pool_name,
+            *ctx.bumps.get("pool").unwrap(),
+            shares)?;
 
         // Create new transaction
         // let transaction = &mut ctx.accounts.transaction;
@@ -329,102 +1047,985 @@ pub mod coin_war {
         // transaction.transaction_type = TransactionType::Deposit.to_code();
         // transaction.timestamp = clock.unix_timestamp;
 
+        let clock: Clock = Clock::get().unwrap();
+        emit!(DepositEvent {
+            user: initializer_key,
+            pool: pool_name,
+            amount,
+            prediction,
+            balance: ctx.accounts.user.balance,
+            timestamp: clock.unix_timestamp,
+        });
+
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-#[instruction(amount: f64, pool_name: String)]
-pub struct Withdraw<'info> {
-    #[account(mut)]
-    pub initializer: Signer<'info>,
-    #[account(mut, seeds = [b"user".as_ref(), initializer.key().as_ref()], bump)]
-    pub user: Account<'info, User>,
-    #[account(
-        constraint=user_token_account.owner == user.key(),
-        constraint=user_token_account.mint == mint_address.key(),
-    )]
-    pub user_token_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub pool: Account<'info, Pool>,
-    #[account(
-        mut,
-        seeds = [b"pool_wallet".as_ref(), pool_name.as_ref()],
-        bump,
-    )]
-    pub pool_token_account: Account<'info, TokenAccount>,   
-    #[account(
-        init, 
-        payer = initializer, 
-        space = Transaction::LEN, 
-        seeds = [b"tx".as_ref(), user.key().as_ref(), pool.key().as_ref(), &user.txn_count.to_be_bytes()] 
-, bump)] 
-    pub transaction: Account<'info, Transaction>,
-    pub token_program: Program<'info, Token>,
-    pub mint_address: Box<Account<'info, Mint>>,
-    pub system_program: Program<'info, System>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub rent: Sysvar<'info, Rent>,
-}
+    // Deposit native SOL from the signer's wallet. The lamports are wrapped into the pool wallet of the native mint,
+    // so they are accounted for like a deposit of any other mint, which the native mint has to be added as.
+    pub fn deposit_sol(mut ctx: Context<DepositSol>, amount: f64, pool_name: u8, prediction: f64) -> Result<()> {
+        require!(!ctx.accounts.config.paused, ErrorCode::ProgramPaused);
+        let base_units = ctx.accounts.config.allowed_mint(&native_mint::ID)?.deposit_units(amount)?;
+        let initializer_key = ctx.accounts.initializer.key();
+        let pool_bump = *ctx.bumps.get("pool").unwrap();
+        let accounts = &mut ctx.accounts;
+        let shares = record_deposit(
+            &mut accounts.config,
+            &native_mint::ID,
+            &mut accounts.user,
+            &mut accounts.pool,
+            &accounts.pool_data,
+            initializer_key,
+            amount,
+            base_units,
+            prediction,
+            None)?;
+        shares::mint_shares(
+            accounts.token_program.to_account_info(),
+            accounts.share_mint.to_account_info(),
+            accounts.user_share_account.to_account_info(),
+            accounts.pool.to_account_info(),
+            pool_name,
+            pool_bump,
+            shares)?;
+
+        // Wrap: move the lamports into the wSOL pool wallet and have the token program add them to its amount
+        let cpi_accounts = system_program::Transfer {
+            from: accounts.initializer.to_account_info(),
+            to: accounts.pool_token_account.to_account_info(),
+        };
+        system_program::transfer(CpiContext::new(accounts.system_program.to_account_info(), cpi_accounts), base_units)?;
+        let cpi_accounts = SyncNative { account: accounts.pool_token_account.to_account_info() };
+        token::sync_native(CpiContext::new(accounts.token_program.to_account_info(), cpi_accounts))?;
 
-#[derive(Accounts)]
-#[instruction(amount: f64, pool_name: u8, prediction: f64)]
-pub struct Deposit<'info> {
-    #[account(mut)]
-    pub initializer: Signer<'info>,
-    #[account(mut, seeds = [b"user".as_ref(), initializer.key().as_ref()], bump)]
-    pub user: Account<'info, User>,
-    #[account(
-        mut,
-        constraint=user_token_account.owner == user.key(),
-        constraint=user_token_account.mint == mint_address.key(),
-    )]
-    pub user_token_account: Account<'info, TokenAccount>,
-    #[account(mut, seeds = [&[pool_name]], bump)]
-    pub pool: Account<'info, Pool>,
-    #[account(
-        mut,
-        constraint=pool_token_account.owner == pool.key(),
-        constraint=pool_token_account.mint == mint_address.key(),
-        seeds = [b"pool_wallet".as_ref()],
-        bump,
-    )]
-    pub pool_token_account: Account<'info, TokenAccount>,   
-    pub token_program: Program<'info, Token>,
-    pub mint_address: Box<Account<'info, Mint>>,
-    pub system_program: Program<'info, System>,
-}
+        let clock: Clock = Clock::get().unwrap();
+        emit!(DepositEvent {
+            user: initializer_key,
+            pool: pool_name,
+            amount,
+            prediction,
+            balance: ctx.accounts.user.balance,
+            timestamp: clock.unix_timestamp,
+        });
 
-#[derive(Accounts)]
-#[instruction(user_key: Pubkey, pool_name: String, prize_amount: f64)]
-pub struct PayWinner<'info> {
-    #[account(mut)]
-    pub owner: Signer<'info>,
-    #[account(mut, seeds = [b"user".as_ref(), user_key.as_ref()], bump)]
-    pub user: Account<'info, User>,
-    #[account(
-        mut,
-        seeds = [b"user_wallet".as_ref(), user_key.as_ref()],
-        bump
-    )]
-    pub user_token_account: Account<'info, TokenAccount>,   
-    #[account(mut, seeds = [pool_name.as_ref()], bump)]
-    pub pool: Account<'info, Pool>,
-    #[account(
-        mut,
-        constraint=pool_token_account.owner == owner.key(),
-        constraint=pool_token_account.mint == mint_address.key(),
-        seeds = [b"pool_wallet".as_ref()],
-        bump,
-    )]
-    pub pool_token_account: Account<'info, TokenAccount>,  
-    pub token_program: Program<'info, Token>,
-    pub mint_address: Box<Account<'info, Mint>>,
-    pub system_program: Program<'info, System>, 
-}
+        Ok(())
+    }
 
-#[derive(Accounts)]
-#[instruction(pool_name: u8, prediction: f64)]
+    // Withdraw as native SOL to the signer's wallet. The wSOL is moved out of the pool wallet into a temporary token
+    // account, which is closed to the signer right away to unwrap it.
+    pub fn withdraw_sol(mut ctx: Context<WithdrawSol>, amount: f64, pool_name: u8) -> Result<()> {
+        let clock: Clock = Clock::get().unwrap();
+        let initializer_key = ctx.accounts.initializer.key();
+        let pool_wallet_bump = *ctx.bumps.get("pool_token_account").unwrap();
+        let user_bump = *ctx.bumps.get("user").unwrap();
+        let accounts = &mut ctx.accounts;
+        let (base_units, shares) = record_withdrawal(
+            &mut accounts.config,
+            &native_mint::ID,
+            &mut accounts.user,
+            &mut accounts.pool,
+            accounts.pool_data.as_ref(),
+            initializer_key,
+            amount,
+            None)?;
+        shares::burn_shares(
+            accounts.token_program.to_account_info(),
+            accounts.share_mint.to_account_info(),
+            &accounts.user_share_account,
+            accounts.initializer.to_account_info(),
+            shares)?;
+
+        transfer_token_out_of_pool(
+            &mut accounts.pool_token_account,
+            &accounts.mint_address,
+            accounts.token_program.to_account_info(),
+            accounts.unwrap_account.to_account_info(),
+            pool_wallet_bump,
+            base_units)?;
+
+        // The unwrap account is owned by the user PDA, so sign with the user seeds
+        let inner = vec![b"user".as_ref(), initializer_key.as_ref(), std::slice::from_ref(&user_bump)];
+        let outer = vec![inner.as_slice()];
+        let cpi_accounts = CloseAccount {
+            account: accounts.unwrap_account.to_account_info(),
+            destination: accounts.initializer.to_account_info(),
+            authority: accounts.user.to_account_info(),
+        };
+        token::close_account(CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            cpi_accounts,
+            outer.as_slice(),
+        ))?;
+
+        record_withdrawal_transaction(&mut accounts.transaction, &mut accounts.user, amount, clock.unix_timestamp);
+
+        emit!(WithdrawEvent {
+            user: initializer_key,
+            pool: pool_name,
+            amount,
+            balance: accounts.user.balance,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Gift deposit out of the donor's token account into the position of beneficiary, which has to have created its
+    // User account, so nobody else can create it without the user wallet create_user sets up. The deposit is the
+    // beneficiary's like any other: it counts towards its weighted balance, the shares are minted to its share
+    // account and only it can withdraw. The donor is kept in the Transaction record. The donor's prediction is taken
+    // for beneficiaries without a balance, the others keep their own.
+    pub fn deposit_for_user(
+        mut ctx: Context<DepositForUser>,
+        beneficiary: Pubkey,
+        amount: f64,
+        pool_name: u8,
+        prediction: f64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.config.paused, ErrorCode::ProgramPaused);
+        let mint = ctx.accounts.mint_address.key();
+        let allowed = *ctx.accounts.config.allowed_mint(&mint)?;
+        let base_units = allowed.deposit_units(amount)?;
+        let prediction = if ctx.accounts.user.balance > 0.0 { ctx.accounts.user.last_prediction } else { prediction };
+
+        let balance_before = ctx.accounts.pool_token_account.amount;
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.donor_token_account.to_account_info(),
+            mint: ctx.accounts.mint_address.to_account_info(),
+            to: ctx.accounts.pool_token_account.to_account_info(),
+            authority: ctx.accounts.donor.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token_interface::transfer_checked(cpi_ctx, base_units, ctx.accounts.mint_address.decimals)?;
+        ctx.accounts.pool_token_account.reload()?;
+        let (amount, base_units) = allowed.received(ctx.accounts.pool_token_account.amount - balance_before);
+
+        let accounts = &mut ctx.accounts;
+        let shares = record_deposit(
+            &mut accounts.config,
+            &mint,
+            &mut accounts.user,
+            &mut accounts.pool,
+            &accounts.pool_data,
+            beneficiary,
+            amount,
+            base_units,
+            prediction,
+            None,
+        )?;
+        shares::mint_shares(
+            accounts.share_token_program.to_account_info(),
+            accounts.share_mint.to_account_info(),
+            accounts.beneficiary_share_account.to_account_info(),
+            accounts.pool.to_account_info(),
+            pool_name,
+            *ctx.bumps.get("pool").unwrap(),
+            shares,
+        )?;
+
+        let clock: Clock = Clock::get().unwrap();
+        let donor = accounts.donor.key();
+        record_gift_transaction(&mut accounts.transaction, &mut accounts.user, donor, amount, clock.unix_timestamp);
+
+        emit!(DepositEvent {
+            user: beneficiary,
+            pool: pool_name,
+            amount,
+            prediction,
+            balance: accounts.user.balance,
+            timestamp: clock.unix_timestamp,
+        });
+        emit!(GiftEvent { donor, user: beneficiary, pool: pool_name, amount, timestamp: clock.unix_timestamp });
+
+        Ok(())
+    }
+
+    // Let delegate deposit for the signer and withdraw what it deposited, see the delegation module
+    pub fn approve_delegate(ctx: Context<ApproveDelegate>, delegate: Pubkey) -> Result<()> {
+        let delegation = &mut ctx.accounts.delegation;
+        delegation.user = ctx.accounts.initializer.key();
+        delegation.delegate = delegate;
+
+        Ok(())
+    }
+
+    pub fn revoke_delegate(ctx: Context<RevokeDelegate>, _delegate: Pubkey) -> Result<()> {
+        require!(ctx.accounts.delegation.deposited <= 0.0, ErrorCode::DelegationInUse);
+
+        Ok(())
+    }
+
+    // Deposit out of the delegate's token account into the position of beneficiary, the wallet of the user that
+    // approved the delegate. The shares are minted to the delegate.
+    pub fn deposit_for(
+        mut ctx: Context<DepositFor>,
+        beneficiary: Pubkey,
+        amount: f64,
+        pool_name: u8,
+        prediction: f64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.config.paused, ErrorCode::ProgramPaused);
+        let mint = ctx.accounts.mint_address.key();
+        let allowed = *ctx.accounts.config.allowed_mint(&mint)?;
+        let base_units = allowed.deposit_units(amount)?;
+
+        let balance_before = ctx.accounts.pool_token_account.amount;
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.delegate_token_account.to_account_info(),
+            mint: ctx.accounts.mint_address.to_account_info(),
+            to: ctx.accounts.pool_token_account.to_account_info(),
+            authority: ctx.accounts.delegate.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token_interface::transfer_checked(cpi_ctx, base_units, ctx.accounts.mint_address.decimals)?;
+        ctx.accounts.pool_token_account.reload()?;
+        let (amount, base_units) = allowed.received(ctx.accounts.pool_token_account.amount - balance_before);
+
+        let accounts = &mut ctx.accounts;
+        let shares = record_deposit(
+            &mut accounts.config,
+            &mint,
+            &mut accounts.user,
+            &mut accounts.pool,
+            &accounts.pool_data,
+            beneficiary,
+            amount,
+            base_units,
+            prediction,
+            Some(&mut *accounts.delegation),
+        )?;
+        shares::mint_shares(
+            accounts.share_token_program.to_account_info(),
+            accounts.share_mint.to_account_info(),
+            accounts.delegate_share_account.to_account_info(),
+            accounts.pool.to_account_info(),
+            pool_name,
+            *ctx.bumps.get("pool").unwrap(),
+            shares,
+        )?;
+
+        let clock: Clock = Clock::get().unwrap();
+        emit!(DepositEvent {
+            user: beneficiary,
+            pool: pool_name,
+            amount,
+            prediction,
+            balance: accounts.user.balance,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Withdraw part of what the delegate deposited for beneficiary into destination, any token account of the mint.
+    // payer covers the Transaction record.
+    pub fn withdraw_to(mut ctx: Context<WithdrawTo>, beneficiary: Pubkey, amount: f64, pool_name: u8) -> Result<()> {
+        let clock: Clock = Clock::get().unwrap();
+        let pool_wallet_bump = *ctx.bumps.get("pool_token_account").unwrap();
+        let accounts = &mut ctx.accounts;
+        let (base_units, shares) = record_withdrawal(
+            &mut accounts.config,
+            &accounts.mint_address.key(),
+            &mut accounts.user,
+            &mut accounts.pool,
+            accounts.pool_data.as_ref(),
+            beneficiary,
+            amount,
+            Some(&mut *accounts.delegation),
+        )?;
+        shares::burn_shares(
+            accounts.share_token_program.to_account_info(),
+            accounts.share_mint.to_account_info(),
+            &accounts.delegate_share_account,
+            accounts.delegate.to_account_info(),
+            shares,
+        )?;
+
+        transfer_token_out_of_pool(
+            &mut accounts.pool_token_account,
+            &accounts.mint_address,
+            accounts.token_program.to_account_info(),
+            accounts.destination.to_account_info(),
+            pool_wallet_bump,
+            base_units,
+        )?;
+
+        record_withdrawal_transaction(&mut accounts.transaction, &mut accounts.user, amount, clock.unix_timestamp);
+
+        emit!(WithdrawEvent {
+            user: beneficiary,
+            pool: pool_name,
+            amount,
+            balance: accounts.user.balance,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Queue amount of the balance to be paid out once game_id, the game in progress, ends instead of right away.
+    // It leaves the active stake of the pool and its shares are burned at once, but the tokens stay in the pool wallet
+    // until complete_withdrawal, see accounting::apply_withdrawal_request.
+    pub fn request_withdrawal(
+        mut ctx: Context<RequestWithdrawal>,
+        _game_id: u64,
+        amount: f64,
+        pool_name: u8,
+    ) -> Result<()> {
+        let clock: Clock = Clock::get().unwrap();
+        let game = &ctx.accounts.game;
+        let in_progress = clock.unix_timestamp >= game.start_time && clock.unix_timestamp < game.end_time;
+        require!(in_progress, ErrorCode::GameNotInProgress);
+        let release_time = game.end_time;
+        let mint = ctx.accounts.mint_address.key();
+        let wallet = ctx.accounts.initializer.key();
+
+        let accounts = &mut ctx.accounts;
+        accounts.user.check_mint(&mint)?;
+        // the amount has to be payable by complete_withdrawal
+        accounts.config.allowed_mint(&mint)?.to_base_units(amount)?;
+        record_accrual(&mut accounts.config, &mint, &mut accounts.user, &mut accounts.pool, wallet)?;
+        let settled_until = accounts.config.settled_until;
+        record_unlock(&mut accounts.user, &mut accounts.pool, wallet, clock.unix_timestamp, settled_until)?;
+        accounting::apply_withdrawal_request(&mut accounts.user, &mut accounts.pool, amount, release_time)?;
+        let shares = accounts.user.take_own_withdrawal(amount, accounts.pool.exchange_rate())?;
+        accounts.user.check_lock(clock.unix_timestamp, settled_until)?;
+        shares::burn_shares(
+            accounts.share_token_program.to_account_info(),
+            accounts.share_mint.to_account_info(),
+            &accounts.user_share_account,
+            accounts.initializer.to_account_info(),
+            shares,
+        )?;
+        update_participant(&mut accounts.user, accounts.pool_data.as_ref(), wallet)?;
+
+        emit!(WithdrawalRequestedEvent {
+            user: wallet,
+            pool: pool_name,
+            amount,
+            balance: accounts.user.balance,
+            release_time,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Put the pending withdrawal back into the pool with a new prediction, minting its shares again at the current
+    // exchange rate
+    pub fn cancel_withdrawal_request(
+        mut ctx: Context<CancelWithdrawalRequest>,
+        pool_name: u8,
+        prediction: f64,
+    ) -> Result<()> {
+        let mint = ctx.accounts.mint_address.key();
+        let wallet = ctx.accounts.initializer.key();
+
+        let accounts = &mut ctx.accounts;
+        accounts.user.check_mint(&mint)?;
+        record_accrual(&mut accounts.config, &mint, &mut accounts.user, &mut accounts.pool, wallet)?;
+        let amount = accounting::apply_withdrawal_cancel(&mut accounts.user, &mut accounts.pool, prediction)?;
+        let shares = shares::shares_for_deposit(amount, accounts.pool.exchange_rate());
+        accounts.user.shares += shares;
+        shares::mint_shares(
+            accounts.share_token_program.to_account_info(),
+            accounts.share_mint.to_account_info(),
+            accounts.user_share_account.to_account_info(),
+            accounts.pool.to_account_info(),
+            pool_name,
+            *ctx.bumps.get("pool").unwrap(),
+            shares,
+        )?;
+        update_participant(&mut accounts.user, Some(&accounts.pool_data), wallet)?;
+
+        let clock: Clock = Clock::get().unwrap();
+        emit!(WithdrawalCancelledEvent {
+            user: wallet,
+            pool: pool_name,
+            amount,
+            prediction,
+            balance: accounts.user.balance,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Pay out the pending withdrawal once its game has ended, into the user wallet or the signer's associated token
+    // account like withdraw
+    pub fn complete_withdrawal(ctx: Context<CompleteWithdrawal>, pool_name: u8) -> Result<()> {
+        let clock: Clock = Clock::get().unwrap();
+        let user_key = ctx.accounts.user.key();
+        custody::custody_of(&ctx.accounts.user_token_account, &ctx.accounts.initializer.key(), &user_key)?;
+        let mint = ctx.accounts.mint_address.key();
+        ctx.accounts.user.check_mint(&mint)?;
+        let now = clock.unix_timestamp;
+        let amount = accounting::apply_withdrawal_completion(&mut ctx.accounts.user, &mut ctx.accounts.pool, now)?;
+        let allowed = ctx.accounts.config.allowed_mint_mut(&mint)?;
+        let base_units = allowed.to_base_units(amount)?;
+        allowed.deposits -= base_units;
+
+        let result = transfer_token_out_of_pool(
+            &mut ctx.accounts.pool_token_account,
+            &ctx.accounts.mint_address,
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.user_token_account.to_account_info(),
+            *ctx.bumps.get("pool_token_account").unwrap(),
+            base_units,
+        );
+        require!(result.is_ok(), ErrorCode::PaymentFailed);
+
+        let user = &mut ctx.accounts.user;
+        record_withdrawal_transaction(&mut ctx.accounts.transaction, user, amount, clock.unix_timestamp);
+
+        emit!(WithdrawalCompletedEvent {
+            user: ctx.accounts.initializer.key(),
+            pool: pool_name,
+            amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Lock amount of the balance until the end of the games-th game counting game_id, the game in progress, for the
+    // prize weight multiplier of that lock length. See the lockup module.
+    pub fn lock_deposit(ctx: Context<LockDeposit>, _game_id: u64, amount: f64, pool_name: u8, games: u8) -> Result<()> {
+        let clock: Clock = Clock::get().unwrap();
+        let game = &ctx.accounts.game;
+        let in_progress = clock.unix_timestamp >= game.start_time && clock.unix_timestamp < game.end_time;
+        require!(in_progress, ErrorCode::GameNotInProgress);
+        lockup::check_lock_age(clock.unix_timestamp, game.end_time)?;
+        let release_time = lockup::release_time(game.end_time, games);
+        let tier = *ctx.accounts.config.lock_tier(games)?;
+
+        let user = &mut ctx.accounts.user;
+        lockup::apply_lock(user, &mut ctx.accounts.pool, amount, &tier, release_time)?;
+
+        emit!(DepositLockedEvent {
+            user: ctx.accounts.initializer.key(),
+            pool: pool_name,
+            amount,
+            games,
+            multiplier: tier.multiplier,
+            release_time,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Drop a released lock and its prize weight. Anyone can call it once the lock's last game is settled.
+    pub fn unlock_deposit(mut ctx: Context<UnlockDeposit>, user_key: Pubkey, pool_name: u8) -> Result<()> {
+        let clock: Clock = Clock::get().unwrap();
+        let settled_until = ctx.accounts.config.settled_until;
+        let accounts = &mut ctx.accounts;
+        let amount = lockup::apply_unlock(&mut accounts.user, &mut accounts.pool, clock.unix_timestamp, settled_until)?;
+
+        emit!(DepositUnlockedEvent { user: user_key, pool: pool_name, amount, timestamp: clock.unix_timestamp });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(beneficiary: Pubkey, amount: f64, pool_name: u8, prediction: f64)]
+pub struct DepositForUser<'info> {
+    #[account(mut)]
+    pub donor: Signer<'info>,
+    #[account(mut, seeds = [b"config".as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"user".as_ref(), beneficiary.as_ref()], bump)]
+    pub user: Account<'info, User>,
+    #[account(mut, token::mint = mint_address, token::authority = donor)]
+    pub donor_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, seeds = [&[pool_name]], bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut, address = pool.pool_data @ ErrorCode::InvalidPoolData)]
+    pub pool_data: AccountLoader<'info, PoolData>,
+    #[account(mut, seeds = [b"pool_wallet".as_ref(), mint_address.key().as_ref()], bump)]
+    pub pool_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, address = pool.share_mint @ ErrorCode::InvalidShareMint)]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut, token::mint = share_mint, token::authority = beneficiary)]
+    pub beneficiary_share_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = donor,
+        space = Transaction::LEN,
+        seeds = [b"tx".as_ref(), user.key().as_ref(), pool.key().as_ref(), &user.txn_count.to_be_bytes()],
+        bump,
+    )]
+    pub transaction: Account<'info, Transaction>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub share_token_program: Program<'info, Token>,
+    pub mint_address: Box<InterfaceAccount<'info, Mint>>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(delegate: Pubkey)]
+pub struct ApproveDelegate<'info> {
+    #[account(mut)]
+    pub initializer: Signer<'info>,
+    #[account(seeds = [b"user".as_ref(), initializer.key().as_ref()], bump)]
+    pub user: Account<'info, User>,
+    #[account(
+        init,
+        payer = initializer,
+        space = Delegation::LEN,
+        seeds = [b"delegation".as_ref(), user.key().as_ref(), delegate.as_ref()],
+        bump,
+    )]
+    pub delegation: Account<'info, Delegation>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(delegate: Pubkey)]
+pub struct RevokeDelegate<'info> {
+    #[account(mut)]
+    pub initializer: Signer<'info>,
+    #[account(seeds = [b"user".as_ref(), initializer.key().as_ref()], bump)]
+    pub user: Account<'info, User>,
+    #[account(
+        mut,
+        close = initializer,
+        seeds = [b"delegation".as_ref(), user.key().as_ref(), delegate.as_ref()],
+        bump,
+    )]
+    pub delegation: Account<'info, Delegation>,
+}
+
+#[derive(Accounts)]
+#[instruction(beneficiary: Pubkey, amount: f64, pool_name: u8, prediction: f64)]
+pub struct DepositFor<'info> {
+    pub delegate: Signer<'info>,
+    #[account(mut, seeds = [b"config".as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"user".as_ref(), beneficiary.as_ref()], bump)]
+    pub user: Account<'info, User>,
+    #[account(
+        mut,
+        seeds = [b"delegation".as_ref(), user.key().as_ref(), delegate.key().as_ref()],
+        bump,
+        has_one = delegate,
+    )]
+    pub delegation: Account<'info, Delegation>,
+    #[account(mut, token::mint = mint_address, token::authority = delegate)]
+    pub delegate_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, seeds = [&[pool_name]], bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut, address = pool.pool_data @ ErrorCode::InvalidPoolData)]
+    pub pool_data: AccountLoader<'info, PoolData>,
+    #[account(mut, seeds = [b"pool_wallet".as_ref(), mint_address.key().as_ref()], bump)]
+    pub pool_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, address = pool.share_mint @ ErrorCode::InvalidShareMint)]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut, token::mint = share_mint, token::authority = delegate)]
+    pub delegate_share_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub share_token_program: Program<'info, Token>,
+    pub mint_address: Box<InterfaceAccount<'info, Mint>>,
+}
+
+#[derive(Accounts)]
+#[instruction(beneficiary: Pubkey, amount: f64, pool_name: u8)]
+pub struct WithdrawTo<'info> {
+    pub delegate: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, seeds = [b"config".as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"user".as_ref(), beneficiary.as_ref()], bump)]
+    pub user: Account<'info, User>,
+    #[account(
+        mut,
+        seeds = [b"delegation".as_ref(), user.key().as_ref(), delegate.key().as_ref()],
+        bump,
+        has_one = delegate,
+    )]
+    pub delegation: Account<'info, Delegation>,
+    #[account(mut, token::mint = mint_address)]
+    pub destination: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, seeds = [&[pool_name]], bump)]
+    pub pool: Account<'info, Pool>,
+    // only needed once the pool has its PoolData, users who aren't listed can withdraw without it
+    #[account(mut, address = pool.pool_data @ ErrorCode::InvalidPoolData)]
+    pub pool_data: Option<AccountLoader<'info, PoolData>>,
+    #[account(mut, seeds = [b"pool_wallet".as_ref(), mint_address.key().as_ref()], bump)]
+    pub pool_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, address = pool.share_mint @ ErrorCode::InvalidShareMint)]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut, token::mint = share_mint, token::authority = delegate)]
+    pub delegate_share_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = payer,
+        space = Transaction::LEN,
+        seeds = [b"tx".as_ref(), user.key().as_ref(), pool.key().as_ref(), &user.txn_count.to_be_bytes()],
+        bump,
+    )]
+    pub transaction: Account<'info, Transaction>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub share_token_program: Program<'info, Token>,
+    pub mint_address: Box<InterfaceAccount<'info, Mint>>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64, amount: f64, pool_name: u8)]
+pub struct LockDeposit<'info> {
+    pub initializer: Signer<'info>,
+    #[account(seeds = [b"config".as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"user".as_ref(), initializer.key().as_ref()], bump)]
+    pub user: Account<'info, User>,
+    #[account(seeds = [b"game".as_ref(), &game_id.to_be_bytes()], bump)]
+    pub game: Account<'info, Game>,
+    #[account(mut, seeds = [&[pool_name]], bump)]
+    pub pool: Account<'info, Pool>,
+}
+
+#[derive(Accounts)]
+#[instruction(user_key: Pubkey, pool_name: u8)]
+pub struct UnlockDeposit<'info> {
+    pub signer: Signer<'info>,
+    #[account(seeds = [b"config".as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"user".as_ref(), user_key.as_ref()], bump)]
+    pub user: Account<'info, User>,
+    #[account(mut, seeds = [&[pool_name]], bump)]
+    pub pool: Account<'info, Pool>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64, amount: f64, pool_name: u8)]
+pub struct RequestWithdrawal<'info> {
+    pub initializer: Signer<'info>,
+    #[account(mut, seeds = [b"config".as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"user".as_ref(), initializer.key().as_ref()], bump)]
+    pub user: Account<'info, User>,
+    #[account(seeds = [b"game".as_ref(), &game_id.to_be_bytes()], bump)]
+    pub game: Account<'info, Game>,
+    #[account(mut, seeds = [&[pool_name]], bump)]
+    pub pool: Account<'info, Pool>,
+    // only needed once the pool has its PoolData, users who aren't listed can withdraw without it
+    #[account(mut, address = pool.pool_data @ ErrorCode::InvalidPoolData)]
+    pub pool_data: Option<AccountLoader<'info, PoolData>>,
+    #[account(mut, address = pool.share_mint @ ErrorCode::InvalidShareMint)]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut, token::mint = share_mint, token::authority = initializer)]
+    pub user_share_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub share_token_program: Program<'info, Token>,
+    // the mint the balance is held in
+    pub mint_address: Box<InterfaceAccount<'info, Mint>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pool_name: u8, prediction: f64)]
+pub struct CancelWithdrawalRequest<'info> {
+    pub initializer: Signer<'info>,
+    #[account(mut, seeds = [b"config".as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"user".as_ref(), initializer.key().as_ref()], bump)]
+    pub user: Account<'info, User>,
+    #[account(mut, seeds = [&[pool_name]], bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut, address = pool.pool_data @ ErrorCode::InvalidPoolData)]
+    pub pool_data: AccountLoader<'info, PoolData>,
+    #[account(mut, address = pool.share_mint @ ErrorCode::InvalidShareMint)]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut, token::mint = share_mint, token::authority = initializer)]
+    pub user_share_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub share_token_program: Program<'info, Token>,
+    pub mint_address: Box<InterfaceAccount<'info, Mint>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pool_name: u8)]
+pub struct CompleteWithdrawal<'info> {
+    #[account(mut)]
+    pub initializer: Signer<'info>,
+    #[account(mut, seeds = [b"config".as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"user".as_ref(), initializer.key().as_ref()], bump)]
+    pub user: Account<'info, User>,
+    // the user wallet or the signer's associated token account, see the custody module
+    #[account(mut, constraint=user_token_account.mint == mint_address.key() @ ErrorCode::InvalidUserTokenAccount)]
+    pub user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, seeds = [&[pool_name]], bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        constraint=pool_token_account.mint == mint_address.key(),
+        seeds = [b"pool_wallet".as_ref(), mint_address.key().as_ref()],
+        bump,
+    )]
+    pub pool_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = initializer,
+        space = Transaction::LEN,
+        seeds = [b"tx".as_ref(), user.key().as_ref(), pool.key().as_ref(), &user.txn_count.to_be_bytes()],
+        bump,
+    )]
+    pub transaction: Account<'info, Transaction>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub mint_address: Box<InterfaceAccount<'info, Mint>>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: f64, pool_name: u8, prediction: f64)]
+pub struct DepositSol<'info> {
+    #[account(mut)]
+    pub initializer: Signer<'info>,
+    #[account(mut, seeds = [b"config".as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"user".as_ref(), initializer.key().as_ref()], bump)]
+    pub user: Account<'info, User>,
+    #[account(mut, seeds = [&[pool_name]], bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut, address = pool.pool_data @ ErrorCode::InvalidPoolData)]
+    pub pool_data: AccountLoader<'info, PoolData>,
+    #[account(mut, seeds = [b"pool_wallet".as_ref(), native_mint::ID.as_ref()], bump)]
+    pub pool_token_account: InterfaceAccount<'info, TokenAccount>,
+    // the pool's share mint and the signer's token account the shares are minted to, see the shares module
+    #[account(mut, address = pool.share_mint @ ErrorCode::InvalidShareMint)]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut, token::mint = share_mint, token::authority = initializer)]
+    pub user_share_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: f64, pool_name: u8)]
+pub struct WithdrawSol<'info> {
+    #[account(mut)]
+    pub initializer: Signer<'info>,
+    #[account(mut, seeds = [b"config".as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"user".as_ref(), initializer.key().as_ref()], bump)]
+    pub user: Account<'info, User>,
+    #[account(mut, seeds = [&[pool_name]], bump)]
+    pub pool: Account<'info, Pool>,
+    // only needed once the pool has its PoolData, users who aren't listed can withdraw without it
+    #[account(mut, address = pool.pool_data @ ErrorCode::InvalidPoolData)]
+    pub pool_data: Option<AccountLoader<'info, PoolData>>,
+    #[account(mut, seeds = [b"pool_wallet".as_ref(), native_mint::ID.as_ref()], bump)]
+    pub pool_token_account: InterfaceAccount<'info, TokenAccount>,
+    // the pool's share mint and the signer's token account the shares are burned from, see the shares module
+    #[account(mut, address = pool.share_mint @ ErrorCode::InvalidShareMint)]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut, token::mint = share_mint, token::authority = initializer)]
+    pub user_share_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    // wSOL on its way out, closed before the instruction ends
+    #[account(
+        init,
+        payer = initializer,
+        seeds = [b"unwrap".as_ref(), user.key().as_ref()],
+        bump,
+        token::mint = mint_address,
+        token::authority = user,
+    )]
+    pub unwrap_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = initializer,
+        space = Transaction::LEN,
+        seeds = [b"tx".as_ref(), user.key().as_ref(), pool.key().as_ref(), &user.txn_count.to_be_bytes()],
+        bump)]
+    pub transaction: Account<'info, Transaction>,
+    #[account(address = native_mint::ID)]
+    pub mint_address: Box<InterfaceAccount<'info, Mint>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: f64, pool_name: u8)]
+pub struct Withdraw<'info> {
+    #[account(mut)]
+    pub initializer: Signer<'info>,
+    #[account(mut, seeds = [b"config".as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"user".as_ref(), initializer.key().as_ref()], bump)]
+    pub user: Account<'info, User>,
+    // the user wallet or the signer's associated token account, see the custody module
+    #[account(mut, constraint=user_token_account.mint == mint_address.key() @ ErrorCode::InvalidUserTokenAccount)]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [&[pool_name]], bump)]
+    pub pool: Account<'info, Pool>,
+    // only needed once the pool has its PoolData, users who aren't listed can withdraw without it
+    #[account(mut, address = pool.pool_data @ ErrorCode::InvalidPoolData)]
+    pub pool_data: Option<AccountLoader<'info, PoolData>>,
+    #[account(
+        mut,
+        constraint=pool_token_account.mint == mint_address.key(),
+        seeds = [b"pool_wallet".as_ref(), mint_address.key().as_ref()],
+        bump,
+    )]
+    pub pool_token_account: InterfaceAccount<'info, TokenAccount>,   
+    // the pool's share mint and the signer's token account the shares are burned from, see the shares module
+    #[account(mut, address = pool.share_mint @ ErrorCode::InvalidShareMint)]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut, token::mint = share_mint, token::authority = initializer)]
+    pub user_share_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init, 
+        payer = initializer, 
+        space = Transaction::LEN, 
+        seeds = [b"tx".as_ref(), user.key().as_ref(), pool.key().as_ref(), &user.txn_count.to_be_bytes()] 
+, bump)] 
+    pub transaction: Account<'info, Transaction>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub share_token_program: Program<'info, Token>,
+    pub mint_address: Box<InterfaceAccount<'info, Mint>>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: f64, pool_name: u8, prediction: f64)]
+pub struct Deposit<'info> {
+    #[account(mut)]
+    pub initializer: Signer<'info>,
+    #[account(mut, seeds = [b"config".as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"user".as_ref(), initializer.key().as_ref()], bump)]
+    pub user: Account<'info, User>,
+    // the user wallet or the signer's associated token account, see the custody module
+    #[account(mut, constraint=user_token_account.mint == mint_address.key() @ ErrorCode::InvalidUserTokenAccount)]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [&[pool_name]], bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut, address = pool.pool_data @ ErrorCode::InvalidPoolData)]
+    pub pool_data: AccountLoader<'info, PoolData>,
+    #[account(
+        mut,
+        constraint=pool_token_account.mint == mint_address.key(),
+        seeds = [b"pool_wallet".as_ref(), mint_address.key().as_ref()],
+        bump,
+    )]
+    pub pool_token_account: InterfaceAccount<'info, TokenAccount>,   
+    // the pool's share mint and the signer's token account the shares are minted to, see the shares module
+    #[account(mut, address = pool.share_mint @ ErrorCode::InvalidShareMint)]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut, token::mint = share_mint, token::authority = initializer)]
+    pub user_share_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub share_token_program: Program<'info, Token>,
+    pub mint_address: Box<InterfaceAccount<'info, Mint>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(user_key: Pubkey, pool_name: u8, game_id: u64)]
+pub struct PayWinner<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(mut, seeds = [b"config".as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"game".as_ref(), &game_id.to_be_bytes()], bump)]
+    pub game: Account<'info, Game>,
+    #[account(mut, seeds = [b"user".as_ref(), user_key.as_ref()], bump)]
+    pub user: Account<'info, User>,
+    // the user wallet or the user's associated token account, see the custody module
+    #[account(mut, constraint=user_token_account.mint == mint_address.key() @ ErrorCode::InvalidUserTokenAccount)]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [&[pool_name]], bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        constraint=pool_token_account.mint == mint_address.key(),
+        seeds = [b"pool_wallet".as_ref(), mint_address.key().as_ref()],
+        bump,
+    )]
+    pub pool_token_account: InterfaceAccount<'info, TokenAccount>,  
+    pub token_program: Interface<'info, TokenInterface>,
+    pub mint_address: Box<InterfaceAccount<'info, Mint>>,
+    pub system_program: Program<'info, System>, 
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64, user_keys: Vec<Pubkey>, pool_name: u8)]
+pub struct PayWinnersBatch<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(mut, seeds = [b"config".as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"game".as_ref(), &game_id.to_be_bytes()], bump)]
+    pub game: Account<'info, Game>,
+    #[account(mut, seeds = [&[pool_name]], bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        constraint=pool_token_account.mint == mint_address.key(),
+        seeds = [b"pool_wallet".as_ref(), mint_address.key().as_ref()],
+        bump,
+    )]
+    pub pool_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub mint_address: Box<InterfaceAccount<'info, Mint>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64, merkle_root: [u8; 32], max_total_claim: u64, max_num_nodes: u64)]
+pub struct CreateDistributor<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config".as_ref()], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"game".as_ref(), &game_id.to_be_bytes()], bump)]
+    pub game: Account<'info, Game>,
+    #[account(
+        init,
+        payer = admin,
+        space = Distributor::space(max_num_nodes),
+        seeds = [b"distributor".as_ref(), &game_id.to_be_bytes()],
+        bump,
+    )]
+    pub distributor: Account<'info, Distributor>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64, pool_name: u8)]
+pub struct ClaimWithProof<'info> {
+    #[account(mut)]
+    pub claimant: Signer<'info>,
+    #[account(seeds = [b"config".as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"user".as_ref(), claimant.key().as_ref()], bump)]
+    pub user: Account<'info, User>,
+    // the user wallet or the claimant's associated token account, see the custody module
+    #[account(mut, constraint=user_token_account.mint == mint_address.key() @ ErrorCode::InvalidUserTokenAccount)]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"game".as_ref(), &game_id.to_be_bytes()], bump)]
+    pub game: Account<'info, Game>,
+    #[account(mut, seeds = [b"distributor".as_ref(), &game_id.to_be_bytes()], bump)]
+    pub distributor: Account<'info, Distributor>,
+    #[account(
+        mut,
+        constraint=pool_token_account.mint == mint_address.key(),
+        seeds = [b"pool_wallet".as_ref(), mint_address.key().as_ref()],
+        bump,
+    )]
+    pub pool_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub mint_address: Box<InterfaceAccount<'info, Mint>>,
+}
+
+#[derive(Accounts)]
+#[instruction(pool_name: u8, prediction: f64)]
 pub struct MakePrediction<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
@@ -435,15 +2036,154 @@ pub struct MakePrediction<'info> {
 }
 
 #[derive(Accounts)]
-#[instruction(pool_names: Vec<u8>, pool_total: Vec<f64>)]
-pub struct SelectWinningPool<'info> {
-    pub clock: Sysvar<'info, Clock>,
+pub struct SetPayoutPreference<'info> {
+    pub owner: Signer<'info>,
+    #[account(mut, seeds = [b"user".as_ref(), owner.key().as_ref()], bump)]
+    pub user: Account<'info, User>,
+}
+
+#[derive(Accounts)]
+#[instruction(pool_names: Vec<u8>, pool_total: Vec<f64>)]
+pub struct SelectWinningPool<'info> {
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct StartGame<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config".as_ref()], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+    #[account(init, payer = admin, space = Game::LEN, seeds = [b"game".as_ref(), &game_id.to_be_bytes()], bump)]
+    pub game: Account<'info, Game>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct CancelGame<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config".as_ref()], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+    #[account(mut, close = admin, seeds = [b"game".as_ref(), &game_id.to_be_bytes()], bump)]
+    pub game: Account<'info, Game>,
+}
+#[derive(Accounts)]
+#[instruction(game_id: u64, winning_pool: u8)]
+pub struct SettleGame<'info> {
+    pub admin: Signer<'info>,
+    #[account(mut, seeds = [b"config".as_ref()], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"game".as_ref(), &game_id.to_be_bytes()], bump)]
+    pub game: Account<'info, Game>,
+    #[account(mut, seeds = [&[winning_pool]], bump)]
+    pub pool: Account<'info, Pool>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct FundPrize<'info> {
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+    #[account(seeds = [b"config".as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"game".as_ref(), &game_id.to_be_bytes()], bump)]
+    pub game: Account<'info, Game>,
+    #[account(
+        init,
+        payer = sponsor,
+        space = Sponsorship::LEN,
+        seeds = [b"sponsorship".as_ref(), &game_id.to_be_bytes(), &game.sponsor_count.to_be_bytes()],
+        bump)]
+    pub sponsorship: Account<'info, Sponsorship>,
+    #[account(
+        mut,
+        constraint=sponsor_token_account.owner == sponsor.key(),
+        constraint=sponsor_token_account.mint == mint_address.key(),
+    )]
+    pub sponsor_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint=pool_token_account.mint == mint_address.key(),
+        seeds = [b"pool_wallet".as_ref(), mint_address.key().as_ref()],
+        bump,
+    )]
+    pub pool_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub mint_address: Box<InterfaceAccount<'info, Mint>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pool_name: u8)]
+pub struct ManageYield<'info> {
+    pub admin: Signer<'info>,
+    #[account(mut, seeds = [b"config".as_ref()], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [&[pool_name]], bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        seeds = [b"pool_wallet".as_ref(), mint_address.key().as_ref()],
+        bump,
+    )]
+    pub pool_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: validated by the lending program
+    #[account(mut)]
+    pub reserve: UncheckedAccount<'info>,
+    /// CHECK: validated by the lending program
+    #[account(mut)]
+    pub reserve_vault: UncheckedAccount<'info>,
+    /// CHECK: validated by the lending program
+    #[account(mut)]
+    pub obligation: UncheckedAccount<'info>,
+    /// CHECK: has to have a yield source adapter, see the yield_source module
+    #[account(executable)]
+    pub lending_program: UncheckedAccount<'info>,
+    // The lending program only takes SPL Token mints, Token-2022 deposits stay in the pool wallet
+    pub token_program: Program<'info, Token>,
+    pub mint_address: Box<InterfaceAccount<'info, Mint>>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct HarvestYield<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config".as_ref()], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"game".as_ref(), &game_id.to_be_bytes()], bump)]
+    pub game: Account<'info, Game>,
+    #[account(
+        mut,
+        seeds = [b"pool_wallet".as_ref(), mint_address.key().as_ref()],
+        bump,
+    )]
+    pub pool_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: validated by the lending program
+    #[account(mut)]
+    pub reserve: UncheckedAccount<'info>,
+    /// CHECK: validated by the lending program
+    #[account(mut)]
+    pub reserve_vault: UncheckedAccount<'info>,
+    /// CHECK: validated by the lending program
+    #[account(mut)]
+    pub obligation: UncheckedAccount<'info>,
+    /// CHECK: has to have a yield source adapter, see the yield_source module
+    #[account(executable)]
+    pub lending_program: UncheckedAccount<'info>,
+    // The lending program only takes SPL Token mints, Token-2022 deposits stay in the pool wallet
+    pub token_program: Program<'info, Token>,
+    pub mint_address: Box<InterfaceAccount<'info, Mint>>,
 }
 
 #[derive(Accounts)]
 pub struct CreateUser<'info> {
     #[account(mut)]
     pub initializer: Signer<'info>,
+    #[account(seeds = [b"config".as_ref()], bump)]
+    pub config: Account<'info, Config>,
     #[account(
         init, 
         payer = initializer, 
@@ -459,40 +2199,227 @@ pub struct CreateUser<'info> {
         token::mint = mint_address,
         token::authority = user,
     )]
-    pub user_token_account: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
-    pub mint_address: Box<Account<'info, Mint>>,
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub mint_address: Box<InterfaceAccount<'info, Mint>>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
 }
 
+#[derive(Accounts)]
+pub struct CreateUserWithoutWallet<'info> {
+    #[account(mut)]
+    pub initializer: Signer<'info>,
+    #[account(
+        init,
+        payer = initializer,
+        space = User::LEN,
+        seeds = [b"user".as_ref(), initializer.key().as_ref()],
+        bump)]
+    pub user: Account<'info, User>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateConfig<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: may be in an earlier layout, read by migrate::upgrade_config
+    #[account(mut, owner = crate::ID, seeds = [b"config".as_ref()], bump)]
+    pub config: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(user_key: Pubkey)]
+pub struct MigrateUser<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: may be in an earlier layout, read by migrate::upgrade_user
+    #[account(mut, owner = crate::ID, seeds = [b"user".as_ref(), user_key.as_ref()], bump)]
+    pub user: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 #[instruction(pool_name: u8)]
-pub struct CreatePool<'info> {
-    // TODO: add constraint = owner.key() == OWNER
+pub struct MigratePool<'info> {
     #[account(mut)]
-    pub owner: Signer<'info>,
-    #[account(init, payer = owner, space = Pool::LEN, seeds = [&[pool_name]], bump)]
-    pub pool: Account<'info, Pool>,
+    pub payer: Signer<'info>,
+    /// CHECK: may be in an earlier layout, read by migrate::upgrade_pool
+    #[account(mut, owner = crate::ID, seeds = [&[pool_name]], bump)]
+    pub pool: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct MigrateGame<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: may be in an earlier layout, read by migrate::upgrade_game
+    #[account(mut, owner = crate::ID, seeds = [b"game".as_ref(), &game_id.to_be_bytes()], bump)]
+    pub game: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(init, payer = admin, space = Config::LEN, seeds = [b"config".as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    // only whoever can upgrade the program gets to be its first admin
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(admin.key()) @ ErrorCode::Unauthorized,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}
+#[derive(Accounts)]
+pub struct AddMint<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(mut, seeds = [b"config".as_ref()], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
     #[account(
         init,
-        payer = owner,
-        seeds = [b"pool_wallet".as_ref()],
+        payer = admin,
+        seeds = [b"pool_wallet".as_ref(), mint_address.key().as_ref()],
         bump,
         token::mint = mint_address,
-        token::authority = pool,
+        token::authority = pool_token_account,
+    )]
+    pub pool_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub mint_address: Box<InterfaceAccount<'info, Mint>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+#[derive(Accounts)]
+pub struct SetMinimumDeposit<'info> {
+    pub admin: Signer<'info>,
+    #[account(mut, seeds = [b"config".as_ref()], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+}
+#[derive(Accounts)]
+pub struct SetLockTier<'info> {
+    pub admin: Signer<'info>,
+    #[account(mut, seeds = [b"config".as_ref()], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+}
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    pub admin: Signer<'info>,
+    #[account(mut, seeds = [b"config".as_ref()], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+}
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config".as_ref()], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        constraint=pool_token_account.mint == mint_address.key(),
+        seeds = [b"pool_wallet".as_ref(), mint_address.key().as_ref()],
+        bump,
     )]
-    pub pool_token_account: Account<'info, TokenAccount>,
-    pub mint_address: Box<Account<'info, Mint>>,
+    pub pool_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, constraint=destination.mint == mint_address.key())]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub mint_address: Box<InterfaceAccount<'info, Mint>>,
+}
+#[derive(Accounts)]
+#[instruction(pool_name: u8)]
+pub struct CreatePool<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config".as_ref()], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+    #[account(init, payer = admin, space = Pool::LEN, seeds = [&[pool_name]], bump)]
+    pub pool: Account<'info, Pool>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(pool_name: u8)]
+pub struct CreatePoolData<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config".as_ref()], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [&[pool_name]], bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(zero)]
+    pub pool_data: AccountLoader<'info, PoolData>,
+}
+
+#[derive(Accounts)]
+#[instruction(pool_name: u8)]
+pub struct CreateShareMint<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config".as_ref()], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [&[pool_name]], bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(init, payer = admin, mint::decimals = AMOUNT_DECIMALS, mint::authority = pool)]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
 }
 
+#[derive(Accounts)]
+#[instruction(game_id: u64, pool_name: u8)]
+pub struct DistributePrize<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config".as_ref()], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"game".as_ref(), &game_id.to_be_bytes()], bump)]
+    pub game: Account<'info, Game>,
+    #[account(mut, seeds = [&[pool_name]], bump)]
+    pub pool: Account<'info, Pool>,
+}
+
+#[derive(Accounts)]
+#[instruction(pool_name: u8)]
+pub struct IssueShares<'info> {
+    pub initializer: Signer<'info>,
+    #[account(mut, seeds = [b"user".as_ref(), initializer.key().as_ref()], bump)]
+    pub user: Account<'info, User>,
+    #[account(seeds = [&[pool_name]], bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut, address = pool.share_mint @ ErrorCode::InvalidShareMint)]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut, token::mint = share_mint, token::authority = initializer)]
+    pub user_share_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64, pool_name: u8)]
+pub struct SnapshotPool<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"config".as_ref()], bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"game".as_ref(), &game_id.to_be_bytes()], bump)]
+    pub game: Account<'info, Game>,
+    #[account(seeds = [&[pool_name]], bump)]
+    pub pool: Account<'info, Pool>,
+    #[account(mut, address = pool.pool_data @ ErrorCode::InvalidPoolData)]
+    pub pool_data: AccountLoader<'info, PoolData>,
+}
+
 #[derive(Clone, Copy, PartialEq)]
 enum TransactionType{
     Deposit,
-    Withdrawal
+    Withdrawal,
+    Gift
 }
 
 impl TransactionType {
@@ -500,6 +2427,7 @@ impl TransactionType {
         match self {
             TransactionType::Deposit => 1,
             TransactionType::Withdrawal => 2,
+            TransactionType::Gift => 3,
         }
     }
 }
@@ -547,6 +2475,46 @@ impl Pools {
 }
 
 #[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,
+    // deposits are rejected while paused
+    pub paused: bool,
+    // mints deposits are accepted in, see the mints module. MAX_MINTS of them at most, max_len only takes a literal.
+    #[max_len(4)]
+    pub mints: Vec<AllowedMint>,
+    // lock lengths offered by lock_deposit, see the lockup module. MAX_LOCK_TIERS of them at most.
+    #[max_len(4)]
+    pub lock_tiers: Vec<LockTier>,
+    // layout version, see the migrate module
+    pub version: u8,
+    // end time of the latest settled game, locks ending by then are released. See the lockup module.
+    pub settled_until: i64,
+}
+
+// A lock length in games and the prize weight multiplier of deposits locked for it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct LockTier {
+    pub games: u8,
+    pub multiplier: f64,
+}
+
+// A mint deposits are accepted in, each one has its own pool wallet
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct AllowedMint {
+    pub mint: Pubkey,
+    // read from the mint account when the mint is added
+    pub decimals: u8,
+    // in base units of the mint, as are the totals below
+    pub minimum_deposit: u64,
+    // owed to depositors, including what is lent out
+    pub deposits: u64,
+    // lent out to the yield source
+    pub invested: u64,
+}
+
+#[account]
+#[derive(InitSpace, Default)]
 pub struct Pool {
     pub is_initialized: bool,
     pub last_update_timestamp: i64,
@@ -554,9 +2522,64 @@ pub struct Pool {
     pub user_count: u64,
     pub name: u8,
     pub average_prediction: f64,
+    // amount currently lent out to the yield source
+    pub invested: f64,
+    // layout version, see the migrate module
+    pub version: u8,
+    // PoolData account of the pool, Pubkey::default() until create_pool_data
+    pub pool_data: Pubkey,
+    // receipt token of the pool's deposits, Pubkey::default() until create_share_mint, see the shares module
+    pub share_mint: Pubkey,
+    // underlying amount per share, raised by distribute_prize. 0 reads as 1.
+    pub share_rate: f64,
+    // prize distributed to the pool that balances haven't caught up with yet
+    pub pending_prize: f64,
+    // requested withdrawals waiting for their game to end, out of total_deposit but still in the pool wallet
+    pub pending_withdrawals: f64,
+    // prize weight locked deposits have on top of their balance, see the lockup module
+    pub lock_boost: f64,
+    // last game the pool won, its users' prize weights are kept from then until they are paid for it. See
+    // accounting::checkpoint_weight.
+    pub won_game_id: u64,
+}
+
+pub const MAX_PARTICIPANTS: usize = 4096;
+pub const SNAPSHOT_COUNT: usize = 64;
+
+#[zero_copy(unsafe)]
+pub struct PoolSnapshot {
+    pub game_id: u64,
+    pub total_deposit: f64,
+    pub user_count: u64,
+    pub average_prediction: f64,
+}
+
+#[zero_copy(unsafe)]
+pub struct ParticipantSlot {
+    pub index: u32,
+}
+
+// Per-pool state too big for Borsh. It is loaded zero-copy, so deposit and withdraw cost the same however many
+// participants the pool has. See the pool_data module.
+#[account(zero_copy(unsafe))]
+pub struct PoolData {
+    // the last SNAPSHOT_COUNT games, the oldest is overwritten first
+    pub snapshots: [PoolSnapshot; SNAPSHOT_COUNT],
+    // wallets with a balance in the pool, e.g. for jackpot draws. Free slots hold Pubkey::default().
+    pub participants: [Pubkey; MAX_PARTICIPANTS],
+    // slots freed by withdrawals, reused before new ones
+    pub free_slots: [ParticipantSlot; MAX_PARTICIPANTS],
+    pub snapshot_count: u64,
+    pub participant_count: u32,
+    // participants past this slot have never been used
+    pub slots_used: u32,
+    pub free_slot_count: u32,
+    pub pool: u8,
+    pub padding: [u8; 3],
 }
 
 #[account]
+#[derive(InitSpace)]
 pub struct Game {
     pub game_id: u64,
     pub start_time: i64,
@@ -564,16 +2587,88 @@ pub struct Game {
     pub winning_pool: u8,
     pub winning_amount: f64,
     pub total_prize: f64,
+    // sum of all prizes paid out for this game, never more than total_prize
+    pub total_paid: f64,
+    // part of total_prize contributed by sponsors through fund_prize
+    pub sponsored_prize: f64,
+    pub sponsor_count: u64,
+    // layout version, see the migrate module
+    pub version: u8,
+    // part of total_paid compounded into the balances of the winning pool
+    pub compounded_prize: f64,
+    // prize weight of the winning pool when the game was settled, the prize shares are taken out of it
+    pub winning_weight: f64,
+    // bit i is set once the yield of the i-th mint in Config::mints was harvested into the prize
+    pub harvested_mints: u8,
+    // mint the prize is held and paid out in, see the mints module. Pubkey::default() until the game is funded.
+    pub prize_mint: Pubkey,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Sponsorship {
+    pub game_id: u64,
+    pub sponsor: Pubkey,
+    // in base units of mint
+    pub amount: u64,
+    pub timestamp: i64,
+    pub mint: Pubkey,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Distributor {
+    pub game_id: u64,
+    pub merkle_root: [u8; 32],
+    pub max_total_claim: u64,
+    pub max_num_nodes: u64,
+    pub total_amount_claimed: u64,
+    pub num_nodes_claimed: u64,
+    // one bit per leaf index, set once the leaf has been claimed. Its length is added in space.
+    #[max_len(0)]
+    pub claimed_bitmap: Vec<u8>,
+}
+
+impl Distributor {
+    pub fn bitmap_len(max_num_nodes: u64) -> usize {
+        max_num_nodes.div_ceil(8) as usize
+    }
+
+    fn is_claimed(&self, index: u64) -> bool {
+        self.claimed_bitmap[(index / 8) as usize] & (1 << (index % 8)) != 0
+    }
+
+    fn set_claimed(&mut self, index: u64) {
+        self.claimed_bitmap[(index / 8) as usize] |= 1 << (index % 8);
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Delegation {
+    // wallet of the user that approved the delegate
+    pub user: Pubkey,
+    pub delegate: Pubkey,
+    // deposited by the delegate and not withdrawn yet, in AMOUNT_DECIMALS units like the balance
+    pub deposited: f64,
+    // shares minted to the delegate and not burned yet
+    pub shares: u64,
 }
 
 #[account]
+#[derive(InitSpace)]
 pub struct Transaction {
     pub timestamp: i64,
     pub amount: f64,
     pub transaction_type: u8,
+    // layout version, see the migrate module
+    pub version: u8,
+    // wallet that paid for a gift deposit, the default for every other transaction
+    pub donor: Pubkey,
 }
 
 #[account]
+#[derive(InitSpace, Default)]
 pub struct User {
     pub pool: u8,
     pub last_prediction: f64,
@@ -586,43 +2681,222 @@ pub struct User {
     pub current_weighted_balance: f64,
     pub current_weighted_days: i64,
     pub txn_count: u64,
+    // id of the last game this user was paid out for
+    pub last_paid_game_id: u64,
+    // layout version, see the migrate module
+    pub version: u8,
+    // slot on the PoolData participant list of the user's pool, counted from 1. 0 when not listed.
+    pub participant_slot: u32,
+    // mint the balance is held in, set by the user's deposits, see the custody module
+    pub mint: Pubkey,
+    // exchange rate of the pool the balance last caught up with, 0 reads as 1. See the shares module.
+    pub share_rate: f64,
+    // shares minted for the balance and not burned yet, not counting those of delegates
+    pub shares: u64,
+    // part of the balance deposited by delegates, which only they can withdraw. See the delegation module.
+    pub delegated_balance: f64,
+    // requested withdrawal out of withdrawal_pool, paid by complete_withdrawal from withdrawal_release_time on
+    pub pending_withdrawal: f64,
+    pub withdrawal_pool: u8,
+    pub withdrawal_release_time: i64,
+    // part of the own balance locked by lock_deposit until lock_release_time, see the lockup module
+    pub locked_amount: f64,
+    pub lock_multiplier: f64,
+    pub lock_release_time: i64,
+    // prizes of pay_winning_pool_user and pay_winners_batch go into the balance instead of the token account
+    pub auto_compound: bool,
+    // prize weight in the pool when it won settled_game_id, kept from the first change to the balance after that
+    pub settled_weight: f64,
+    pub settled_game_id: u64,
+}
+
+// Events for off-chain indexers. user is always the wallet, not its User account.
+
+#[event]
+pub struct DepositEvent {
+    pub user: Pubkey,
+    pub pool: u8,
+    pub amount: f64,
+    pub prediction: f64,
+    // user balance after the deposit
+    pub balance: f64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WithdrawEvent {
+    pub user: Pubkey,
+    pub pool: u8,
+    pub amount: f64,
+    // user balance after the withdrawal
+    pub balance: f64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PredictionEvent {
+    pub user: Pubkey,
+    pub pool: u8,
+    pub prediction: f64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GameStartedEvent {
+    pub game_id: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
+#[event]
+pub struct GameSettledEvent {
+    pub game_id: u64,
+    pub winning_pool: u8,
+    // includes sponsored and harvested prize
+    pub total_prize: f64,
+}
+
+#[event]
+pub struct GameCancelledEvent {
+    pub game_id: u64,
+}
+
+#[event]
+pub struct PrizePaidEvent {
+    pub game_id: u64,
+    pub user: Pubkey,
+    pub pool: u8,
+    pub amount: u64,
+}
+
+#[event]
+pub struct PrizeDistributedEvent {
+    pub game_id: u64,
+    pub pool: u8,
+    pub amount: f64,
+    // of the pool after the distribution
+    pub exchange_rate: f64,
+}
+
+// A deposit of donor into the position of user, always emitted along with the DepositEvent of the user
+#[event]
+pub struct GiftEvent {
+    pub donor: Pubkey,
+    pub user: Pubkey,
+    pub pool: u8,
+    pub amount: f64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WithdrawalRequestedEvent {
+    pub user: Pubkey,
+    pub pool: u8,
+    pub amount: f64,
+    // user balance after the request, the pending withdrawal is no longer part of it
+    pub balance: f64,
+    pub release_time: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WithdrawalCancelledEvent {
+    pub user: Pubkey,
+    pub pool: u8,
+    pub amount: f64,
+    pub prediction: f64,
+    // user balance after the pending withdrawal was put back
+    pub balance: f64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WithdrawalCompletedEvent {
+    pub user: Pubkey,
+    pub pool: u8,
+    pub amount: f64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DepositLockedEvent {
+    pub user: Pubkey,
+    pub pool: u8,
+    pub amount: f64,
+    pub games: u8,
+    pub multiplier: f64,
+    pub release_time: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DepositUnlockedEvent {
+    pub user: Pubkey,
+    pub pool: u8,
+    pub amount: f64,
+    pub timestamp: i64,
+}
+
+// A prize added to the balance of a user who auto-compounds, instead of a PrizePaidEvent
+#[event]
+pub struct PrizeCompoundedEvent {
+    pub game_id: u64,
+    pub user: Pubkey,
+    pub pool: u8,
+    pub amount: f64,
+    // user balance after the prize was added
+    pub balance: f64,
 }
 
-const DISCRIMINATOR: usize = 8;
-const TIMESTAMP: usize = 8;
-const AMOUNT: usize = 8;
-const COUNT: usize = 8;
-const STRING_PREFIX: usize = 4; // Stores the size of the string
-const POOL: usize = 20 * 4; // 20 chars max.
+// A balance catching up with the exchange rate of its pool
+#[event]
+pub struct PrizeAccruedEvent {
+    pub user: Pubkey,
+    pub pool: u8,
+    pub amount: f64,
+    // user balance after the accrual
+    pub balance: f64,
+}
 
-// Calculate space for User Account
+// Account space, see the space module
 impl User {
-    const LEN: usize = DISCRIMINATOR
-        + AMOUNT
-        + TIMESTAMP 
-        + COUNT
-        + AMOUNT
-        + AMOUNT
-        + AMOUNT
-        + COUNT
-        + STRING_PREFIX + POOL;
-}
-// Calculate space for Transaction Account
+    pub const LEN: usize = DISCRIMINATOR + User::INIT_SPACE + RESERVED;
+}
+
+impl Game {
+    pub const LEN: usize = DISCRIMINATOR + Game::INIT_SPACE + RESERVED;
+}
+
+impl Sponsorship {
+    pub const LEN: usize = DISCRIMINATOR + Sponsorship::INIT_SPACE;
+}
+
+// the bitmap grows with the number of leaves
+impl Distributor {
+    pub fn space(max_num_nodes: u64) -> usize {
+        DISCRIMINATOR + Distributor::INIT_SPACE + Distributor::bitmap_len(max_num_nodes)
+    }
+}
+
+impl Config {
+    pub const LEN: usize = DISCRIMINATOR + Config::INIT_SPACE + RESERVED;
+}
+
+impl Delegation {
+    pub const LEN: usize = DISCRIMINATOR + Delegation::INIT_SPACE + RESERVED;
+}
+
 impl Transaction {
-    const LEN: usize = DISCRIMINATOR
-        + AMOUNT
-        + TIMESTAMP 
-        + STRING_PREFIX;
+    pub const LEN: usize = DISCRIMINATOR + Transaction::INIT_SPACE;
 }
 
-// Calculate space for Pool Account
 impl Pool {
-    const LEN: usize = DISCRIMINATOR
-        + AMOUNT
-        + TIMESTAMP 
-        + AMOUNT
-        + AMOUNT
-        + COUNT;
+    pub const LEN: usize = DISCRIMINATOR + Pool::INIT_SPACE + RESERVED;
+}
+
+// Calculate space for PoolData Account, zero-copy accounts are stored as they are laid out in memory
+impl PoolData {
+    pub const LEN: usize = DISCRIMINATOR + std::mem::size_of::<PoolData>();
 }
 
 #[error_code]
@@ -647,6 +2921,103 @@ pub enum ErrorCode {
     PoolsInWrongOrder,
     #[msg("Pool data sizes do not match.")]
     PoolsDataSizeDoNotMatch,
-    #[msg("Minimum Deposit amount is 1 sol.")]
+    #[msg("Deposit is below the minimum for this mint.")]
     DepositInsufficient,
+    #[msg("Invalid game id.")]
+    InvalidGameId,
+    #[msg("Remaining accounts must be one (user, user token account) pair per user key.")]
+    BatchAccountsMismatch,
+    #[msg("User account does not match the user key.")]
+    InvalidUserAccount,
+    #[msg("User token account is not owned by the user or has the wrong mint.")]
+    InvalidUserTokenAccount,
+    #[msg("User has already been paid for this game.")]
+    AlreadyPaid,
+    #[msg("Payouts for this game would exceed the recorded prize.")]
+    PrizeExceeded,
+    #[msg("Game has not been settled yet.")]
+    GameNotSettled,
+    #[msg("Game has already been settled.")]
+    GameAlreadySettled,
+    #[msg("Game is not over yet.")]
+    GameNotOver,
+    #[msg("Pool is not the winning pool for this game.")]
+    NotWinningPool,
+    #[msg("Invalid prize amount.")]
+    InvalidPrizeAmount,
+    #[msg("You have no balance in this pool.")]
+    UserNotInPool,
+    #[msg("Invalid merkle proof.")]
+    InvalidProof,
+    #[msg("This leaf has already been claimed.")]
+    AlreadyClaimed,
+    #[msg("Amount must be a positive whole number of token units.")]
+    InvalidAmount,
+    #[msg("Prediction must be a finite number.")]
+    InvalidPrediction,
+    #[msg("Only the admin can do this.")]
+    Unauthorized,
+    #[msg("Deposits are paused.")]
+    ProgramPaused,
+    #[msg("Game already holds a prize and can't be cancelled.")]
+    GameHasPrize,
+    #[msg("Account version is newer than this program supports.")]
+    UnsupportedAccountVersion,
+    #[msg("Pool data has already been created for this pool.")]
+    PoolDataAlreadyCreated,
+    #[msg("Pool data account does not belong to this pool.")]
+    InvalidPoolData,
+    // no longer returned since the participant list is best effort, kept so the codes after it don't change
+    #[msg("Pool has no room for more participants.")]
+    PoolFull,
+    #[msg("Pool has already been snapshotted for this game.")]
+    SnapshotExists,
+    #[msg("Deposits are not accepted in this mint.")]
+    MintNotAllowed,
+    #[msg("This mint is already allowed.")]
+    MintAlreadyAllowed,
+    #[msg("No more mints can be allowed.")]
+    TooManyMints,
+    #[msg("The user's balance is held in another mint.")]
+    WrongMint,
+    #[msg("The pool already has a share mint.")]
+    ShareMintAlreadyCreated,
+    #[msg("Not the pool's share mint.")]
+    InvalidShareMint,
+    #[msg("Part of the balance was deposited by a delegate and can only be withdrawn by it.")]
+    DelegatedBalance,
+    #[msg("The delegate can only withdraw what it deposited.")]
+    DelegationExceeded,
+    #[msg("The delegate still has a deposit in the position.")]
+    DelegationInUse,
+    #[msg("Game is not in progress.")]
+    GameNotInProgress,
+    #[msg("A withdrawal requested in another pool or game is still pending.")]
+    WithdrawalPending,
+    #[msg("There is no withdrawal request for this pool.")]
+    NoWithdrawalRequest,
+    #[msg("The requested withdrawal is released when its game ends.")]
+    WithdrawalNotReleased,
+    #[msg("No lock is offered for this number of games.")]
+    NoLockTier,
+    #[msg("Lock tiers need at least one game and a multiplier of at least one.")]
+    InvalidLockTier,
+    #[msg("Too many lock tiers.")]
+    TooManyLockTiers,
+    #[msg("The user already has a deposit locked.")]
+    AlreadyLocked,
+    #[msg("The user has no deposit locked.")]
+    NoLock,
+    #[msg("The locked amount can't be withdrawn before the lock is released.")]
+    DepositLocked,
+    #[msg("The yield of this mint was already harvested for the game.")]
+    AlreadyHarvested,
+    #[msg("Deposits can only be locked until a day before the game ends.")]
+    LockTooLate,
+    #[msg("The prize of this game is held in another mint.")]
+    PrizeMintMismatch,
+    #[msg("The shares of the withdrawn balance are no longer held by the signer.")]
+    SharesNotHeld,
+    #[msg("There is no yield source adapter for this lending program.")]
+    UnsupportedYieldSource,
 }
\ No newline at end of file
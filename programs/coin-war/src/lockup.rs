@@ -0,0 +1,126 @@
+use anchor_lang::prelude::*;
+
+use crate::{accounting, Config, ErrorCode, LockTier, Pool, User, GAME_DURATION_IN_DAYS, MAX_LOCK_TIERS};
+
+/* Lockups - a user can commit part of its own balance for a number of games with lock_deposit, at one of the lock
+ * lengths the admin set a multiplier for with set_lock_tier. Until the lock is released the balance can't drop
+ * below the locked amount, and the locked amount weighs multiplier times in the prize share of pay_winning_pool_user
+ * and pay_winners_batch: the extra weight is kept in Pool::lock_boost so the shares still add up to the prize.
+ * distribute_prize raises the exchange rate of every balance alike and gives no boost. A lock is released once its
+ * last game, counted from the game it was taken in, is over and settled, so the winning weight settle_game records
+ * has its boost, or a game later if that game is never settled. unlock_deposit then drops the boost. Anyone can call
+ * it, and withdrawals of the user do it themselves. Locks are taken at least MIN_LOCK_AGE_IN_SECS before the end of
+ * the game they are taken in, so the boost can't be picked up just before a game ends.
+ */
+
+const GAME_DURATION_IN_SECS: i64 = GAME_DURATION_IN_DAYS * 24 * 60 * 60;
+pub const MIN_LOCK_AGE_IN_SECS: i64 = 24 * 60 * 60;
+
+impl Config {
+    pub fn lock_tier(&self, games: u8) -> Result<&LockTier> {
+        self.lock_tiers.iter().find(|tier| tier.games == games).ok_or_else(|| error!(ErrorCode::NoLockTier))
+    }
+
+    // Set the multiplier of a lock length, a multiplier of zero removes it. Existing locks keep theirs.
+    pub fn set_lock_tier(&mut self, games: u8, multiplier: f64) -> Result<()> {
+        require!(games > 0, ErrorCode::InvalidLockTier);
+        if multiplier == 0.0 {
+            self.lock_tiers.retain(|tier| tier.games != games);
+            return Ok(());
+        }
+        require!(multiplier.is_finite() && multiplier >= 1.0, ErrorCode::InvalidLockTier);
+        match self.lock_tiers.iter_mut().find(|tier| tier.games == games) {
+            Some(tier) => tier.multiplier = multiplier,
+            None => {
+                require!(self.lock_tiers.len() < MAX_LOCK_TIERS, ErrorCode::TooManyLockTiers);
+                self.lock_tiers.push(LockTier { games, multiplier });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl User {
+    // Prize weight the lock adds on top of the balance
+    pub fn lock_boost(&self) -> f64 {
+        self.locked_amount * (self.lock_multiplier - 1.0)
+    }
+
+    // Weight of the user's balance in the prize shares of pool, none for users in another pool
+    pub fn prize_weight(&self, pool: &Pool) -> f64 {
+        if self.pool != pool.name {
+            return 0.0;
+        }
+        self.balance_at_rate(pool) + self.lock_boost()
+    }
+
+    // settled_until is Config::settled_until
+    pub fn lock_released(&self, now: i64, settled_until: i64) -> bool {
+        self.locked_amount > 0.0 && is_released(self.lock_release_time, now, settled_until)
+    }
+
+    // Checked after a withdrawal of the user's own balance, the locked amount has to be left until the release.
+    // Withdrawals bring the balance up to the exchange rate first, so it is on the basis the lock was taken on.
+    pub fn check_lock(&self, now: i64, settled_until: i64) -> Result<()> {
+        let released = is_released(self.lock_release_time, now, settled_until);
+        require!(released || self.own_balance() >= self.locked_amount, ErrorCode::DepositLocked);
+        Ok(())
+    }
+}
+
+impl Pool {
+    // Total the prize shares are taken out of, see User::prize_weight
+    pub fn prize_weight(&self) -> f64 {
+        self.underlying() + self.lock_boost
+    }
+}
+
+// End of the games-th game counting the one ending at game_end_time, for games run back to back
+pub fn release_time(game_end_time: i64, games: u8) -> i64 {
+    game_end_time + (games as i64 - 1) * GAME_DURATION_IN_SECS
+}
+
+// Whether a lock until release_time is released, given the end of the last settled game
+pub fn is_released(release_time: i64, now: i64, settled_until: i64) -> bool {
+    now >= release_time && (settled_until >= release_time || now >= release_time + GAME_DURATION_IN_SECS)
+}
+
+// Locks are taken in a game in progress ending at game_end_time, early enough to have been held for a while
+pub fn check_lock_age(now: i64, game_end_time: i64) -> Result<()> {
+    require!(now + MIN_LOCK_AGE_IN_SECS <= game_end_time, ErrorCode::LockTooLate);
+    Ok(())
+}
+
+// Lock amount of the user's own balance in pool until release_time, one lock at a time. The amount is counted at the
+// pool's exchange rate like the balance in prize_weight, so prizes distributed since the user's last deposit or
+// withdrawal can be locked too and the boost is on the same basis as the weight it adds to.
+pub fn apply_lock(user: &mut User, pool: &mut Pool, amount: f64, tier: &LockTier, release_time: i64) -> Result<()> {
+    require!(user.locked_amount <= 0.0, ErrorCode::AlreadyLocked);
+    require!(user.pool == pool.name, ErrorCode::UserNotInPool);
+    require!(amount > 0.0 && amount.fract() == 0.0, ErrorCode::InvalidAmount);
+    require!(amount <= user.own_balance_at_rate(pool), ErrorCode::InsufficientBalance);
+    accounting::checkpoint_weight(user, pool);
+
+    user.locked_amount = amount;
+    user.lock_multiplier = tier.multiplier;
+    user.lock_release_time = release_time;
+    pool.lock_boost += user.lock_boost();
+
+    Ok(())
+}
+
+// Release the user's lock in pool once its last game is over and settled. Returns the amount that was locked.
+pub fn apply_unlock(user: &mut User, pool: &mut Pool, now: i64, settled_until: i64) -> Result<f64> {
+    require!(user.locked_amount > 0.0, ErrorCode::NoLock);
+    require!(user.pool == pool.name, ErrorCode::UserNotInPool);
+    require!(is_released(user.lock_release_time, now, settled_until), ErrorCode::DepositLocked);
+    accounting::checkpoint_weight(user, pool);
+    let amount = user.locked_amount;
+
+    pool.lock_boost -= user.lock_boost();
+    user.locked_amount = 0.0;
+    user.lock_multiplier = 0.0;
+    user.lock_release_time = 0;
+
+    Ok(amount)
+}
@@ -0,0 +1,103 @@
+use crate::{AMOUNT_DECIMALS, GAME_DURATION_IN_DAYS};
+
+/* Game math - weighted balances, prediction averages, prize shares and winner selection. Everything here works on
+ * plain values so the instruction handlers, the accounting module and off-chain tools all share the exact same
+ * calculations.
+ */
+
+// A user's balance weighted by the number of days it is held during a game
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WeightedBalance {
+    pub average_balance: f64,
+    pub weighted_balance: f64,
+    pub weighted_days: i64,
+}
+
+impl WeightedBalance {
+    // balance held for the whole game, used when a game starts over for the user
+    pub fn full_game(balance: f64) -> WeightedBalance {
+        WeightedBalance {
+            average_balance: balance,
+            weighted_balance: balance * GAME_DURATION_IN_DAYS as f64,
+            weighted_days: GAME_DURATION_IN_DAYS,
+        }
+    }
+
+    // add amount held for the days_left days until the game is over
+    pub fn add_deposit(self, amount: f64, days_left: i64) -> WeightedBalance {
+        let weighted_balance = self.weighted_balance + days_left as f64 * amount;
+        let weighted_days = self.weighted_days + days_left;
+        WeightedBalance {
+            average_balance: weighted_balance / weighted_days as f64,
+            weighted_balance,
+            weighted_days,
+        }
+    }
+}
+
+// Pool average after one user's prediction changes from old_prediction to new_prediction, while the number of
+// users counted in the average goes from count_before to count_after
+pub fn replace_prediction(
+    average_prediction: f64,
+    count_before: u64,
+    count_after: u64,
+    old_prediction: f64,
+    new_prediction: f64,
+) -> f64 {
+    let total_prediction = average_prediction * count_before as f64;
+    let new_total_prediction = total_prediction - old_prediction + new_prediction;
+    if count_after > 0 {
+        new_total_prediction / count_after as f64
+    } else {
+        0.0
+    }
+}
+
+// Share of the prize for a user, proportional to their part of the pool. Rounded down to a whole amount, so the
+// shares of the whole pool never add up to more than the prize whatever the floating point error.
+pub fn prize_share(user_balance: f64, total_deposit: f64, prize_amount: f64) -> f64 {
+    if total_deposit <= 0.0 {
+        return 0.0;
+    }
+    let percentage_of_pool = user_balance / total_deposit;
+    (percentage_of_pool * prize_amount).floor()
+}
+
+// Pick the pool whose prediction has the smallest delta to its coin price. Predictions and prices are given in
+// the order of the Pools enum and the pool code of the winner is returned.
+pub fn select_winning_pool(pool_predictions: &[f64], pool_coin_prices: &[f64]) -> u8 {
+    let mut winning_index = 0;
+    let mut current_smallest_delta = 100000.000;
+    for (i, (prediction, price)) in pool_predictions.iter().zip(pool_coin_prices.iter()).enumerate() {
+        let delta = (prediction - price).abs();
+        if delta < current_smallest_delta {
+            current_smallest_delta = delta;
+            winning_index = i;
+        }
+    }
+    winning_index as u8 + 1
+}
+
+// Power of ten between AMOUNT_DECIMALS units and base units of a mint with decimals, exact in f64 for mints up to 22
+// decimals away from AMOUNT_DECIMALS, and whether base units are the smaller of the two
+fn decimal_scale(decimals: u8) -> (f64, bool) {
+    let shift = decimals as i32 - AMOUNT_DECIMALS as i32;
+    (10f64.powi(shift.abs()), shift >= 0)
+}
+
+// Whole base units of a mint with decimals in amount, rounded down. Amounts too big for a u64 saturate.
+pub fn to_base_units(amount: f64, decimals: u8) -> u64 {
+    let (scale, base_units_smaller) = decimal_scale(decimals);
+    let base_units = if base_units_smaller { amount * scale } else { amount / scale };
+    base_units.floor() as u64
+}
+
+// Amount in AMOUNT_DECIMALS units of base_units of a mint with decimals
+pub fn from_base_units(base_units: u64, decimals: u8) -> f64 {
+    let (scale, base_units_smaller) = decimal_scale(decimals);
+    if base_units_smaller {
+        base_units as f64 / scale
+    } else {
+        base_units as f64 * scale
+    }
+}
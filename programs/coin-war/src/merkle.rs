@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+
+/* Merkle trees of create_distributor and claim_with_proof. Leaves and inner nodes are hashed with a different prefix
+ * byte, so the hash of two nodes can never pass for a leaf, and pairs are hashed in sorted order so a proof does not
+ * need to carry left/right flags. Off-chain tools building the tree use the same functions.
+ */
+
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+
+// Leaf of a claim of amount, in AMOUNT_DECIMALS units, by claimant at index
+pub fn leaf(index: u64, claimant: &Pubkey, amount: u64) -> [u8; 32] {
+    keccak::hashv(&[&[LEAF_PREFIX], &index.to_le_bytes(), claimant.as_ref(), &amount.to_le_bytes()]).0
+}
+
+pub fn node(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    keccak::hashv(&[&[NODE_PREFIX], first, second]).0
+}
+
+pub fn verify_proof(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+    proof.iter().fold(leaf, |computed_hash, proof_element| node(&computed_hash, proof_element)) == root
+}
@@ -0,0 +1,108 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+
+use crate::{Config, ErrorCode, Game, Pool, Transaction, User};
+
+/* Account versioning. Config, User, Pool, Game and Transaction accounts have a version byte that is bumped whenever
+ * their layout changes. Fields have only ever been appended so far, so every earlier layout is a prefix of the
+ * current one: padded with zeros to the current size it reads as the current layout with the new fields zeroed and
+ * version 0, the version of every account created before versioning. Conversions that need more than that go in
+ * the upgrade functions below, matched on the stored version.
+ */
+
+pub const CONFIG_VERSION: u8 = 2;
+pub const USER_VERSION: u8 = 9;
+pub const POOL_VERSION: u8 = 6;
+pub const GAME_VERSION: u8 = 5;
+pub const TRANSACTION_VERSION: u8 = 2;
+
+// Read account data in the current or any earlier layout of T, which is len bytes at most
+fn read_padded<T: AccountDeserialize>(data: &[u8], len: usize) -> Result<T> {
+    let mut padded = data.to_vec();
+    if padded.len() < len {
+        padded.resize(len, 0);
+    }
+    T::try_deserialize(&mut padded.as_slice())
+}
+
+fn check_version(version: u8, current: u8) -> Result<()> {
+    require!(version <= current, ErrorCode::UnsupportedAccountVersion);
+    Ok(())
+}
+
+// Version 0 configs may be from before the mint allow-list or the lock tiers, their reserved bytes read as empty
+// lists. This also grows them to hold MAX_MINTS mints and MAX_LOCK_TIERS tiers. Version 1 configs have no
+// settled_until, locks ending before the next settle_game are released a game late unless it settles their game.
+pub fn upgrade_config(data: &[u8]) -> Result<Config> {
+    let mut config: Config = read_padded(data, Config::LEN)?;
+    check_version(config.version, CONFIG_VERSION)?;
+    config.version = CONFIG_VERSION;
+    Ok(config)
+}
+
+// Version 0 users may be missing last_paid_game_id, zero means never paid. Version 1 users have no
+// participant_slot, zero means not listed. Version 2 users have no mint, the default takes any mint until their next
+// deposit records one. Version 3 users have no share_rate, zero reads as one like the pools, nor shares, see
+// issue_shares. Version 4 users have no delegated_balance, nothing was deposited by delegates before. Version 5
+// users have no pending withdrawal. Version 6 users have nothing locked. Version 7 users are paid out, there was no
+// auto_compound. Version 8 users have no settled_weight, pools had no won_game_id to keep it for.
+pub fn upgrade_user(data: &[u8]) -> Result<User> {
+    let mut user: User = read_padded(data, User::LEN)?;
+    check_version(user.version, USER_VERSION)?;
+    user.version = USER_VERSION;
+    Ok(user)
+}
+
+// Version 0 pools may be missing invested, zero since nothing was lent out before the yield source existed.
+// Version 1 pools have no pool_data until create_pool_data. Version 2 pools have no share_mint until create_share_mint,
+// their zero share_rate reads as one. Version 3 pools have no pending_withdrawals, withdrawals were never queued.
+// Version 4 pools have no lock_boost. Version 5 pools have no won_game_id, their users' prize weights are taken as
+// they are at payout like before.
+pub fn upgrade_pool(data: &[u8]) -> Result<Pool> {
+    let mut pool: Pool = read_padded(data, Pool::LEN)?;
+    check_version(pool.version, POOL_VERSION)?;
+    pool.version = POOL_VERSION;
+    Ok(pool)
+}
+
+// Version 0 games may be missing total_paid and the sponsorship totals, games from then had no sponsors. Version 1
+// games have no compounded_prize, none of their prize was compounded. Version 2 games have no winning_weight, see
+// accounting::winning_weight. Version 3 games have no harvested_mints, their yield can be harvested once more.
+// Version 4 games have no prize_mint, their prize can be paid in any mint.
+pub fn upgrade_game(data: &[u8]) -> Result<Game> {
+    let mut game: Game = read_padded(data, Game::LEN)?;
+    check_version(game.version, GAME_VERSION)?;
+    game.version = GAME_VERSION;
+    Ok(game)
+}
+
+// Transactions are never written after they are created, this is only for reading old ones. Version 1 transactions
+// have no donor, there were no gift deposits before.
+pub fn upgrade_transaction(data: &[u8]) -> Result<Transaction> {
+    let mut transaction: Transaction = read_padded(data, Transaction::LEN)?;
+    check_version(transaction.version, TRANSACTION_VERSION)?;
+    transaction.version = TRANSACTION_VERSION;
+    Ok(transaction)
+}
+
+// Write an upgraded account back, first growing it to len with the payer covering the extra rent. Accounts are
+// never shrunk.
+pub fn write_upgraded<'info, T: AccountSerialize>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    upgraded: &T,
+    len: usize,
+) -> Result<()> {
+    if account.data_len() < len {
+        let rent = Rent::get()?.minimum_balance(len).saturating_sub(account.lamports());
+        if rent > 0 {
+            let cpi_accounts = system_program::Transfer { from: payer.clone(), to: account.clone() };
+            system_program::transfer(CpiContext::new(system_program.clone(), cpi_accounts), rent)?;
+        }
+        account.realloc(len, true)?;
+    }
+
+    let mut data = account.try_borrow_mut_data()?;
+    upgraded.try_serialize(&mut &mut data[..])
+}
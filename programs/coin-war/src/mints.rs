@@ -0,0 +1,96 @@
+use anchor_lang::prelude::*;
+
+use crate::{math, AllowedMint, Config, ErrorCode, Game, MAX_MINTS};
+
+/* Mints - the allow-list of mints deposits are accepted in, kept in Config. Instructions moving tokens look the mint
+ * they are given up here, so tokens of any other mint never get in, and every mint has its own pool wallet seeded
+ * with the mint. Amounts in instructions and in the User, Pool and Game accounts are in AMOUNT_DECIMALS units
+ * whatever the mint, converted to base units with the decimals read from the mint account when it was added.
+ * Mints can belong to the SPL Token or the Token-2022 program. Deposits in mints with a transfer fee are credited
+ * with what the pool wallet received, withdrawals and payouts leave the fee to the recipient.
+ * A game's prize is held in a single mint, the first one it is funded or harvested in, and is only paid out in that
+ * mint, out of its treasury so prizes never come out of the deposits.
+ */
+
+impl Config {
+    pub fn allowed_mint(&self, mint: &Pubkey) -> Result<&AllowedMint> {
+        self.mints.iter().find(|allowed| allowed.mint == *mint).ok_or_else(|| error!(ErrorCode::MintNotAllowed))
+    }
+
+    pub fn allowed_mint_mut(&mut self, mint: &Pubkey) -> Result<&mut AllowedMint> {
+        self.mints.iter_mut().find(|allowed| allowed.mint == *mint).ok_or_else(|| error!(ErrorCode::MintNotAllowed))
+    }
+
+    // Position of the mint in the allow-list. Mints are never removed, so it doesn't change.
+    pub fn mint_index(&self, mint: &Pubkey) -> Result<usize> {
+        self.mints.iter().position(|allowed| allowed.mint == *mint).ok_or_else(|| error!(ErrorCode::MintNotAllowed))
+    }
+
+    pub fn add_mint(&mut self, mint: Pubkey, decimals: u8, minimum_deposit: u64) -> Result<()> {
+        require!(self.allowed_mint(&mint).is_err(), ErrorCode::MintAlreadyAllowed);
+        require!(self.mints.len() < MAX_MINTS, ErrorCode::TooManyMints);
+        self.mints.push(AllowedMint { mint, decimals, minimum_deposit, deposits: 0, invested: 0 });
+        Ok(())
+    }
+}
+
+impl AllowedMint {
+    // Base units moved for a deposit or withdrawal of amount, which has to be a whole number of them so the books
+    // match the pool wallet
+    pub fn to_base_units(&self, amount: f64) -> Result<u64> {
+        let base_units = math::to_base_units(amount, self.decimals);
+        require!(base_units > 0 && self.to_amount(base_units) == amount, ErrorCode::InvalidAmount);
+        Ok(base_units)
+    }
+
+    // Base units of a deposit of amount, which has to be at least the minimum
+    pub fn deposit_units(&self, amount: f64) -> Result<u64> {
+        require!(math::to_base_units(amount, self.decimals) >= self.minimum_deposit, ErrorCode::DepositInsufficient);
+        self.to_base_units(amount)
+    }
+
+    // Base units paid out for a prize of amount, rounded down so payouts never exceed the prize
+    pub fn payout_units(&self, amount: f64) -> u64 {
+        math::to_base_units(amount, self.decimals)
+    }
+
+    // Base units paid out for a prize of amount, which the treasury of the pool wallet has to cover
+    pub fn prize_units(&self, amount: f64, pool_wallet_amount: u64) -> Result<u64> {
+        let units = self.payout_units(amount);
+        require!(units <= self.treasury(pool_wallet_amount), ErrorCode::InsufficientBalance);
+        Ok(units)
+    }
+
+    // Amount credited for base_units that arrived in the pool wallet, which with a transfer fee aren't always a whole
+    // amount. Returns the amount rounded down and its base units, the rest stays in the pool wallet as treasury.
+    pub fn received(&self, base_units: u64) -> (f64, u64) {
+        let amount = self.to_amount(base_units).floor();
+        (amount, math::to_base_units(amount, self.decimals))
+    }
+
+    pub fn to_amount(&self, base_units: u64) -> f64 {
+        math::from_base_units(base_units, self.decimals)
+    }
+
+    // Base units in the pool wallet that are not owed to depositors. Funds lent out to the yield source are still
+    // deposits but are not in the pool wallet, so the wallet only has to cover the rest.
+    pub fn treasury(&self, pool_wallet_amount: u64) -> u64 {
+        (pool_wallet_amount + self.invested).saturating_sub(self.deposits)
+    }
+}
+
+impl Game {
+    // Adds amount in mint to the prize, which the first funding or harvest fixes the mint of
+    pub fn add_prize(&mut self, mint: &Pubkey, amount: f64) -> Result<()> {
+        self.check_prize_mint(mint)?;
+        self.prize_mint = *mint;
+        self.total_prize += amount;
+        Ok(())
+    }
+
+    // Games that were never funded, or were funded before the prize mint was recorded, can be paid in any mint
+    pub fn check_prize_mint(&self, mint: &Pubkey) -> Result<()> {
+        require!(self.prize_mint == Pubkey::default() || self.prize_mint == *mint, ErrorCode::PrizeMintMismatch);
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+
+use crate::{ErrorCode, ParticipantSlot, PoolData, PoolSnapshot, MAX_PARTICIPANTS, SNAPSHOT_COUNT};
+
+/* Pool data - the participant list and snapshot history kept in each pool's zero-copy PoolData account. Every
+ * operation here touches a fixed number of entries, so its cost doesn't depend on how many participants there are:
+ * slots freed by withdrawals go on a stack and are reused before new ones, and snapshots go round a ring buffer.
+ * Slots are handed out counted from 1 so User::participant_slot can use 0 for not listed.
+ */
+
+impl PoolData {
+    // Put wallet on the participant list and return its slot, None when the list is full
+    pub fn add_participant(&mut self, wallet: Pubkey) -> Option<u32> {
+        let index = if self.free_slot_count > 0 {
+            self.free_slot_count -= 1;
+            self.free_slots[self.free_slot_count as usize].index
+        } else {
+            if self.slots_used as usize >= MAX_PARTICIPANTS {
+                return None;
+            }
+            self.slots_used += 1;
+            self.slots_used - 1
+        };
+        self.participants[index as usize] = wallet;
+        self.participant_count += 1;
+        Some(index + 1)
+    }
+
+    // Take wallet, listed in slot, off the participant list
+    pub fn remove_participant(&mut self, slot: u32, wallet: Pubkey) -> Result<()> {
+        require!(slot > 0 && slot <= self.slots_used, ErrorCode::InvalidPoolData);
+        let index = slot - 1;
+        require!(self.participants[index as usize] == wallet, ErrorCode::InvalidPoolData);
+        self.participants[index as usize] = Pubkey::default();
+        self.free_slots[self.free_slot_count as usize] = ParticipantSlot { index };
+        self.free_slot_count += 1;
+        self.participant_count -= 1;
+        Ok(())
+    }
+
+    // Snapshot of the most recent game, if any
+    pub fn latest_snapshot(&self) -> Option<PoolSnapshot> {
+        if self.snapshot_count == 0 {
+            return None;
+        }
+        Some(self.snapshots[((self.snapshot_count - 1) % SNAPSHOT_COUNT as u64) as usize])
+    }
+
+    // Add a snapshot to the history, games have to be snapshotted in order and only once
+    pub fn record_snapshot(&mut self, snapshot: PoolSnapshot) -> Result<()> {
+        if let Some(latest) = self.latest_snapshot() {
+            require!(snapshot.game_id > latest.game_id, ErrorCode::SnapshotExists);
+        }
+        self.snapshots[(self.snapshot_count % SNAPSHOT_COUNT as u64) as usize] = snapshot;
+        self.snapshot_count += 1;
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Burn, MintTo};
+use anchor_spl::token_interface::TokenAccount;
+
+use crate::{ErrorCode, Pool, User};
+
+/* Shares - receipt tokens for pool deposits. Every pool has an SPL Token share mint with AMOUNT_DECIMALS decimals
+ * and the pool as mint authority, attached with create_share_mint. Deposits mint shares to the depositor at the
+ * pool's exchange rate and withdrawals burn them from the withdrawing user, so positions show up in wallets and can
+ * be held by other programs, while only the user they were issued to can withdraw, and only holding the shares.
+ * A prize handed out with distribute_prize stays in the pool and raises the exchange rate instead, so every share of
+ * the winning pool gains its part of the prize, the same part prize_share would pay out. Balances catch up with the
+ * rate the next time the user deposits or withdraws, until then the difference is in Pool::pending_prize.
+ */
+
+impl Pool {
+    // Underlying amount per share, pools from before shares start at one
+    pub fn exchange_rate(&self) -> f64 {
+        if self.share_rate > 0.0 {
+            self.share_rate
+        } else {
+            1.0
+        }
+    }
+
+    // Deposits plus prize distributed to them that balances haven't caught up with yet
+    pub fn underlying(&self) -> f64 {
+        self.total_deposit + self.pending_prize
+    }
+
+    // Raise the exchange rate so the pool's underlying grows by prize, which comes out of pending_prize as balances
+    // catch up
+    pub fn distribute(&mut self, prize: f64) -> Result<()> {
+        let underlying = self.underlying();
+        require!(underlying > 0.0, ErrorCode::InvalidPrizeAmount);
+        self.share_rate = self.exchange_rate() * (underlying + prize) / underlying;
+        self.pending_prize += prize;
+        Ok(())
+    }
+}
+
+impl User {
+    // Exchange rate the balance last caught up with
+    pub fn share_rate(&self) -> f64 {
+        if self.share_rate > 0.0 {
+            self.share_rate
+        } else {
+            1.0
+        }
+    }
+
+    // Balance in pool at its current exchange rate, in whole amounts like the balance itself
+    pub fn balance_at_rate(&self, pool: &Pool) -> f64 {
+        if self.pool != pool.name || self.balance <= 0.0 {
+            return self.balance;
+        }
+        (self.balance * pool.exchange_rate() / self.share_rate()).floor()
+    }
+
+    // The user's own part of balance_at_rate, leaving out what delegates deposited
+    pub fn own_balance_at_rate(&self, pool: &Pool) -> f64 {
+        if self.pool != pool.name || self.own_balance() <= 0.0 {
+            return self.own_balance();
+        }
+        (self.own_balance() * pool.exchange_rate() / self.share_rate()).floor()
+    }
+
+    // Shares owed for the balance, for positions from before the pool had a share mint and compounded prizes
+    pub fn unissued_shares(&self) -> u64 {
+        shares_for_deposit(self.own_balance(), self.share_rate()).saturating_sub(self.shares)
+    }
+}
+
+// Shares minted for a deposit of amount, rounded down
+pub fn shares_for_deposit(amount: f64, rate: f64) -> u64 {
+    (amount / rate).floor() as u64
+}
+
+// Shares burned for a withdrawal of amount out of issued, rounded up so the shares left never back more than the
+// balance left. The last of the balance takes all of them.
+pub fn shares_for_withdrawal(amount: f64, balance_after: f64, rate: f64, issued: u64) -> u64 {
+    if balance_after <= 0.0 {
+        return issued;
+    }
+    ((amount / rate).ceil() as u64).min(issued)
+}
+
+// Mint shares to destination, signed by the pool
+pub fn mint_shares<'info>(
+    token_program: AccountInfo<'info>,
+    share_mint: AccountInfo<'info>,
+    destination: AccountInfo<'info>,
+    pool: AccountInfo<'info>,
+    pool_name: u8,
+    pool_bump: u8,
+    shares: u64,
+) -> Result<()> {
+    if shares == 0 {
+        return Ok(());
+    }
+    let seeds = [std::slice::from_ref(&pool_name), std::slice::from_ref(&pool_bump)];
+    let signer_seeds = [&seeds[..]];
+    let cpi_accounts = MintTo { mint: share_mint, to: destination, authority: pool };
+    token::mint_to(CpiContext::new_with_signer(token_program, cpi_accounts, &signer_seeds), shares)
+}
+
+// Burn shares held by the signing owner, all of them or the withdrawal fails
+pub fn burn_shares<'info>(
+    token_program: AccountInfo<'info>,
+    share_mint: AccountInfo<'info>,
+    source: &InterfaceAccount<'info, TokenAccount>,
+    owner: AccountInfo<'info>,
+    shares: u64,
+) -> Result<()> {
+    if shares == 0 {
+        return Ok(());
+    }
+    require!(source.amount >= shares, ErrorCode::SharesNotHeld);
+    let cpi_accounts = Burn { mint: share_mint, from: source.to_account_info(), authority: owner };
+    token::burn(CpiContext::new(token_program, cpi_accounts), shares)
+}
@@ -0,0 +1,8 @@
+/* Account space. Each account's LEN is the discriminator plus its INIT_SPACE, which #[derive(InitSpace)] sums from
+ * the Borsh sizes of its field types, so a field can't be left out or counted with the wrong type. Vec fields are
+ * counted at their max_len. Accounts that live on from game to game get RESERVED zeroed bytes on top, so fields
+ * appended later fit without a realloc and read as zero in accounts created before them, see the migrate module.
+ */
+
+pub const DISCRIMINATOR: usize = 8;
+pub const RESERVED: usize = 64;
@@ -0,0 +1,114 @@
+use anchor_lang::prelude::*;
+
+use crate::{ErrorCode, HarvestYield, ManageYield};
+
+/* Yield sources - lending programs idle pool funds are lent out to so there is interest to pay out as the prize.
+ * ManageYield and HarvestYield take the lending program and its accounts unchecked, the adapter for the lending
+ * program passed in interprets them and the lending program validates them. Adapters are compiled in behind a
+ * feature each, and the lending program has to be one of theirs, so builds without an adapter can't lend out.
+ * The mock lending program in this workspace is only for tests, behind the mock-lending feature.
+ */
+
+// A yield source idle pool funds can be lent out to. The pool wallet is always the depositor and signs with its
+// seeds.
+pub trait YieldSource<'info> {
+    fn deposit(&self, amount: u64, signer_seeds: &[&[&[u8]]]) -> Result<()>;
+    fn withdraw(&self, amount: u64, signer_seeds: &[&[&[u8]]]) -> Result<()>;
+    // Move the interest earned so far into the pool token account, leaving the principal lent out
+    fn harvest(&self, signer_seeds: &[&[&[u8]]]) -> Result<()>;
+}
+
+// Accounts of a yield source CPI, see the adapter of lending_program for what the others are
+pub struct YieldAccounts<'info> {
+    pub lending_program: AccountInfo<'info>,
+    pub reserve: AccountInfo<'info>,
+    pub reserve_vault: AccountInfo<'info>,
+    pub obligation: AccountInfo<'info>,
+    pub pool_token_account: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+}
+
+// Adapter for the lending program of accounts, if one is compiled in
+pub fn yield_source<'info>(accounts: YieldAccounts<'info>) -> Result<Box<dyn YieldSource<'info> + 'info>> {
+    #[cfg(feature = "mock-lending")]
+    if accounts.lending_program.key() == mock_lending::ID {
+        return Ok(Box::new(MockLendingYieldSource(accounts)));
+    }
+    msg!("no yield source adapter for {}", accounts.lending_program.key());
+    err!(ErrorCode::UnsupportedYieldSource)
+}
+
+impl<'info> ManageYield<'info> {
+    pub fn yield_source(&self) -> Result<Box<dyn YieldSource<'info> + 'info>> {
+        yield_source(YieldAccounts {
+            lending_program: self.lending_program.to_account_info(),
+            reserve: self.reserve.to_account_info(),
+            reserve_vault: self.reserve_vault.to_account_info(),
+            obligation: self.obligation.to_account_info(),
+            pool_token_account: self.pool_token_account.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+        })
+    }
+}
+
+impl<'info> HarvestYield<'info> {
+    pub fn yield_source(&self) -> Result<Box<dyn YieldSource<'info> + 'info>> {
+        yield_source(YieldAccounts {
+            lending_program: self.lending_program.to_account_info(),
+            reserve: self.reserve.to_account_info(),
+            reserve_vault: self.reserve_vault.to_account_info(),
+            obligation: self.obligation.to_account_info(),
+            pool_token_account: self.pool_token_account.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+        })
+    }
+}
+
+// Yield source backed by the mock lending program, for tests
+#[cfg(feature = "mock-lending")]
+struct MockLendingYieldSource<'info>(YieldAccounts<'info>);
+
+#[cfg(feature = "mock-lending")]
+impl<'info> MockLendingYieldSource<'info> {
+    fn withdraw_accounts(&self) -> mock_lending::cpi::accounts::Withdraw<'info> {
+        mock_lending::cpi::accounts::Withdraw {
+            owner: self.0.pool_token_account.clone(),
+            reserve: self.0.reserve.clone(),
+            reserve_vault: self.0.reserve_vault.clone(),
+            obligation: self.0.obligation.clone(),
+            destination: self.0.pool_token_account.clone(),
+            token_program: self.0.token_program.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "mock-lending")]
+impl<'info> YieldSource<'info> for MockLendingYieldSource<'info> {
+    fn deposit(&self, amount: u64, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+        let cpi_accounts = mock_lending::cpi::accounts::Deposit {
+            owner: self.0.pool_token_account.clone(),
+            reserve: self.0.reserve.clone(),
+            reserve_vault: self.0.reserve_vault.clone(),
+            obligation: self.0.obligation.clone(),
+            source: self.0.pool_token_account.clone(),
+            token_program: self.0.token_program.clone(),
+        };
+        mock_lending::cpi::deposit(
+            CpiContext::new_with_signer(self.0.lending_program.clone(), cpi_accounts, signer_seeds),
+            amount,
+        )
+    }
+
+    fn withdraw(&self, amount: u64, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+        mock_lending::cpi::withdraw(
+            CpiContext::new_with_signer(self.0.lending_program.clone(), self.withdraw_accounts(), signer_seeds),
+            amount,
+        )
+    }
+
+    fn harvest(&self, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+        mock_lending::cpi::harvest(
+            CpiContext::new_with_signer(self.0.lending_program.clone(), self.withdraw_accounts(), signer_seeds),
+        )
+    }
+}
@@ -0,0 +1,352 @@
+use anchor_lang::prelude::{require, Pubkey};
+use anchor_lang::AnchorSerialize;
+use coin_war::{accounting, math, ErrorCode, Game, Pool, User};
+use proptest::prelude::*;
+
+const POOLS: [u8; 4] = [1, 2, 3, 4];
+const USERS: usize = 5;
+
+#[derive(Clone, Debug)]
+enum Op {
+    Deposit { user: usize, pool: u8, amount: f64, prediction: f64 },
+    Withdraw { user: usize, pool: u8, amount: f64 },
+    // withdraw a share of the balance so partial and full withdrawals are both common
+    WithdrawShare { user: usize, percent: u8 },
+    MakePrediction { user: usize, pool: u8, prediction: f64 },
+    // withdraw everything and deposit into another pool
+    Switch { user: usize, pool: u8, prediction: f64 },
+    // queue a share of the balance until the game ending at release_time
+    RequestWithdrawal { user: usize, percent: u8, release_time: i64 },
+    CancelWithdrawal { user: usize, prediction: f64 },
+    CompleteWithdrawal { user: usize, now: i64 },
+    // settle a new game won by pool with a share of the reserves as its prize
+    Settle { pool: u8, percent: u8 },
+    // pay the user for the last settled game, into the balance for users who auto-compound
+    Pay { user: usize },
+    SetAutoCompound { user: usize, auto_compound: bool },
+}
+
+// In-memory copy of the accounts the instructions touch, plus the pool wallet token balance. The pool wallet only
+// moves by the amounts the accounting functions return, as the instruction handlers transfer those.
+struct Model {
+    users: Vec<User>,
+    pools: Vec<Pool>,
+    // last settled game
+    game: Game,
+    vault: u64,
+    // tokens that were in the pool wallet from the start, e.g. sponsored prizes, and haven't been paid out
+    reserves: u64,
+}
+
+fn new_user() -> User {
+    User {
+        pool: 0,
+        last_prediction: 0.0,
+        balance: 0.0,
+        last_active: 0,
+        game_history_count: 0,
+        current_average_balance: 0.0,
+        current_weighted_balance: 0.0,
+        current_weighted_days: 0,
+        txn_count: 0,
+        last_paid_game_id: 0,
+        version: 1,
+        participant_slot: 0,
+        mint: Pubkey::default(),
+        share_rate: 0.0,
+        shares: 0,
+        delegated_balance: 0.0,
+        pending_withdrawal: 0.0,
+        withdrawal_pool: 0,
+        withdrawal_release_time: 0,
+        locked_amount: 0.0,
+        lock_multiplier: 0.0,
+        lock_release_time: 0,
+        auto_compound: false,
+        settled_weight: 0.0,
+        settled_game_id: 0,
+    }
+}
+
+fn new_pool(name: u8) -> Pool {
+    Pool {
+        is_initialized: true,
+        last_update_timestamp: 0,
+        total_deposit: 0.0,
+        user_count: 0,
+        name,
+        average_prediction: 0.0,
+        invested: 0.0,
+        version: 1,
+        pool_data: Pubkey::default(),
+        share_mint: Pubkey::default(),
+        share_rate: 0.0,
+        pending_prize: 0.0,
+        pending_withdrawals: 0.0,
+        lock_boost: 0.0,
+        won_game_id: 0,
+    }
+}
+
+fn new_game() -> Game {
+    Game {
+        game_id: 0,
+        start_time: 0,
+        end_time: 0,
+        winning_pool: 0,
+        winning_amount: 0.0,
+        total_prize: 0.0,
+        total_paid: 0.0,
+        sponsored_prize: 0.0,
+        sponsor_count: 0,
+        version: 1,
+        compounded_prize: 0.0,
+        winning_weight: 0.0,
+        harvested_mints: 0,
+        prize_mint: Pubkey::default(),
+    }
+}
+
+impl Model {
+    fn new(reserves: u64) -> Model {
+        Model {
+            users: (0..USERS).map(|_| new_user()).collect(),
+            pools: POOLS.iter().map(|name| new_pool(*name)).collect(),
+            game: new_game(),
+            vault: reserves,
+            reserves,
+        }
+    }
+
+    fn snapshot(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for user in &self.users {
+            bytes.extend(user.try_to_vec().unwrap());
+        }
+        for pool in &self.pools {
+            bytes.extend(pool.try_to_vec().unwrap());
+        }
+        bytes.extend(self.game.try_to_vec().unwrap());
+        bytes
+    }
+
+    fn deposit(&mut self, user: usize, pool: u8, amount: f64, prediction: f64) -> anchor_lang::Result<()> {
+        let pool = &mut self.pools[pool as usize - 1];
+        let credited = accounting::apply_deposit(&mut self.users[user], pool, amount, prediction)?;
+        self.vault += credited as u64;
+        Ok(())
+    }
+
+    fn withdraw(&mut self, user: usize, pool: u8, amount: f64) -> anchor_lang::Result<()> {
+        let pool = &mut self.pools[pool as usize - 1];
+        let withdrawn = accounting::apply_withdraw(&mut self.users[user], pool, amount)?;
+        self.vault -= withdrawn as u64;
+        Ok(())
+    }
+
+    // Like settle_game, after the reserves were funded or harvested into the prize
+    fn settle(&mut self, pool: u8, percent: u8) {
+        let pool = &mut self.pools[pool as usize - 1];
+        let game_id = self.game.game_id + 1;
+        self.game = Game {
+            game_id,
+            winning_pool: pool.name,
+            total_prize: (self.reserves * percent as u64 / 100) as f64,
+            winning_weight: pool.prize_weight(),
+            ..new_game()
+        };
+        pool.won_game_id = game_id;
+    }
+
+    // Like pay_winning_pool_user
+    fn pay(&mut self, user: usize) -> anchor_lang::Result<()> {
+        let game = &mut self.game;
+        require!(game.winning_pool != 0, ErrorCode::GameNotSettled);
+        let user = &mut self.users[user];
+        let pool = &mut self.pools[game.winning_pool as usize - 1];
+        require!(user.last_paid_game_id < game.game_id, ErrorCode::AlreadyPaid);
+
+        let weight = accounting::user_weight(user, pool, game);
+        let prize = math::prize_share(weight, accounting::winning_weight(pool, game), game.total_prize);
+        require!(game.total_paid + prize <= game.total_prize, ErrorCode::PrizeExceeded);
+        if accounting::compounds(user, pool, &user.mint) {
+            let amount = accounting::apply_compound(user, pool, prize)?;
+            game.total_paid += amount;
+            game.compounded_prize += amount;
+            self.reserves -= amount as u64;
+        } else {
+            // payouts are rounded down to whole tokens, the rest stays in the pool wallet
+            self.vault -= prize as u64;
+            self.reserves -= prize as u64;
+            game.total_paid += prize;
+        }
+        accounting::reset_after_payout(user, game.game_id);
+        Ok(())
+    }
+
+    fn apply(&mut self, op: &Op) -> anchor_lang::Result<()> {
+        match *op {
+            Op::Deposit { user, pool, amount, prediction } => self.deposit(user, pool, amount, prediction),
+            Op::Withdraw { user, pool, amount } => self.withdraw(user, pool, amount),
+            Op::WithdrawShare { user, percent } => {
+                let pool = self.users[user].pool.max(1);
+                let amount = (self.users[user].balance * percent as f64 / 100.0).ceil();
+                self.withdraw(user, pool, amount)
+            }
+            Op::MakePrediction { user, pool, prediction } => {
+                accounting::apply_prediction(&mut self.users[user], &mut self.pools[pool as usize - 1], prediction)
+            }
+            Op::Switch { user, pool, prediction } => {
+                let balance = self.users[user].balance;
+                let from = self.users[user].pool;
+                if from == 0 || balance <= 0.0 {
+                    return self.deposit(user, pool, 1.0, prediction);
+                }
+                self.withdraw(user, from, balance)?;
+                self.deposit(user, pool, balance, prediction)
+            }
+            Op::RequestWithdrawal { user, percent, release_time } => {
+                let pool = self.users[user].pool.max(1);
+                let amount = (self.users[user].balance * percent as f64 / 100.0).ceil();
+                let pool = &mut self.pools[pool as usize - 1];
+                accounting::apply_withdrawal_request(&mut self.users[user], pool, amount, release_time)
+            }
+            Op::CancelWithdrawal { user, prediction } => {
+                let pool = &mut self.pools[self.users[user].withdrawal_pool.max(1) as usize - 1];
+                accounting::apply_withdrawal_cancel(&mut self.users[user], pool, prediction).map(|_| ())
+            }
+            Op::CompleteWithdrawal { user, now } => {
+                let pool = &mut self.pools[self.users[user].withdrawal_pool.max(1) as usize - 1];
+                let amount = accounting::apply_withdrawal_completion(&mut self.users[user], pool, now)?;
+                self.vault -= amount as u64;
+                Ok(())
+            }
+            Op::Settle { pool, percent } => {
+                self.settle(pool, percent);
+                Ok(())
+            }
+            Op::Pay { user } => self.pay(user),
+            Op::SetAutoCompound { user, auto_compound } => {
+                self.users[user].auto_compound = auto_compound;
+                Ok(())
+            }
+        }
+    }
+
+    fn check_invariants(&self) {
+        for pool in &self.pools {
+            let members: Vec<&User> = self.users.iter().filter(|user| user.pool == pool.name).collect();
+
+            // sum of user balances == pool total
+            let balances: f64 = members.iter().map(|user| user.balance).sum();
+            assert_eq!(balances, pool.total_deposit, "pool {} total", pool.name);
+            assert!(pool.total_deposit >= 0.0);
+
+            // user_count matches users with a non-zero balance
+            let active = members.iter().filter(|user| user.balance > 0.0).count() as u64;
+            assert_eq!(pool.user_count, active, "pool {} user count", pool.name);
+
+            // requested withdrawals are counted apart from the deposits
+            let requests = self.users.iter().filter(|user| user.withdrawal_pool == pool.name);
+            let pending: f64 = requests.map(|user| user.pending_withdrawal).sum();
+            assert_eq!(pending, pool.pending_withdrawals, "pool {} pending withdrawals", pool.name);
+
+            // the average stays finite and is the mean of the counted predictions
+            assert!(pool.average_prediction.is_finite(), "pool {} average", pool.name);
+            let predictions: f64 = members.iter().map(|user| user.last_prediction).sum();
+            let expected = if active > 0 { predictions / active as f64 } else { 0.0 };
+            let tolerance = 1e-6 * (1.0 + expected.abs() + pool.average_prediction.abs());
+            assert!(
+                (pool.average_prediction - expected).abs() <= tolerance,
+                "pool {} average {} expected {}",
+                pool.name,
+                pool.average_prediction,
+                expected
+            );
+        }
+
+        for user in &self.users {
+            assert!(user.balance >= 0.0);
+            assert!(user.current_average_balance.is_finite());
+            // users out of a pool don't keep a prediction in its average
+            if user.balance <= 0.0 {
+                assert_eq!(user.last_prediction, 0.0);
+            }
+        }
+
+        // vault token balance == total deposits + pending withdrawals + reserves
+        let deposits: f64 = self.pools.iter().map(|pool| pool.total_deposit + pool.pending_withdrawals).sum();
+        assert_eq!(self.vault, deposits as u64 + self.reserves);
+
+        // the prize is never overpaid
+        let game = &self.game;
+        assert!(game.total_paid <= game.total_prize, "paid {} of {}", game.total_paid, game.total_prize);
+    }
+}
+
+fn user() -> impl Strategy<Value = usize> {
+    0..USERS
+}
+
+fn pool() -> impl Strategy<Value = u8> {
+    1..=4u8
+}
+
+// Mostly whole token amounts, with some fractional, negative and non-finite ones that must be rejected
+fn amount() -> impl Strategy<Value = f64> {
+    prop_oneof![
+        8 => (0..10_000u64).prop_map(|amount| amount as f64),
+        1 => -1_000.0..1_000.0f64,
+        1 => prop_oneof![Just(f64::NAN), Just(f64::INFINITY), Just(-0.0)],
+    ]
+}
+
+fn prediction() -> impl Strategy<Value = f64> {
+    prop_oneof![
+        9 => -1_000_000.0..1_000_000.0f64,
+        1 => prop_oneof![Just(f64::NAN), Just(f64::INFINITY), Just(f64::NEG_INFINITY)],
+    ]
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        3 => (user(), pool(), amount(), prediction())
+            .prop_map(|(user, pool, amount, prediction)| Op::Deposit { user, pool, amount, prediction }),
+        1 => (user(), pool(), amount()).prop_map(|(user, pool, amount)| Op::Withdraw { user, pool, amount }),
+        2 => (user(), 0..=100u8).prop_map(|(user, percent)| Op::WithdrawShare { user, percent }),
+        2 => (user(), pool(), prediction())
+            .prop_map(|(user, pool, prediction)| Op::MakePrediction { user, pool, prediction }),
+        1 => (user(), pool(), prediction()).prop_map(|(user, pool, prediction)| Op::Switch { user, pool, prediction }),
+        1 => (user(), 0..=100u8, 0..2i64)
+            .prop_map(|(user, percent, release_time)| Op::RequestWithdrawal { user, percent, release_time }),
+        1 => (user(), prediction()).prop_map(|(user, prediction)| Op::CancelWithdrawal { user, prediction }),
+        1 => (user(), 0..3i64).prop_map(|(user, now)| Op::CompleteWithdrawal { user, now }),
+        1 => (pool(), 0..=100u8).prop_map(|(pool, percent)| Op::Settle { pool, percent }),
+        3 => user().prop_map(|user| Op::Pay { user }),
+        1 => (user(), any::<bool>()).prop_map(|(user, auto_compound)| Op::SetAutoCompound { user, auto_compound }),
+    ]
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    #[test]
+    fn pool_accounting_invariants(reserves in 0..1_000_000u64, ops in prop::collection::vec(op(), 1..64)) {
+        let mut model = Model::new(reserves);
+        for op in &ops {
+            let before = model.snapshot();
+            let vault = model.vault;
+            let result = model.apply(op);
+            // every winner's share fits in what is left of the prize, however the pool changed after settlement
+            if let Err(error) = &result {
+                prop_assert_ne!(error, &ErrorCode::PrizeExceeded.into(), "{:?}", op);
+            }
+            // a failed instruction is rolled back, and the single-step ones must not have written anything
+            if result.is_err() && !matches!(op, Op::Switch { .. }) {
+                prop_assert_eq!(&before, &model.snapshot(), "{:?} failed but changed state", op);
+                prop_assert_eq!(vault, model.vault);
+            }
+            model.check_invariants();
+        }
+    }
+}
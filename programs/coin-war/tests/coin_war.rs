@@ -0,0 +1,1117 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use coin_war::{ErrorCode, MAX_PARTICIPANTS};
+use common::*;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+
+#[tokio::test]
+async fn create_pool_for_all_four_pools() {
+    let mut game = TestGame::start().await;
+
+    for pool_name in ALL_POOLS {
+        let pool = game.pool(pool_name).await;
+        assert!(pool.is_initialized);
+        assert_eq!(pool.name, pool_name);
+        assert_eq!(pool.total_deposit, 0.0);
+        assert_eq!(pool.user_count, 0);
+    }
+    assert_eq!(game.pool_wallet_balance().await, 0);
+
+    // unknown pool
+    assert_error(game.create_pool(5).await, ErrorCode::PoolUnknown);
+}
+
+#[tokio::test]
+async fn create_user() {
+    let mut game = TestGame::start().await;
+    let wallet = game.create_user(1_000).await;
+
+    let user = game.user(&wallet.pubkey()).await;
+    assert_eq!(user.pool, 0);
+    assert_eq!(user.balance, 0.0);
+    assert_eq!(user.txn_count, 0);
+    assert_eq!(user.last_paid_game_id, 0);
+    assert_eq!(game.user_wallet_balance(&wallet.pubkey()).await, 1_000);
+}
+
+#[tokio::test]
+async fn deposit_withdraw_round_trip() {
+    let mut game = TestGame::start().await;
+    let wallet = game.create_user(1_000).await;
+
+    game.deposit(&wallet, SOLANA, 600.0, 100.0).await.unwrap();
+    assert_eq!(game.user(&wallet.pubkey()).await.balance, 600.0);
+    assert_eq!(game.pool(SOLANA).await.total_deposit, 600.0);
+    assert_eq!(game.pool(SOLANA).await.user_count, 1);
+    assert_eq!(game.pool_wallet_balance().await, 600);
+    assert_eq!(game.user_wallet_balance(&wallet.pubkey()).await, 400);
+
+    game.withdraw(&wallet, SOLANA, 200.0).await.unwrap();
+    assert_eq!(game.user(&wallet.pubkey()).await.balance, 400.0);
+    assert_eq!(game.pool(SOLANA).await.total_deposit, 400.0);
+    assert_eq!(game.pool(SOLANA).await.user_count, 1);
+
+    // can't take out more than was put in
+    assert_error(game.withdraw(&wallet, SOLANA, 500.0).await, ErrorCode::InsufficientBalance);
+
+    game.withdraw(&wallet, SOLANA, 400.0).await.unwrap();
+    let user = game.user(&wallet.pubkey()).await;
+    assert_eq!(user.balance, 0.0);
+    assert_eq!(user.txn_count, 2);
+    let pool = game.pool(SOLANA).await;
+    assert_eq!(pool.total_deposit, 0.0);
+    assert_eq!(pool.user_count, 0);
+    assert_eq!(pool.average_prediction, 0.0);
+    assert_eq!(game.pool_wallet_balance().await, 0);
+    assert_eq!(game.user_wallet_balance(&wallet.pubkey()).await, 1_000);
+
+    // withdrawals are recorded
+    let transaction: coin_war::Transaction =
+        get_account(&mut game.context, tx_pda(&wallet.pubkey(), SOLANA, 1)).await;
+    assert_eq!(transaction.amount, 400.0);
+}
+
+#[tokio::test]
+async fn deposit_rules() {
+    let mut game = TestGame::start().await;
+    let wallet = game.create_user(1_000).await;
+
+    assert_error(game.deposit(&wallet, SOLANA, 0.5, 100.0).await, ErrorCode::DepositInsufficient);
+
+    // only one pool at a time
+    game.deposit(&wallet, SOLANA, 100.0, 100.0).await.unwrap();
+    assert_error(game.deposit(&wallet, BNB, 100.0, 100.0).await, ErrorCode::MultiplePoolNotAllowed);
+    assert_error(game.withdraw(&wallet, BNB, 100.0).await, ErrorCode::InvalidWithdrawal);
+
+    // switching is fine once the first pool is empty
+    game.withdraw(&wallet, SOLANA, 100.0).await.unwrap();
+    next_blockhash(&mut game.context).await;
+    game.deposit(&wallet, BNB, 100.0, 100.0).await.unwrap();
+    assert_eq!(game.user(&wallet.pubkey()).await.pool, BNB);
+    assert_eq!(game.pool(SOLANA).await.user_count, 0);
+    assert_eq!(game.pool(BNB).await.user_count, 1);
+}
+
+#[tokio::test]
+async fn predictions_are_averaged_per_pool() {
+    let mut game = TestGame::start().await;
+    let alice = game.create_user(1_000).await;
+    let bob = game.create_user(1_000).await;
+    let carol = game.create_user(1_000).await;
+
+    game.deposit(&alice, ETHEREUM, 100.0, 10.0).await.unwrap();
+    game.deposit(&bob, ETHEREUM, 100.0, 20.0).await.unwrap();
+    game.deposit(&carol, POLYGON, 100.0, 5.0).await.unwrap();
+    assert_eq!(game.pool(ETHEREUM).await.average_prediction, 15.0);
+    assert_eq!(game.pool(POLYGON).await.average_prediction, 5.0);
+
+    game.make_prediction(&alice, ETHEREUM, 30.0).await.unwrap();
+    assert_eq!(game.pool(ETHEREUM).await.average_prediction, 25.0);
+    assert_eq!(game.user(&alice.pubkey()).await.last_prediction, 30.0);
+
+    // depositing again replaces the previous prediction
+    game.deposit(&bob, ETHEREUM, 100.0, 40.0).await.unwrap();
+    assert_eq!(game.pool(ETHEREUM).await.average_prediction, 35.0);
+
+    // leaving the pool removes the prediction
+    game.withdraw(&bob, ETHEREUM, 200.0).await.unwrap();
+    assert_eq!(game.pool(ETHEREUM).await.average_prediction, 30.0);
+
+    // can only predict for the pool the user is in
+    assert_error(game.make_prediction(&carol, ETHEREUM, 1.0).await, ErrorCode::UserNotInPool);
+    assert_error(game.make_prediction(&bob, ETHEREUM, 1.0).await, ErrorCode::UserNotInPool);
+}
+
+#[tokio::test]
+async fn select_winning_pool_checks_inputs() {
+    let mut game = TestGame::start().await;
+    let predictions = vec![30.0, 300.0, 1.0, 2_000.0];
+    let prices = vec![35.0, 280.0, 0.9, 1_900.0];
+
+    game.select_winning_pool(ALL_POOLS.to_vec(), predictions.clone(), prices.clone()).await.unwrap();
+    assert_error(
+        game.select_winning_pool(ALL_POOLS.to_vec(), predictions.clone(), vec![35.0, 280.0, 0.9]).await,
+        ErrorCode::PoolsDataSizeDoNotMatch,
+    );
+    assert_error(
+        game.select_winning_pool(vec![BNB, SOLANA, POLYGON, ETHEREUM], predictions, prices).await,
+        ErrorCode::PoolsInWrongOrder,
+    );
+}
+
+#[tokio::test]
+async fn full_game() {
+    let mut game = TestGame::start().await;
+    let alice = game.create_user(1_000).await;
+    let bob = game.create_user(1_000).await;
+    let carol = game.create_user(1_000).await;
+    let dave = game.create_user(1_000).await;
+
+    game.start_game(1).await.unwrap();
+    game.deposit(&alice, SOLANA, 300.0, 25.0).await.unwrap();
+    game.deposit(&bob, SOLANA, 100.0, 30.0).await.unwrap();
+    game.deposit(&carol, SOLANA, 600.0, 35.0).await.unwrap();
+    game.deposit(&dave, BNB, 500.0, 300.0).await.unwrap();
+
+    // sponsors make up the prize
+    let sponsor = Keypair::new();
+    fund_wallet(&mut game.context, &sponsor.pubkey()).await;
+    let mint = game.mint;
+    let sponsor_token_account = create_token_account(&mut game.context, &mint, &sponsor.pubkey()).await;
+    mint_to(&mut game.context, &mint, &sponsor_token_account, 200).await;
+    game.fund_prize(&sponsor, sponsor_token_account, 1, 200).await.unwrap();
+    let sponsorship: coin_war::Sponsorship = get_account(&mut game.context, sponsorship_pda(1, 0)).await;
+    assert_eq!(sponsorship.sponsor, sponsor.pubkey());
+    assert_eq!(sponsorship.amount, 200);
+    game.sponsor_prize(1, 800).await.unwrap();
+    assert_eq!(game.pool_wallet_balance().await, 2_500);
+
+    // the game can't be settled or paid before it is over
+    assert_error(game.settle_game(1, SOLANA).await, ErrorCode::GameNotOver);
+    assert_error(game.pay_winning_pool_user(&alice.pubkey(), SOLANA, 1).await, ErrorCode::GameNotSettled);
+
+    // and only the admin settles it
+    warp_forward(&mut game.context, GAME_DURATION_IN_SECS).await;
+    let stranger = Keypair::new();
+    let accounts = coin_war::accounts::SettleGame {
+        admin: stranger.pubkey(),
+        config: config_pda(),
+        game: game_pda(1),
+        pool: pool_pda(SOLANA),
+    };
+    let instruction = ix(accounts, coin_war::instruction::SettleGame { game_id: 1, winning_pool: SOLANA });
+    assert_error(process(&mut game.context, &[instruction], &[&stranger]).await, ErrorCode::Unauthorized);
+    game.settle_game(1, SOLANA).await.unwrap();
+    next_blockhash(&mut game.context).await;
+    assert_error(game.settle_game(1, SOLANA).await, ErrorCode::GameAlreadySettled);
+    let settled = game.game(1).await;
+    assert_eq!(settled.winning_pool, SOLANA);
+    assert_eq!((settled.total_prize, settled.sponsored_prize, settled.sponsor_count), (1_000.0, 1_000.0, 2));
+    assert_eq!(settled.winning_weight, 1_000.0);
+
+    // only the winning pool gets paid
+    assert_error(game.pay_winning_pool_user(&dave.pubkey(), BNB, 1).await, ErrorCode::NotWinningPool);
+
+    // prize is split by share of the pool, and each user can only be paid once
+    game.pay_winning_pool_user(&alice.pubkey(), SOLANA, 1).await.unwrap();
+    assert_eq!(game.user_wallet_balance(&alice.pubkey()).await, 700 + 300);
+    assert_eq!(game.user(&alice.pubkey()).await.last_paid_game_id, 1);
+    next_blockhash(&mut game.context).await;
+    assert_error(game.pay_winning_pool_user(&alice.pubkey(), SOLANA, 1).await, ErrorCode::AlreadyPaid);
+
+    // batch payouts skip users who were already paid, so a retried batch is harmless
+    game.pay_winners_batch(&[alice.pubkey(), bob.pubkey()], SOLANA, 1).await.unwrap();
+    game.pay_winners_batch(&[alice.pubkey(), bob.pubkey(), carol.pubkey()], SOLANA, 1).await.unwrap();
+    assert_eq!(game.user_wallet_balance(&alice.pubkey()).await, 1_000);
+    assert_eq!(game.user_wallet_balance(&bob.pubkey()).await, 900 + 100);
+    assert_eq!(game.user_wallet_balance(&carol.pubkey()).await, 400 + 600);
+
+    let settled = game.game(1).await;
+    assert_eq!(settled.total_paid, 1_000.0);
+    assert_error(game.pay_winning_pool_user(&carol.pubkey(), SOLANA, 1).await, ErrorCode::AlreadyPaid);
+
+    // deposits stay in the pool for the next game, the prize was paid out of what the sponsors put in
+    assert_eq!(game.pool_wallet_balance().await, 1_500);
+    assert_eq!(game.config().await.allowed_mint(&mint).unwrap().deposits, 1_500);
+    assert_eq!(game.pool(SOLANA).await.total_deposit, 1_000.0);
+    game.withdraw(&carol, SOLANA, 600.0).await.unwrap();
+    assert_eq!(game.user_wallet_balance(&carol.pubkey()).await, 1_600);
+}
+
+#[tokio::test]
+async fn prize_shares_are_fixed_when_the_game_is_settled() {
+    let mut game = TestGame::start().await;
+    let alice = game.create_user(1_000).await;
+    let bob = game.create_user(1_000).await;
+    let carol = game.create_user(1_000).await;
+
+    game.start_game(1).await.unwrap();
+    game.deposit(&alice, SOLANA, 300.0, 25.0).await.unwrap();
+    game.deposit(&bob, SOLANA, 100.0, 30.0).await.unwrap();
+    game.sponsor_prize(1, 400).await.unwrap();
+    warp_forward(&mut game.context, GAME_DURATION_IN_SECS).await;
+    game.settle_game(1, SOLANA).await.unwrap();
+
+    // a deposit after the game is settled doesn't buy a share of its prize
+    game.deposit(&carol, SOLANA, 600.0, 35.0).await.unwrap();
+    game.pay_winning_pool_user(&carol.pubkey(), SOLANA, 1).await.unwrap();
+    assert_eq!(game.user_wallet_balance(&carol.pubkey()).await, 400);
+
+    // a winner leaving the pool before being paid still gets the share of the settled pool, and neither that nor
+    // a paid winner leaving takes anything from the others
+    game.withdraw(&bob, SOLANA, 100.0).await.unwrap();
+    game.pay_winning_pool_user(&alice.pubkey(), SOLANA, 1).await.unwrap();
+    game.withdraw(&alice, SOLANA, 300.0).await.unwrap();
+    game.pay_winning_pool_user(&bob.pubkey(), SOLANA, 1).await.unwrap();
+    assert_eq!(game.user_wallet_balance(&alice.pubkey()).await, 1_000 + 300);
+    assert_eq!(game.user_wallet_balance(&bob.pubkey()).await, 1_000 + 100);
+    assert_eq!(game.game(1).await.total_paid, 400.0);
+    assert_eq!(game.pool_wallet_balance().await, 600);
+}
+
+#[tokio::test]
+async fn yield_is_harvested_into_the_prize_once_per_mint() {
+    let mut game = TestGame::start().await;
+    let alice = game.create_user(1_000).await;
+    game.start_game(1).await.unwrap();
+    game.deposit(&alice, SOLANA, 1_000.0, 25.0).await.unwrap();
+
+    // 10 bps a day on what the pool lends out
+    game.open_lending_reserve(10, 1_000).await;
+    game.deposit_idle_funds(SOLANA, 1_000).await.unwrap();
+    assert_eq!(game.pool_wallet_balance().await, 0);
+    assert_error(game.harvest_yield(1).await, ErrorCode::GameNotOver);
+
+    // the interest of the 5 days of the game goes into the prize, and can't be harvested twice
+    warp_forward(&mut game.context, GAME_DURATION_IN_SECS).await;
+    game.harvest_yield(1).await.unwrap();
+    assert_eq!(game.pool_wallet_balance().await, 5);
+    let harvested = game.game(1).await;
+    assert_eq!((harvested.total_prize, harvested.harvested_mints), (5.0, 1));
+    warp_forward(&mut game.context, GAME_DURATION_IN_SECS).await;
+    assert_error(game.harvest_yield(1).await, ErrorCode::AlreadyHarvested);
+
+    game.settle_game(1, SOLANA).await.unwrap();
+    game.pay_winning_pool_user(&alice.pubkey(), SOLANA, 1).await.unwrap();
+    assert_eq!(game.user_wallet_balance(&alice.pubkey()).await, 5);
+}
+
+#[tokio::test]
+async fn batch_payout_validates_accounts() {
+    let mut game = TestGame::start().await;
+    let alice = game.create_user(1_000).await;
+    let bob = game.create_user(1_000).await;
+
+    game.start_game(1).await.unwrap();
+    game.deposit(&alice, SOLANA, 100.0, 25.0).await.unwrap();
+    warp_forward(&mut game.context, GAME_DURATION_IN_SECS).await;
+    game.settle_game(1, SOLANA).await.unwrap();
+
+    // user key does not match the User account passed in
+    let accounts = coin_war::accounts::PayWinnersBatch {
+        owner: game.owner(),
+        config: config_pda(),
+        game: game_pda(1),
+        pool: pool_pda(SOLANA),
+        pool_token_account: pool_wallet_pda(&game.mint),
+        token_program: spl_token::id(),
+        mint_address: game.mint,
+        system_program: solana_sdk::system_program::id(),
+    };
+    let mut instruction = ix(
+        accounts,
+        coin_war::instruction::PayWinnersBatch { game_id: 1, user_keys: vec![alice.pubkey()], pool_name: SOLANA },
+    );
+    instruction.accounts.push(solana_sdk::instruction::AccountMeta::new(user_pda(&bob.pubkey()), false));
+    instruction.accounts.push(solana_sdk::instruction::AccountMeta::new(user_wallet_pda(&bob.pubkey()), false));
+    assert_error(process(&mut game.context, &[instruction.clone()], &[]).await, ErrorCode::InvalidUserAccount);
+
+    // missing token account
+    instruction.accounts.pop();
+    instruction.accounts.pop();
+    instruction.accounts.push(solana_sdk::instruction::AccountMeta::new(user_pda(&alice.pubkey()), false));
+    assert_error(process(&mut game.context, &[instruction], &[]).await, ErrorCode::BatchAccountsMismatch);
+}
+
+#[tokio::test]
+async fn admin_controls() {
+    let mut game = TestGame::start().await;
+    let alice = game.create_user(1_000).await;
+    let config: coin_war::Config = get_account(&mut game.context, config_pda()).await;
+    assert_eq!(config.admin, game.owner());
+    assert!(!config.paused);
+
+    // only the admin can pause, and deposits are rejected while paused
+    let stranger = Keypair::new();
+    let accounts = coin_war::accounts::SetPaused { admin: stranger.pubkey(), config: config_pda() };
+    let instruction = ix(accounts, coin_war::instruction::SetPaused { paused: true });
+    assert_error(process(&mut game.context, &[instruction], &[&stranger]).await, ErrorCode::Unauthorized);
+    game.set_paused(true).await.unwrap();
+    assert_error(game.deposit(&alice, SOLANA, 100.0, 25.0).await, ErrorCode::ProgramPaused);
+    game.set_paused(false).await.unwrap();
+    next_blockhash(&mut game.context).await;
+    game.deposit(&alice, SOLANA, 100.0, 25.0).await.unwrap();
+
+    // an unsettled game without prize can be cancelled and started again
+    game.start_game(1).await.unwrap();
+    game.cancel_game(1).await.unwrap();
+    assert!(!account_exists(&mut game.context, game_pda(1)).await);
+    next_blockhash(&mut game.context).await;
+    game.start_game(1).await.unwrap();
+
+    // sponsored prize is owed to the winners, so the game can no longer be cancelled
+    let mint = game.mint;
+    let sponsor = Keypair::new();
+    fund_wallet(&mut game.context, &sponsor.pubkey()).await;
+    let sponsor_token_account = create_token_account(&mut game.context, &mint, &sponsor.pubkey()).await;
+    mint_to(&mut game.context, &mint, &sponsor_token_account, 50).await;
+    game.fund_prize(&sponsor, sponsor_token_account, 1, 50).await.unwrap();
+    assert_error(game.cancel_game(1).await, ErrorCode::GameHasPrize);
+
+    // the treasury is whatever the pool wallet holds beyond the deposits
+    let owner = game.owner();
+    let treasury = create_token_account(&mut game.context, &mint, &owner).await;
+    assert_error(game.withdraw_treasury(treasury, 51).await, ErrorCode::InsufficientBalance);
+    game.withdraw_treasury(treasury, 50).await.unwrap();
+    assert_eq!(token_balance(&mut game.context, treasury).await, 50);
+    assert_eq!(game.pool_wallet_balance().await, 100);
+}
+
+#[tokio::test]
+async fn only_the_admin_sets_up_games() {
+    // the config can only be created by the program's upgrade authority
+    let mut context = program_test().start_with_context().await;
+    let stranger = Keypair::new();
+    fund_wallet(&mut context, &stranger.pubkey()).await;
+    set_upgrade_authority(&mut context, &Pubkey::new_unique()).await;
+    let accounts = coin_war::accounts::InitializeConfig {
+        admin: stranger.pubkey(),
+        config: config_pda(),
+        program_data: program_data_pda(),
+        system_program: solana_sdk::system_program::id(),
+    };
+    let instruction = ix(accounts, coin_war::instruction::InitializeConfig {});
+    assert_error(process(&mut context, &[instruction], &[&stranger]).await, ErrorCode::Unauthorized);
+
+    // and games and pools only by its admin
+    let mut game = TestGame::start().await;
+    fund_wallet(&mut game.context, &stranger.pubkey()).await;
+    let accounts = coin_war::accounts::StartGame {
+        admin: stranger.pubkey(),
+        config: config_pda(),
+        game: game_pda(1),
+        system_program: solana_sdk::system_program::id(),
+    };
+    let instruction = ix(accounts, coin_war::instruction::StartGame { game_id: 1 });
+    assert_error(process(&mut game.context, &[instruction], &[&stranger]).await, ErrorCode::Unauthorized);
+    let accounts = coin_war::accounts::CreatePool {
+        admin: stranger.pubkey(),
+        config: config_pda(),
+        pool: pool_pda(5),
+        system_program: solana_sdk::system_program::id(),
+    };
+    let instruction = ix(accounts, coin_war::instruction::CreatePool { pool_name: 5 });
+    assert_error(process(&mut game.context, &[instruction], &[&stranger]).await, ErrorCode::Unauthorized);
+    game.start_game(1).await.unwrap();
+}
+
+#[tokio::test]
+async fn deposits_are_taken_in_allowed_mints_only() {
+    let mut game = TestGame::start().await;
+    let sol = create_mint(&mut game.context, 9).await.pubkey();
+
+    // only the admin can allow a mint
+    let stranger = Keypair::new();
+    fund_wallet(&mut game.context, &stranger.pubkey()).await;
+    let accounts = coin_war::accounts::AddMint {
+        admin: stranger.pubkey(),
+        config: config_pda(),
+        pool_token_account: pool_wallet_pda(&sol),
+        mint_address: sol,
+        token_program: spl_token::id(),
+        system_program: solana_sdk::system_program::id(),
+        rent: solana_sdk::sysvar::rent::id(),
+    };
+    let instruction = ix(accounts, coin_war::instruction::AddMint { minimum_deposit: 1 });
+    assert_error(process(&mut game.context, &[instruction], &[&stranger]).await, ErrorCode::Unauthorized);
+
+    // users can't hold a mint that isn't allowed
+    let wallet = Keypair::new();
+    assert_error(game.create_user_with_mint(&wallet, &sol).await, ErrorCode::MintNotAllowed);
+
+    // a 9 decimal mint with a minimum of 2 tokens, amounts stay in 6 decimal units
+    game.add_mint(&sol, 2_000_000_000).await.unwrap();
+    next_blockhash(&mut game.context).await;
+    assert!(game.add_mint(&sol, 1).await.is_err());
+    let allowed = *game.config().await.allowed_mint(&sol).unwrap();
+    assert_eq!((allowed.decimals, allowed.minimum_deposit), (9, 2_000_000_000));
+
+    game.create_user_with_mint(&wallet, &sol).await.unwrap();
+    mint_to(&mut game.context, &sol, &user_wallet_pda(&wallet.pubkey()), 5_000_000_000).await;
+    assert_error(game.deposit(&wallet, SOLANA, 1_000_000.0, 30.0).await, ErrorCode::DepositInsufficient);
+    game.deposit(&wallet, SOLANA, 2_000_000.0, 30.0).await.unwrap();
+    game.set_minimum_deposit(&sol, 1_000_000_000).await.unwrap();
+    next_blockhash(&mut game.context).await;
+    game.deposit(&wallet, SOLANA, 1_000_000.0, 30.0).await.unwrap();
+
+    // each mint has its own pool wallet, while both count towards the same pool
+    let alice = game.create_user(1_000).await;
+    game.deposit(&alice, SOLANA, 1_000.0, 30.0).await.unwrap();
+    assert_eq!(token_balance(&mut game.context, pool_wallet_pda(&sol)).await, 3_000_000_000);
+    assert_eq!(game.pool_wallet_balance().await, 1_000);
+    assert_eq!(game.pool(SOLANA).await.total_deposit, 3_001_000.0);
+
+    game.withdraw(&wallet, SOLANA, 500_000.0).await.unwrap();
+    assert_eq!(game.user_wallet_balance(&wallet.pubkey()).await, 2_500_000_000);
+    assert_eq!(game.config().await.allowed_mint(&sol).unwrap().deposits, 2_500_000_000);
+}
+
+#[tokio::test]
+async fn token_2022_deposits_are_credited_what_arrives_after_the_transfer_fee() {
+    let mut game = TestGame::start().await;
+    // a 1% transfer fee
+    let mint = create_mint_with_transfer_fee(&mut game.context, 6, 100, 1_000_000).await.pubkey();
+    game.add_mint(&mint, 1).await.unwrap();
+
+    let wallet = Keypair::new();
+    game.create_user_with_mint(&wallet, &mint).await.unwrap();
+    mint_to(&mut game.context, &mint, &user_wallet_pda(&wallet.pubkey()), 100_000).await;
+
+    game.deposit(&wallet, SOLANA, 10_000.0, 30.0).await.unwrap();
+    assert_eq!(token_balance(&mut game.context, pool_wallet_pda(&mint)).await, 9_900);
+    assert_eq!(game.user(&wallet.pubkey()).await.balance, 9_900.0);
+    assert_eq!(game.pool(SOLANA).await.total_deposit, 9_900.0);
+    assert_eq!(game.config().await.allowed_mint(&mint).unwrap().deposits, 9_900);
+
+    // the fee on the way out comes off what the user receives
+    game.withdraw(&wallet, SOLANA, 9_900.0).await.unwrap();
+    assert_eq!(game.user_wallet_balance(&wallet.pubkey()).await, 90_000 + 9_801);
+    assert_eq!(token_balance(&mut game.context, pool_wallet_pda(&mint)).await, 0);
+    assert_eq!(game.config().await.allowed_mint(&mint).unwrap().deposits, 0);
+}
+
+#[tokio::test]
+async fn sol_is_wrapped_on_deposit_and_unwrapped_on_withdrawal() {
+    let mut game = TestGame::start().await;
+    let native_mint = spl_token::native_mint::id();
+    game.add_mint(&native_mint, 100_000_000).await.unwrap();
+
+    // SOL is only taken from users without a balance in another mint
+    let alice = game.create_user(1_000).await;
+    game.deposit(&alice, BNB, 1_000.0, 300.0).await.unwrap();
+    assert_error(game.deposit_sol(&alice, BNB, 200_000.0, 300.0).await, ErrorCode::WrongMint);
+
+    let wallet = Keypair::new();
+    game.create_user_with_mint(&wallet, &native_mint).await.unwrap();
+    let before = lamports(&mut game.context, wallet.pubkey()).await;
+    assert_error(game.deposit_sol(&wallet, SOLANA, 50_000.0, 30.0).await, ErrorCode::DepositInsufficient);
+    game.deposit_sol(&wallet, SOLANA, 500_000.0, 30.0).await.unwrap();
+    assert_eq!(before - lamports(&mut game.context, wallet.pubkey()).await, 500_000_000);
+    assert_eq!(token_balance(&mut game.context, pool_wallet_pda(&native_mint)).await, 500_000_000);
+    assert_eq!(game.user(&wallet.pubkey()).await.balance, 500_000.0);
+    assert_eq!(game.pool(SOLANA).await.total_deposit, 500_000.0);
+
+    // the signer gets SOL back and the rent of the unwrap account, less the rent of the transaction record
+    game.withdraw_sol(&wallet, SOLANA, 200_000.0).await.unwrap();
+    let rent = game.context.banks_client.get_rent().await.unwrap().minimum_balance(coin_war::Transaction::LEN);
+    assert_eq!(lamports(&mut game.context, wallet.pubkey()).await, before - 300_000_000 - rent);
+    assert_eq!(token_balance(&mut game.context, pool_wallet_pda(&native_mint)).await, 300_000_000);
+    assert!(!account_exists(&mut game.context, unwrap_pda(&wallet.pubkey())).await);
+
+    // the rest is an ordinary deposit of the native mint
+    game.withdraw(&wallet, SOLANA, 300_000.0).await.unwrap();
+    assert_eq!(game.user_wallet_balance(&wallet.pubkey()).await, 300_000_000);
+    assert_eq!(game.config().await.allowed_mint(&native_mint).unwrap().deposits, 0);
+    assert_eq!(game.pool(SOLANA).await.user_count, 0);
+}
+
+#[tokio::test]
+async fn users_deposit_from_and_get_paid_into_their_associated_token_account() {
+    let mut game = TestGame::start().await;
+    let mint = game.mint;
+    let alice = game.create_user(1_000).await;
+
+    // bob has no user wallet, the tokens stay in bob's own associated token account until deposited
+    let bob = Keypair::new();
+    game.create_user_without_wallet(&bob).await.unwrap();
+    let bob_account = create_associated_token_account(&mut game.context, &bob.pubkey(), &mint).await;
+    mint_to(&mut game.context, &mint, &bob_account, 1_000).await;
+    assert!(!account_exists(&mut game.context, user_wallet_pda(&bob.pubkey())).await);
+
+    game.start_game(1).await.unwrap();
+    game.deposit(&alice, SOLANA, 300.0, 25.0).await.unwrap();
+    game.deposit(&bob, SOLANA, 700.0, 35.0).await.unwrap();
+    assert_eq!(token_balance(&mut game.context, bob_account).await, 300);
+    assert_eq!(game.user(&bob.pubkey()).await.mint, mint);
+    assert_eq!(game.pool_wallet_balance().await, 1_000);
+
+    // users with a user wallet can deposit from their associated token account as well
+    let alice_account = create_associated_token_account(&mut game.context, &alice.pubkey(), &mint).await;
+    mint_to(&mut game.context, &mint, &alice_account, 100).await;
+    game.deposit_from(&alice, alice_account, SOLANA, 100.0, 25.0).await.unwrap();
+    assert_eq!(game.user(&alice.pubkey()).await.balance, 400.0);
+
+    // nobody else's token account is taken or paid into
+    assert_error(game.deposit_from(&alice, bob_account, SOLANA, 10.0, 25.0).await, ErrorCode::InvalidUserTokenAccount);
+    assert_error(game.withdraw_to(&alice, bob_account, SOLANA, 10.0).await, ErrorCode::InvalidUserTokenAccount);
+    // nor any other token account of the user's
+    let bob_other_account = create_token_account(&mut game.context, &mint, &bob.pubkey()).await;
+    assert_error(game.withdraw_to(&bob, bob_other_account, SOLANA, 10.0).await, ErrorCode::InvalidUserTokenAccount);
+
+    // a balance stays in one mint whichever token account it came from
+    let usdt = create_mint(&mut game.context, 6).await.pubkey();
+    game.add_mint(&usdt, 1).await.unwrap();
+    let bob_usdt = create_associated_token_account(&mut game.context, &bob.pubkey(), &usdt).await;
+    mint_to(&mut game.context, &usdt, &bob_usdt, 100).await;
+    assert_error(game.deposit_from(&bob, bob_usdt, SOLANA, 100.0, 35.0).await, ErrorCode::WrongMint);
+
+    // payouts land in whichever account the user uses
+    let sponsor = Keypair::new();
+    fund_wallet(&mut game.context, &sponsor.pubkey()).await;
+    let sponsor_token_account = create_token_account(&mut game.context, &mint, &sponsor.pubkey()).await;
+    mint_to(&mut game.context, &mint, &sponsor_token_account, 550).await;
+    game.fund_prize(&sponsor, sponsor_token_account, 1, 550).await.unwrap();
+    assert_eq!(game.game(1).await.prize_mint, mint);
+    // the prize stays in the mint it was funded in
+    let sponsor_usdt = create_associated_token_account(&mut game.context, &sponsor.pubkey(), &usdt).await;
+    mint_to(&mut game.context, &usdt, &sponsor_usdt, 100).await;
+    let result = game.fund_prize_in(&sponsor, sponsor_usdt, &usdt, 1, 100).await;
+    assert_error(result, ErrorCode::PrizeMintMismatch);
+    warp_forward(&mut game.context, GAME_DURATION_IN_SECS).await;
+    game.settle_game(1, SOLANA).await.unwrap();
+    game.pay_winning_pool_user(&bob.pubkey(), SOLANA, 1).await.unwrap();
+    assert_eq!(token_balance(&mut game.context, bob_account).await, 300 + 350);
+    game.pay_winners_batch(&[alice.pubkey(), bob.pubkey()], SOLANA, 1).await.unwrap();
+    assert_eq!(game.user_wallet_balance(&alice.pubkey()).await, 700 + 200);
+    assert_eq!(game.game(1).await.total_paid, 550.0);
+
+    // and so do withdrawals, after which the balance can move to another mint
+    game.withdraw(&bob, SOLANA, 700.0).await.unwrap();
+    assert_eq!(token_balance(&mut game.context, bob_account).await, 1_350);
+    game.deposit_from(&bob, bob_usdt, SOLANA, 100.0, 35.0).await.unwrap();
+    assert_eq!(game.user(&bob.pubkey()).await.mint, usdt);
+}
+
+#[tokio::test]
+async fn deposits_are_represented_by_shares_and_prizes_distributed_through_the_exchange_rate() {
+    let mut game = TestGame::start().await;
+    let mint = game.mint;
+    let alice = game.create_user(1_000).await;
+    let bob = game.create_user(1_000).await;
+    assert_error(game.create_share_mint(SOLANA).await, ErrorCode::ShareMintAlreadyCreated);
+
+    game.start_game(1).await.unwrap();
+    game.deposit(&alice, SOLANA, 300.0, 25.0).await.unwrap();
+    game.deposit(&bob, SOLANA, 100.0, 35.0).await.unwrap();
+    assert_eq!(game.share_balance(&alice.pubkey(), SOLANA).await, 300);
+    assert_eq!(game.user(&bob.pubkey()).await.shares, 100);
+
+    // only the pool's own share mint is taken
+    let mut instruction = game.deposit_instruction(&alice.pubkey(), SOLANA, 10.0, 25.0).await;
+    instruction.accounts[7].pubkey = game.share_mint_address(BNB).await;
+    instruction.accounts[8].pubkey = game.user_share_account(&alice.pubkey(), BNB).await;
+    assert_error(process(&mut game.context, &[instruction], &[&alice]).await, ErrorCode::InvalidShareMint);
+
+    // the prize stays in the pool and every share is worth more
+    let sponsor = Keypair::new();
+    fund_wallet(&mut game.context, &sponsor.pubkey()).await;
+    let sponsor_token_account = create_token_account(&mut game.context, &mint, &sponsor.pubkey()).await;
+    mint_to(&mut game.context, &mint, &sponsor_token_account, 200).await;
+    game.fund_prize(&sponsor, sponsor_token_account, 1, 200).await.unwrap();
+    warp_forward(&mut game.context, GAME_DURATION_IN_SECS).await;
+    game.settle_game(1, SOLANA).await.unwrap();
+    assert_error(game.distribute_prize(1, BNB).await, ErrorCode::NotWinningPool);
+    game.distribute_prize(1, SOLANA).await.unwrap();
+    let pool = game.pool(SOLANA).await;
+    assert_eq!((pool.exchange_rate(), pool.total_deposit, pool.pending_prize), (1.5, 400.0, 200.0));
+    assert_eq!(game.game(1).await.total_paid, 200.0);
+    next_blockhash(&mut game.context).await;
+    assert_error(game.distribute_prize(1, SOLANA).await, ErrorCode::AlreadyPaid);
+    assert_error(game.pay_winning_pool_user(&alice.pubkey(), SOLANA, 1).await, ErrorCode::PrizeExceeded);
+
+    // balances catch up with the rate on the next withdrawal or deposit
+    game.withdraw(&alice, SOLANA, 450.0).await.unwrap();
+    assert_eq!(game.user_wallet_balance(&alice.pubkey()).await, 700 + 450);
+    assert_eq!((game.user(&alice.pubkey()).await.shares, game.share_balance(&alice.pubkey(), SOLANA).await), (0, 0));
+    game.deposit(&bob, SOLANA, 150.0, 35.0).await.unwrap();
+    let user = game.user(&bob.pubkey()).await;
+    assert_eq!((user.balance, user.shares), (300.0, 200));
+    assert_eq!(game.share_balance(&bob.pubkey(), SOLANA).await, 200);
+
+    // and the pool wallet holds exactly what is owed
+    let pool = game.pool(SOLANA).await;
+    assert_eq!((pool.total_deposit, pool.pending_prize), (300.0, 0.0));
+    assert_eq!(game.pool_wallet_balance().await, 300);
+    assert_eq!(game.config().await.allowed_mint(&mint).unwrap().deposits, 300);
+}
+
+#[tokio::test]
+async fn withdrawals_burn_the_shares_of_the_balance() {
+    let mut game = TestGame::start().await;
+    let alice = game.create_user(1_000).await;
+    let bob = game.create_user(1_000).await;
+    game.start_game(1).await.unwrap();
+    game.deposit(&alice, SOLANA, 300.0, 25.0).await.unwrap();
+
+    // shares moved elsewhere take the balance with them, neither the user nor the holder can withdraw it
+    let source = game.user_share_account(&alice.pubkey(), SOLANA).await;
+    let destination = game.user_share_account(&bob.pubkey(), SOLANA).await;
+    let transfer = |from: &Pubkey, to: &Pubkey, owner: &Pubkey| {
+        spl_token::instruction::transfer(&spl_token::id(), from, to, owner, &[], 200)
+    };
+    let instruction = transfer(&source, &destination, &alice.pubkey()).unwrap();
+    process(&mut game.context, &[instruction], &[&alice]).await.unwrap();
+    assert_error(game.withdraw(&alice, SOLANA, 200.0).await, ErrorCode::SharesNotHeld);
+    assert_error(game.request_withdrawal(&alice, 1, SOLANA, 200.0).await, ErrorCode::SharesNotHeld);
+    assert_error(game.withdraw(&bob, SOLANA, 200.0).await, ErrorCode::InvalidWithdrawal);
+
+    // what the user still holds covers what is withdrawn
+    game.withdraw(&alice, SOLANA, 100.0).await.unwrap();
+    let instruction = transfer(&destination, &source, &bob.pubkey()).unwrap();
+    process(&mut game.context, &[instruction], &[&bob]).await.unwrap();
+    next_blockhash(&mut game.context).await;
+    game.withdraw(&alice, SOLANA, 200.0).await.unwrap();
+    assert_eq!((game.user(&alice.pubkey()).await.shares, game.share_balance(&alice.pubkey(), SOLANA).await), (0, 0));
+    assert_eq!(game.user_wallet_balance(&alice.pubkey()).await, 1_000);
+}
+
+#[tokio::test]
+async fn gift_deposits_go_to_existing_users_and_only_they_can_withdraw() {
+    let mut game = TestGame::start().await;
+    let mint = game.mint;
+    let donor = game.create_user(0).await;
+    let donor_token_account = create_token_account(&mut game.context, &mint, &donor.pubkey()).await;
+    mint_to(&mut game.context, &mint, &donor_token_account, 500).await;
+    let friend = Keypair::new();
+    fund_wallet(&mut game.context, &friend.pubkey()).await;
+    game.create_share_accounts(&friend.pubkey()).await;
+    game.start_game(1).await.unwrap();
+
+    // gifts can't create the friend's User account, which would keep the friend from creating it with a user wallet
+    let result = game.deposit_for_user(&donor, &friend.pubkey(), donor_token_account, SOLANA, 200.0, 25.0).await;
+    assert!(result.is_err());
+    game.create_user_with_mint(&friend, &mint).await.unwrap();
+    assert!(account_exists(&mut game.context, user_wallet_pda(&friend.pubkey())).await);
+
+    next_blockhash(&mut game.context).await;
+    game.deposit_for_user(&donor, &friend.pubkey(), donor_token_account, SOLANA, 200.0, 25.0).await.unwrap();
+    let user = game.user(&friend.pubkey()).await;
+    assert_eq!((user.balance, user.last_prediction, user.shares, user.txn_count), (200.0, 25.0, 200, 1));
+    assert!(user.current_weighted_balance > 0.0);
+    assert_eq!(user.version, coin_war::migrate::USER_VERSION);
+    assert_eq!(game.share_balance(&friend.pubkey(), SOLANA).await, 200);
+    let transaction: coin_war::Transaction = get_account(&mut game.context, tx_pda(&friend.pubkey(), SOLANA, 0)).await;
+    assert_eq!((transaction.amount, transaction.transaction_type, transaction.donor), (200.0, 3, donor.pubkey()));
+
+    // a later gift adds to the position and leaves the friend's prediction alone
+    game.deposit_for_user(&donor, &friend.pubkey(), donor_token_account, SOLANA, 100.0, 40.0).await.unwrap();
+    let user = game.user(&friend.pubkey()).await;
+    assert_eq!((user.balance, user.last_prediction, user.txn_count), (300.0, 25.0, 2));
+    assert_eq!(token_balance(&mut game.context, donor_token_account).await, 200);
+
+    // the donor has no claim on the gift, the friend withdraws it like its own deposit
+    assert_error(game.withdraw(&donor, SOLANA, 100.0).await, ErrorCode::InvalidWithdrawal);
+    let friend_token_account = create_associated_token_account(&mut game.context, &friend.pubkey(), &mint).await;
+    game.withdraw_to(&friend, friend_token_account, SOLANA, 300.0).await.unwrap();
+    assert_eq!(token_balance(&mut game.context, friend_token_account).await, 300);
+    assert_eq!(game.user(&friend.pubkey()).await.balance, 0.0);
+}
+
+#[tokio::test]
+async fn delegates_deposit_for_a_user_and_withdraw_only_what_they_deposited() {
+    let mut game = TestGame::start().await;
+    let mint = game.mint;
+    let alice = game.create_user(1_000).await;
+    let vault = Keypair::new();
+    fund_wallet(&mut game.context, &vault.pubkey()).await;
+    game.create_share_accounts(&vault.pubkey()).await;
+    let vault_token_account = create_token_account(&mut game.context, &mint, &vault.pubkey()).await;
+    mint_to(&mut game.context, &mint, &vault_token_account, 500).await;
+    game.start_game(1).await.unwrap();
+
+    // nothing until the user approves the delegate
+    assert!(game.deposit_for(&vault, &alice.pubkey(), vault_token_account, SOLANA, 200.0, 30.0).await.is_err());
+    game.approve_delegate(&alice, &vault.pubkey()).await.unwrap();
+    game.deposit(&alice, SOLANA, 100.0, 30.0).await.unwrap();
+    next_blockhash(&mut game.context).await;
+    game.deposit_for(&vault, &alice.pubkey(), vault_token_account, SOLANA, 200.0, 30.0).await.unwrap();
+    let user = game.user(&alice.pubkey()).await;
+    assert_eq!((user.balance, user.delegated_balance, user.shares), (300.0, 200.0, 100));
+    assert_eq!(game.share_balance(&vault.pubkey(), SOLANA).await, 200);
+    let delegation: coin_war::Delegation =
+        get_account(&mut game.context, delegation_pda(&alice.pubkey(), &vault.pubkey())).await;
+    assert_eq!((delegation.deposited, delegation.shares), (200.0, 200));
+
+    // the user only withdraws its own deposits, the delegate only its own
+    assert_error(game.withdraw(&alice, SOLANA, 150.0).await, ErrorCode::DelegatedBalance);
+    let destination = create_token_account(&mut game.context, &mint, &Pubkey::new_unique()).await;
+    assert_error(
+        game.delegate_withdraw(&vault, &alice.pubkey(), destination, SOLANA, 250.0).await,
+        ErrorCode::DelegationExceeded,
+    );
+    assert_error(game.revoke_delegate(&alice, &vault.pubkey()).await, ErrorCode::DelegationInUse);
+    game.delegate_withdraw(&vault, &alice.pubkey(), destination, SOLANA, 200.0).await.unwrap();
+    assert_eq!(token_balance(&mut game.context, destination).await, 200);
+    assert_eq!(game.share_balance(&vault.pubkey(), SOLANA).await, 0);
+    let user = game.user(&alice.pubkey()).await;
+    assert_eq!((user.balance, user.delegated_balance, user.txn_count), (100.0, 0.0, 1));
+
+    // once the delegate is out the delegation can be closed
+    next_blockhash(&mut game.context).await;
+    game.revoke_delegate(&alice, &vault.pubkey()).await.unwrap();
+    assert!(!account_exists(&mut game.context, delegation_pda(&alice.pubkey(), &vault.pubkey())).await);
+    game.withdraw(&alice, SOLANA, 100.0).await.unwrap();
+}
+
+#[tokio::test]
+async fn requested_withdrawals_leave_the_game_and_are_paid_once_it_ends() {
+    let mut game = TestGame::start().await;
+    let mint = game.mint;
+    let alice = game.create_user(1_000).await;
+    let bob = game.create_user(1_000).await;
+    game.start_game(1).await.unwrap();
+    game.deposit(&alice, SOLANA, 300.0, 30.0).await.unwrap();
+    game.deposit(&bob, SOLANA, 100.0, 30.0).await.unwrap();
+
+    // the request leaves the active stake right away, the tokens stay in the pool wallet
+    game.request_withdrawal(&alice, 1, SOLANA, 100.0).await.unwrap();
+    let user = game.user(&alice.pubkey()).await;
+    assert_eq!((user.balance, user.shares, user.pending_withdrawal), (200.0, 200, 100.0));
+    assert_eq!(user.withdrawal_release_time, game.game(1).await.end_time);
+    let pool = game.pool(SOLANA).await;
+    assert_eq!((pool.total_deposit, pool.pending_withdrawals), (300.0, 100.0));
+    assert_eq!(game.pool_wallet_balance().await, 400);
+    assert_error(game.complete_withdrawal(&alice, SOLANA).await, ErrorCode::WithdrawalNotReleased);
+
+    // cancelling puts it back with a new prediction
+    game.cancel_withdrawal_request(&alice, SOLANA, 35.0).await.unwrap();
+    let user = game.user(&alice.pubkey()).await;
+    assert_eq!((user.balance, user.shares, user.last_prediction, user.pending_withdrawal), (300.0, 300, 35.0, 0.0));
+    next_blockhash(&mut game.context).await;
+    assert_error(game.cancel_withdrawal_request(&alice, SOLANA, 35.0).await, ErrorCode::NoWithdrawalRequest);
+    next_blockhash(&mut game.context).await;
+    game.request_withdrawal(&alice, 1, SOLANA, 100.0).await.unwrap();
+
+    // the prize is shared by the active stake only
+    let sponsor = Keypair::new();
+    fund_wallet(&mut game.context, &sponsor.pubkey()).await;
+    let sponsor_token_account = create_token_account(&mut game.context, &mint, &sponsor.pubkey()).await;
+    mint_to(&mut game.context, &mint, &sponsor_token_account, 150).await;
+    game.fund_prize(&sponsor, sponsor_token_account, 1, 150).await.unwrap();
+    warp_forward(&mut game.context, GAME_DURATION_IN_SECS).await;
+    assert_error(game.request_withdrawal(&bob, 1, SOLANA, 50.0).await, ErrorCode::GameNotInProgress);
+    game.settle_game(1, SOLANA).await.unwrap();
+    game.distribute_prize(1, SOLANA).await.unwrap();
+    assert_eq!(game.pool(SOLANA).await.exchange_rate(), 1.5);
+
+    // once the game is over the request is paid in full, without the prize
+    game.complete_withdrawal(&alice, SOLANA).await.unwrap();
+    assert_eq!(game.user_wallet_balance(&alice.pubkey()).await, 700 + 100);
+    let user = game.user(&alice.pubkey()).await;
+    assert_eq!((user.balance, user.pending_withdrawal, user.txn_count), (200.0, 0.0, 1));
+    assert_eq!(game.pool(SOLANA).await.pending_withdrawals, 0.0);
+    assert_eq!(game.pool_wallet_balance().await, 300 + 150);
+    assert_eq!(game.config().await.allowed_mint(&mint).unwrap().deposits, 300);
+    next_blockhash(&mut game.context).await;
+    assert_error(game.complete_withdrawal(&alice, SOLANA).await, ErrorCode::NoWithdrawalRequest);
+}
+
+#[tokio::test]
+async fn locked_deposits_get_a_bigger_prize_share_and_stay_until_released() {
+    let mut game = TestGame::start().await;
+    let alice = game.create_user(1_000).await;
+    let bob = game.create_user(1_000).await;
+    let dave = game.create_user(1_000).await;
+    game.start_game(1).await.unwrap();
+    game.deposit(&alice, SOLANA, 300.0, 25.0).await.unwrap();
+    game.deposit(&bob, SOLANA, 300.0, 30.0).await.unwrap();
+    game.deposit(&dave, BNB, 500.0, 300.0).await.unwrap();
+
+    // only the lock lengths the admin set a multiplier for
+    game.set_lock_tier(2, 2.0).await.unwrap();
+    assert_error(game.lock_deposit(&alice, 1, 200.0, SOLANA, 3).await, ErrorCode::NoLockTier);
+    assert_error(game.lock_deposit(&alice, 1, 400.0, SOLANA, 2).await, ErrorCode::InsufficientBalance);
+    game.lock_deposit(&alice, 1, 200.0, SOLANA, 2).await.unwrap();
+    let user = game.user(&alice.pubkey()).await;
+    assert_eq!((user.locked_amount, user.lock_multiplier), (200.0, 2.0));
+    assert_eq!(user.lock_release_time, game.game(1).await.end_time + GAME_DURATION_IN_SECS);
+    assert_eq!(game.pool(SOLANA).await.lock_boost, 200.0);
+    assert_error(game.withdraw(&alice, SOLANA, 150.0).await, ErrorCode::DepositLocked);
+
+    // locks taken in the last day of the game come too late for it
+    game.sponsor_prize(1, 800).await.unwrap();
+    warp_forward(&mut game.context, GAME_DURATION_IN_SECS - 60 * 60).await;
+    assert_error(game.lock_deposit(&bob, 1, 100.0, SOLANA, 2).await, ErrorCode::LockTooLate);
+
+    // the locked 200 weigh twice: 500 of 800
+    warp_forward(&mut game.context, 60 * 60).await;
+    game.settle_game(1, SOLANA).await.unwrap();
+    game.pay_winning_pool_user(&alice.pubkey(), SOLANA, 1).await.unwrap();
+    game.pay_winning_pool_user(&bob.pubkey(), SOLANA, 1).await.unwrap();
+    assert_eq!(game.user_wallet_balance(&alice.pubkey()).await, 700 + 500);
+    assert_eq!(game.user_wallet_balance(&bob.pubkey()).await, 700 + 300);
+
+    // the lock runs through the next game, until it is settled with the boost in its winning weight
+    game.start_game(2).await.unwrap();
+    assert_error(game.unlock_deposit(&alice.pubkey(), SOLANA).await, ErrorCode::DepositLocked);
+    next_blockhash(&mut game.context).await;
+    assert_error(game.withdraw(&alice, SOLANA, 150.0).await, ErrorCode::DepositLocked);
+    warp_forward(&mut game.context, GAME_DURATION_IN_SECS).await;
+    next_blockhash(&mut game.context).await;
+    assert_error(game.unlock_deposit(&alice.pubkey(), SOLANA).await, ErrorCode::DepositLocked);
+    game.settle_game(2, SOLANA).await.unwrap();
+    assert_eq!(game.game(2).await.winning_weight, 800.0);
+    next_blockhash(&mut game.context).await;
+    game.unlock_deposit(&alice.pubkey(), SOLANA).await.unwrap();
+    assert_eq!((game.user(&alice.pubkey()).await.locked_amount, game.pool(SOLANA).await.lock_boost), (0.0, 0.0));
+    next_blockhash(&mut game.context).await;
+    assert_error(game.unlock_deposit(&alice.pubkey(), SOLANA).await, ErrorCode::NoLock);
+    game.withdraw(&alice, SOLANA, 300.0).await.unwrap();
+}
+
+#[tokio::test]
+async fn prizes_distributed_before_a_lock_are_locked_and_boosted_at_the_exchange_rate() {
+    let mut game = TestGame::start().await;
+    let alice = game.create_user(1_000).await;
+    let bob = game.create_user(1_000).await;
+    let dave = game.create_user(1_000).await;
+    game.start_game(1).await.unwrap();
+    game.deposit(&alice, SOLANA, 300.0, 25.0).await.unwrap();
+    game.deposit(&bob, SOLANA, 100.0, 30.0).await.unwrap();
+    game.deposit(&dave, BNB, 500.0, 300.0).await.unwrap();
+    game.sponsor_prize(1, 200).await.unwrap();
+    warp_forward(&mut game.context, GAME_DURATION_IN_SECS).await;
+    game.settle_game(1, SOLANA).await.unwrap();
+    game.distribute_prize(1, SOLANA).await.unwrap();
+
+    // alice's balance is 450 at the exchange rate of 1.5 without having caught up with it yet
+    game.set_lock_tier(1, 2.0).await.unwrap();
+    game.start_game(2).await.unwrap();
+    assert_error(game.lock_deposit(&alice, 2, 451.0, SOLANA, 1).await, ErrorCode::InsufficientBalance);
+    game.lock_deposit(&alice, 2, 450.0, SOLANA, 1).await.unwrap();
+    assert_eq!(game.user(&alice.pubkey()).await.balance, 300.0);
+    assert_eq!(game.pool(SOLANA).await.lock_boost, 450.0);
+    assert_error(game.withdraw(&alice, SOLANA, 1.0).await, ErrorCode::DepositLocked);
+
+    // the locked 450 weigh twice: 900 of 1050
+    game.sponsor_prize(2, 1_050).await.unwrap();
+    warp_forward(&mut game.context, GAME_DURATION_IN_SECS).await;
+    game.settle_game(2, SOLANA).await.unwrap();
+    assert_eq!(game.game(2).await.winning_weight, 1_050.0);
+    game.pay_winning_pool_user(&alice.pubkey(), SOLANA, 2).await.unwrap();
+    game.pay_winning_pool_user(&bob.pubkey(), SOLANA, 2).await.unwrap();
+    assert_eq!(game.user_wallet_balance(&alice.pubkey()).await, 700 + 900);
+    assert_eq!(game.user_wallet_balance(&bob.pubkey()).await, 900 + 150);
+}
+
+#[tokio::test]
+async fn auto_compounded_prizes_stay_in_the_balance_without_shrinking_the_other_shares() {
+    let mut game = TestGame::start().await;
+    let alice = game.create_user(1_000).await;
+    let bob = game.create_user(1_000).await;
+    let dave = game.create_user(1_000).await;
+    game.start_game(1).await.unwrap();
+    game.deposit(&alice, SOLANA, 300.0, 25.0).await.unwrap();
+    game.deposit(&bob, SOLANA, 300.0, 30.0).await.unwrap();
+    game.deposit(&dave, BNB, 500.0, 300.0).await.unwrap();
+    game.set_payout_preference(&alice, true).await.unwrap();
+
+    // alice's half of the prize goes into her balance, bob still gets half of it paid out
+    game.sponsor_prize(1, 600).await.unwrap();
+    warp_forward(&mut game.context, GAME_DURATION_IN_SECS).await;
+    game.settle_game(1, SOLANA).await.unwrap();
+    game.pay_winning_pool_user(&alice.pubkey(), SOLANA, 1).await.unwrap();
+    game.pay_winning_pool_user(&bob.pubkey(), SOLANA, 1).await.unwrap();
+    assert_eq!(game.user_wallet_balance(&alice.pubkey()).await, 700);
+    assert_eq!(game.user_wallet_balance(&bob.pubkey()).await, 700 + 300);
+    assert_eq!(game.user(&alice.pubkey()).await.balance, 600.0);
+    assert_eq!(game.pool(SOLANA).await.total_deposit, 900.0);
+    let settled = game.game(1).await;
+    assert_eq!((settled.total_paid, settled.compounded_prize), (600.0, 300.0));
+
+    // the compounded prize is a deposit like any other, its shares are minted by issue_shares
+    game.issue_shares(&alice, SOLANA).await.unwrap();
+    assert_eq!(game.share_balance(&alice.pubkey(), SOLANA).await, 600);
+    game.set_payout_preference(&alice, false).await.unwrap();
+    game.withdraw(&alice, SOLANA, 600.0).await.unwrap();
+    assert_eq!(game.user_wallet_balance(&alice.pubkey()).await, 1_300);
+}
+
+// Hex encoded account data from tests/fixtures
+fn fixture(hex: &str) -> Vec<u8> {
+    let hex = hex.trim();
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect()
+}
+
+#[tokio::test]
+async fn legacy_accounts_are_migrated() {
+    let mut game = TestGame::start().await;
+    let alice = game.create_user(1_000).await;
+    game.start_game(1).await.unwrap();
+
+    // a pool from before the yield source is too small for the current layout until it is migrated
+    let legacy_pool = fixture(include_str!("fixtures/pool_v0_baseline.hex"));
+    let legacy_len = legacy_pool.len();
+    set_account_data(&mut game.context, pool_pda(SOLANA), legacy_pool).await;
+    assert!(game.deposit(&alice, SOLANA, 100.0, 30.0).await.is_err());
+
+    game.migrate_pool(SOLANA).await.unwrap();
+    let account = game.context.banks_client.get_account(pool_pda(SOLANA)).await.unwrap().unwrap();
+    let rent = game.context.banks_client.get_rent().await.unwrap();
+    assert!(account.data.len() > legacy_len);
+    assert!(rent.is_exempt(account.lamports, account.data.len()));
+    let pool = game.pool(SOLANA).await;
+    assert_eq!((pool.total_deposit, pool.user_count, pool.invested), (1_000.0, 4, 0.0));
+    assert_eq!(pool.version, coin_war::migrate::POOL_VERSION);
+
+    // and needs a PoolData account and a share mint before taking deposits again
+    assert_eq!((pool.pool_data, pool.share_mint), (Pubkey::default(), Pubkey::default()));
+    game.create_pool_data(SOLANA).await.unwrap();
+    game.create_share_mint(SOLANA).await.unwrap();
+    game.create_share_accounts(&alice.pubkey()).await;
+    next_blockhash(&mut game.context).await;
+    game.deposit(&alice, SOLANA, 100.0, 30.0).await.unwrap();
+    assert_eq!(game.pool(SOLANA).await.total_deposit, 1_100.0);
+    assert_eq!(game.share_balance(&alice.pubkey(), SOLANA).await, 100);
+
+    // old users are too small for the current layout too until they are migrated. Migrating twice is a no-op.
+    let legacy_user = fixture(include_str!("fixtures/user_v0_baseline.hex"));
+    set_account_data(&mut game.context, user_pda(&alice.pubkey()), legacy_user).await;
+    next_blockhash(&mut game.context).await;
+    assert!(game.deposit(&alice, SOLANA, 100.0, 30.0).await.is_err());
+    game.migrate_user(&alice.pubkey()).await.unwrap();
+    let user = game.user(&alice.pubkey()).await;
+    assert_eq!((user.balance, user.last_paid_game_id), (250.0, 0));
+    assert_eq!(user.version, coin_war::migrate::USER_VERSION);
+    next_blockhash(&mut game.context).await;
+    game.migrate_user(&alice.pubkey()).await.unwrap();
+    assert_eq!(game.user(&alice.pubkey()).await.balance, 250.0);
+
+    // and get the shares of their balance minted once
+    game.issue_shares(&alice, SOLANA).await.unwrap();
+    next_blockhash(&mut game.context).await;
+    game.issue_shares(&alice, SOLANA).await.unwrap();
+    assert_eq!(game.user(&alice.pubkey()).await.shares, 250);
+    assert_eq!(game.share_balance(&alice.pubkey(), SOLANA).await, 350);
+
+    // games were exactly the size of their layout
+    set_account_data(&mut game.context, game_pda(1), fixture(include_str!("fixtures/game_v0_sponsorship.hex"))).await;
+    game.migrate_game(1).await.unwrap();
+    let migrated = game.game(1).await;
+    assert_eq!((migrated.total_prize, migrated.sponsor_count), (40.0, 1));
+    assert_eq!(migrated.version, coin_war::migrate::GAME_VERSION);
+
+    // configs from before the mint allow-list only had room for their reserve
+    let mut legacy_config = <coin_war::Config as anchor_lang::Discriminator>::discriminator().to_vec();
+    legacy_config.extend_from_slice(game.owner().as_ref());
+    legacy_config.push(0);
+    legacy_config.resize(legacy_config.len() + coin_war::space::RESERVED, 0);
+    set_account_data(&mut game.context, config_pda(), legacy_config).await;
+    game.migrate_config().await.unwrap();
+    let account = game.context.banks_client.get_account(config_pda()).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), coin_war::Config::LEN);
+    assert_eq!(game.config().await.admin, game.owner());
+    assert_eq!(game.config().await.version, coin_war::migrate::CONFIG_VERSION);
+    let usdt = create_mint(&mut game.context, 6).await.pubkey();
+    game.add_mint(&usdt, 1).await.unwrap();
+    assert_eq!(game.config().await.mints.len(), 1);
+}
+
+#[tokio::test]
+async fn participants_are_listed_per_pool() {
+    let mut game = TestGame::start().await;
+    let alice = game.create_user(1_000).await;
+    let bob = game.create_user(1_000).await;
+    let carol = game.create_user(1_000).await;
+    game.start_game(1).await.unwrap();
+    assert_error(game.create_pool_data(SOLANA).await, ErrorCode::PoolDataAlreadyCreated);
+
+    game.deposit(&alice, SOLANA, 100.0, 30.0).await.unwrap();
+    game.deposit(&bob, SOLANA, 100.0, 30.0).await.unwrap();
+    next_blockhash(&mut game.context).await;
+    game.deposit(&alice, SOLANA, 100.0, 30.0).await.unwrap();
+    assert_eq!(game.user(&alice.pubkey()).await.participant_slot, 1);
+    assert_eq!(game.user(&bob.pubkey()).await.participant_slot, 2);
+    let pool_data = game.pool_data(SOLANA).await;
+    assert_eq!((pool_data.participant_count, pool_data.slots_used, pool_data.pool), (2, 2, SOLANA));
+    assert_eq!(pool_data.participants[..2], [alice.pubkey(), bob.pubkey()]);
+    assert!(game.pool_data(BNB).await.participant_count == 0);
+
+    // users stay listed until they withdraw everything, and their slot is reused
+    game.withdraw(&alice, SOLANA, 100.0).await.unwrap();
+    assert_eq!(game.user(&alice.pubkey()).await.participant_slot, 1);
+    next_blockhash(&mut game.context).await;
+    game.withdraw(&alice, SOLANA, 100.0).await.unwrap();
+    assert_eq!(game.user(&alice.pubkey()).await.participant_slot, 0);
+    assert_eq!(game.pool_data(SOLANA).await.participants[0], Pubkey::default());
+
+    game.deposit(&carol, SOLANA, 100.0, 30.0).await.unwrap();
+    assert_eq!(game.user(&carol.pubkey()).await.participant_slot, 1);
+    let pool_data = game.pool_data(SOLANA).await;
+    assert_eq!((pool_data.participant_count, pool_data.slots_used, pool_data.free_slot_count), (2, 2, 0));
+    assert_eq!(pool_data.participants[..2], [carol.pubkey(), bob.pubkey()]);
+
+    // only the pool's own PoolData is accepted
+    let mut instruction = game.deposit_instruction(&alice.pubkey(), SOLANA, 100.0, 30.0).await;
+    let solana_pool_data = game.pool_data_address(SOLANA).await;
+    let bnb_pool_data = game.pool_data_address(BNB).await;
+    instruction.accounts.iter_mut().find(|meta| meta.pubkey == solana_pool_data).unwrap().pubkey = bnb_pool_data;
+    assert_error(process(&mut game.context, &[instruction], &[&alice]).await, ErrorCode::InvalidPoolData);
+}
+
+#[tokio::test]
+async fn pools_are_snapshotted_after_each_game() {
+    let mut game = TestGame::start().await;
+    let alice = game.create_user(1_000).await;
+    game.start_game(1).await.unwrap();
+    game.deposit(&alice, SOLANA, 300.0, 25.0).await.unwrap();
+    assert_error(game.snapshot_pool(1, SOLANA).await, ErrorCode::GameNotOver);
+
+    warp_forward(&mut game.context, GAME_DURATION_IN_SECS).await;
+    game.snapshot_pool(1, SOLANA).await.unwrap();
+    next_blockhash(&mut game.context).await;
+    assert_error(game.snapshot_pool(1, SOLANA).await, ErrorCode::SnapshotExists);
+
+    let snapshot = game.pool_data(SOLANA).await.latest_snapshot().unwrap();
+    assert_eq!((snapshot.game_id, snapshot.total_deposit), (1, 300.0));
+    assert_eq!((snapshot.user_count, snapshot.average_prediction), (1, 25.0));
+    assert!(game.pool_data(BNB).await.latest_snapshot().is_none());
+}
+
+// Deposit and withdraw only touch their own participant slot, so their cost stays within budget however full the
+// participant list is. Run with --nocapture to see the measurements.
+#[tokio::test]
+async fn compute_units_stay_within_budget_as_participants_grow() {
+    let mut game = TestGame::start().await;
+    game.start_game(1).await.unwrap();
+
+    for participants in [0, 1_000, MAX_PARTICIPANTS - 1] {
+        let wallet = game.create_user(1_000).await;
+        game.fill_participants(SOLANA, participants).await;
+
+        let deposit = game.deposit_instruction(&wallet.pubkey(), SOLANA, 100.0, 30.0).await;
+        let deposit_units = compute_units(&mut game.context, &[deposit], &[&wallet]).await;
+        game.deposit(&wallet, SOLANA, 100.0, 30.0).await.unwrap();
+        let withdraw = game.withdraw_instruction(&wallet.pubkey(), SOLANA, 100.0).await;
+        let withdraw_units = compute_units(&mut game.context, &[withdraw], &[&wallet]).await;
+        println!("{} participants: deposit {} CU, withdraw {} CU", participants, deposit_units, withdraw_units);
+
+        assert!(deposit_units < 200_000);
+        assert!(withdraw_units < 200_000);
+    }
+
+    // the last deposit took the last slot
+    assert_eq!(game.pool_data(SOLANA).await.slots_used as usize, MAX_PARTICIPANTS);
+
+    // the list is best effort, so the next user still gets in, just without a slot
+    let wallet = game.create_user(1_000).await;
+    game.deposit(&wallet, SOLANA, 100.0, 30.0).await.unwrap();
+    assert_eq!(game.user(&wallet.pubkey()).await.participant_slot, 0);
+    assert_eq!(game.pool_data(SOLANA).await.participant_count as usize, MAX_PARTICIPANTS);
+    game.withdraw(&wallet, SOLANA, 100.0).await.unwrap();
+}
+
+#[tokio::test]
+async fn users_withdraw_from_pools_without_pool_data() {
+    let mut game = TestGame::start().await;
+    let alice = game.create_user(1_000).await;
+    game.start_game(1).await.unwrap();
+    game.deposit(&alice, SOLANA, 100.0, 30.0).await.unwrap();
+
+    // a listed user can't leave without the PoolData account that lists them
+    let mut pool = game.pool(SOLANA).await;
+    pool.pool_data = Pubkey::default();
+    write_account(&mut game.context, pool_pda(SOLANA), &pool).await;
+    assert_error(game.withdraw(&alice, SOLANA, 100.0).await, ErrorCode::InvalidPoolData);
+
+    // but users who aren't listed, like those of a pool from before PoolData, don't need it
+    let mut user = game.user(&alice.pubkey()).await;
+    user.participant_slot = 0;
+    write_account(&mut game.context, user_pda(&alice.pubkey()), &user).await;
+    next_blockhash(&mut game.context).await;
+    game.withdraw(&alice, SOLANA, 100.0).await.unwrap();
+    assert_eq!(game.user(&alice.pubkey()).await.balance, 0.0);
+}
@@ -0,0 +1,1355 @@
+#![allow(dead_code)]
+
+use anchor_lang::{AccountDeserialize, AccountSerialize, InstructionData, ToAccountMetas};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    bpf_loader_upgradeable,
+    clock::Clock,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction, system_program, sysvar,
+    transaction::{Transaction, TransactionError},
+};
+use spl_token_2022::extension::{transfer_fee, ExtensionType, StateWithExtensions};
+
+pub const SOLANA: u8 = 1;
+pub const BNB: u8 = 2;
+pub const POLYGON: u8 = 3;
+pub const ETHEREUM: u8 = 4;
+pub const ALL_POOLS: [u8; 4] = [SOLANA, BNB, POLYGON, ETHEREUM];
+
+pub const GAME_DURATION_IN_SECS: i64 = 5 * 24 * 60 * 60;
+
+pub fn program_test() -> ProgramTest {
+    let mut program_test = ProgramTest::new("coin_war", coin_war::id(), processor!(coin_war::entry));
+    program_test.add_program("mock_lending", mock_lending::id(), processor!(mock_lending::entry));
+    add_native_mint(&mut program_test);
+    program_test
+}
+
+// The native mint as it is on the real clusters, for SOL deposits
+fn add_native_mint(program_test: &mut ProgramTest) {
+    let mint = spl_token::state::Mint {
+        decimals: spl_token::native_mint::DECIMALS,
+        is_initialized: true,
+        ..Default::default()
+    };
+    let mut data = vec![0; spl_token::state::Mint::LEN];
+    spl_token::state::Mint::pack(mint, &mut data).unwrap();
+    let account = Account { lamports: 1_000_000_000, data, owner: spl_token::id(), executable: false, rent_epoch: 0 };
+    program_test.add_account(spl_token::native_mint::id(), account);
+}
+
+pub fn program_data_pda() -> Pubkey {
+    Pubkey::find_program_address(&[coin_war::id().as_ref()], &bpf_loader_upgradeable::id()).0
+}
+
+pub fn config_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &coin_war::id()).0
+}
+
+pub fn pool_pda(pool_name: u8) -> Pubkey {
+    Pubkey::find_program_address(&[&[pool_name]], &coin_war::id()).0
+}
+
+pub fn pool_wallet_pda(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"pool_wallet", mint.as_ref()], &coin_war::id()).0
+}
+
+pub fn user_pda(wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"user", wallet.as_ref()], &coin_war::id()).0
+}
+
+pub fn user_wallet_pda(wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"user_wallet", user_pda(wallet).as_ref()], &coin_war::id()).0
+}
+
+pub fn tx_pda(wallet: &Pubkey, pool_name: u8, txn_count: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"tx", user_pda(wallet).as_ref(), pool_pda(pool_name).as_ref(), &txn_count.to_be_bytes()],
+        &coin_war::id(),
+    )
+    .0
+}
+
+pub fn unwrap_pda(wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"unwrap", user_pda(wallet).as_ref()], &coin_war::id()).0
+}
+
+pub fn game_pda(game_id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"game", &game_id.to_be_bytes()], &coin_war::id()).0
+}
+
+pub fn sponsorship_pda(game_id: u64, sponsor_count: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"sponsorship", &game_id.to_be_bytes(), &sponsor_count.to_be_bytes()],
+        &coin_war::id(),
+    )
+    .0
+}
+
+pub fn distributor_pda(game_id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"distributor", &game_id.to_be_bytes()], &coin_war::id()).0
+}
+
+pub fn delegation_pda(wallet: &Pubkey, delegate: &Pubkey) -> Pubkey {
+    coin_war::delegation::delegation_address(&user_pda(wallet), delegate)
+}
+
+pub fn lending_reserve_pda(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"reserve", mint.as_ref()], &mock_lending::id()).0
+}
+
+pub fn lending_reserve_vault_pda(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"reserve_vault", mint.as_ref()], &mock_lending::id()).0
+}
+
+pub fn lending_obligation_pda(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"obligation", lending_reserve_pda(mint).as_ref(), pool_wallet_pda(mint).as_ref()],
+        &mock_lending::id(),
+    )
+    .0
+}
+
+pub fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: coin_war::id(),
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+// Identical transactions under the same blockhash are deduplicated by the bank, so
+// resending one needs a `next_blockhash` first
+pub async fn process(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let blockhash = context.banks_client.get_latest_blockhash().await?;
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+// Compute units a transaction would consume, measured by simulating it
+pub async fn compute_units(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> u64 {
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+    let simulation = context.banks_client.simulate_transaction(transaction).await.unwrap();
+    simulation.result.unwrap().unwrap();
+    simulation.simulation_details.unwrap().units_consumed
+}
+
+// Assert that a transaction failed with the given coin-war error code
+pub fn assert_error(result: Result<(), BanksClientError>, error: coin_war::ErrorCode) {
+    match result.unwrap_err().unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => {
+            assert_eq!(code, u32::from(error), "unexpected error code {}", code)
+        }
+        other => panic!("unexpected error {:?}", other),
+    }
+}
+
+pub async fn get_account<T: AccountDeserialize>(context: &mut ProgramTestContext, address: Pubkey) -> T {
+    let account = context.banks_client.get_account(address).await.unwrap().unwrap();
+    T::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+// Replace the data of an account, e.g. with a fixture of an earlier layout, keeping its lamports
+pub async fn set_account_data(context: &mut ProgramTestContext, address: Pubkey, data: Vec<u8>) {
+    let mut account: Account = context.banks_client.get_account(address).await.unwrap().unwrap();
+    account.data = data;
+    context.set_account(&address, &account.into());
+}
+
+// processor! programs are not deployed through the upgradeable loader, so give coin_war the ProgramData account it
+// would have on a cluster, with authority allowed to upgrade it. Its lamports come out of the payer, accounts set
+// after genesis don't count in the bank's capitalization, which warp_to_slot checks.
+pub async fn set_upgrade_authority(context: &mut ProgramTestContext, authority: &Pubkey) {
+    // bincode of UpgradeableLoaderState::ProgramData { slot: 0, upgrade_authority_address: Some(authority) }
+    let mut data = vec![3, 0, 0, 0];
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.push(1);
+    data.extend_from_slice(authority.as_ref());
+    let account = Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: bpf_loader_upgradeable::id(),
+        executable: false,
+        rent_epoch: 0,
+    };
+    let payer = context.payer.pubkey();
+    let mut payer_account: Account = context.banks_client.get_account(payer).await.unwrap().unwrap();
+    payer_account.lamports -= account.lamports;
+    context.set_account(&payer, &payer_account.into());
+    context.set_account(&program_data_pda(), &account.into());
+}
+
+// Overwrite the account at address with value, keeping its size
+pub async fn write_account<T: AccountSerialize>(context: &mut ProgramTestContext, address: Pubkey, value: &T) {
+    let mut account: Account = context.banks_client.get_account(address).await.unwrap().unwrap();
+    let mut data = Vec::new();
+    value.try_serialize(&mut data).unwrap();
+    data.resize(account.data.len(), 0);
+    account.data = data;
+    context.set_account(&address, &account.into());
+}
+
+// Optional accounts are left out while they are Pubkey::default()
+pub fn optional(address: Pubkey) -> Option<Pubkey> {
+    (address != Pubkey::default()).then_some(address)
+}
+
+pub async fn account_exists(context: &mut ProgramTestContext, address: Pubkey) -> bool {
+    context.banks_client.get_account(address).await.unwrap().is_some()
+}
+
+pub async fn lamports(context: &mut ProgramTestContext, address: Pubkey) -> u64 {
+    context.banks_client.get_balance(address).await.unwrap()
+}
+
+// SPL Token or Token-2022 program, whichever owns mint
+pub async fn token_program_of(context: &mut ProgramTestContext, mint: &Pubkey) -> Pubkey {
+    context.banks_client.get_account(*mint).await.unwrap().unwrap().owner
+}
+
+// Token account of either token program, without its extensions
+pub async fn token_account(context: &mut ProgramTestContext, address: Pubkey) -> spl_token_2022::state::Account {
+    let account: Account = context.banks_client.get_account(address).await.unwrap().unwrap();
+    StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data).unwrap().base
+}
+
+pub async fn token_balance(context: &mut ProgramTestContext, address: Pubkey) -> u64 {
+    token_account(context, address).await.amount
+}
+
+// Move the cluster clock forward, e.g. to the end of a game. Warping a slot also gives a fresh
+// blockhash, so a transaction that failed before the warp can be sent again afterwards.
+pub async fn warp_forward(context: &mut ProgramTestContext, seconds: i64) {
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    context.warp_to_slot(clock.slot + 2).unwrap();
+    clock.slot += 2;
+    clock.unix_timestamp += seconds;
+    context.set_sysvar(&clock);
+}
+
+// Start a new slot without moving the clock, so an identical transaction can be resent
+pub async fn next_blockhash(context: &mut ProgramTestContext) {
+    warp_forward(context, 0).await;
+}
+
+pub async fn create_mint(context: &mut ProgramTestContext, decimals: u8) -> Keypair {
+    let mint = Keypair::new();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let payer = context.payer.pubkey();
+    let instructions = [
+        system_instruction::create_account(
+            &payer,
+            &mint.pubkey(),
+            rent.minimum_balance(spl_token::state::Mint::LEN),
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_mint(&spl_token::id(), &mint.pubkey(), &payer, None, decimals).unwrap(),
+    ];
+    process(context, &instructions, &[&mint]).await.unwrap();
+    mint
+}
+
+// Token-2022 mint whose transfers pay fee_basis_points of the amount, up to maximum_fee, to the recipient's account
+pub async fn create_mint_with_transfer_fee(
+    context: &mut ProgramTestContext,
+    decimals: u8,
+    fee_basis_points: u16,
+    maximum_fee: u64,
+) -> Keypair {
+    let mint = Keypair::new();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let payer = context.payer.pubkey();
+    let len = ExtensionType::get_account_len::<spl_token_2022::state::Mint>(&[ExtensionType::TransferFeeConfig]);
+    let instructions = [
+        system_instruction::create_account(
+            &payer,
+            &mint.pubkey(),
+            rent.minimum_balance(len),
+            len as u64,
+            &spl_token_2022::id(),
+        ),
+        transfer_fee::instruction::initialize_transfer_fee_config(
+            &spl_token_2022::id(),
+            &mint.pubkey(),
+            None,
+            None,
+            fee_basis_points,
+            maximum_fee,
+        )
+        .unwrap(),
+        spl_token_2022::instruction::initialize_mint(&spl_token_2022::id(), &mint.pubkey(), &payer, None, decimals)
+            .unwrap(),
+    ];
+    process(context, &instructions, &[&mint]).await.unwrap();
+    mint
+}
+
+pub async fn create_token_account(context: &mut ProgramTestContext, mint: &Pubkey, owner: &Pubkey) -> Pubkey {
+    let account = Keypair::new();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let payer = context.payer.pubkey();
+    let instructions = [
+        system_instruction::create_account(
+            &payer,
+            &account.pubkey(),
+            rent.minimum_balance(spl_token::state::Account::LEN),
+            spl_token::state::Account::LEN as u64,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_account(&spl_token::id(), &account.pubkey(), mint, owner).unwrap(),
+    ];
+    process(context, &instructions, &[&account]).await.unwrap();
+    account.pubkey()
+}
+
+// Associated token account of wallet for mint, created by the associated token program
+pub async fn create_associated_token_account(
+    context: &mut ProgramTestContext,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+) -> Pubkey {
+    let token_program = token_program_of(context, mint).await;
+    let address = associated_token_account(wallet, mint, &token_program);
+    let instruction = Instruction {
+        program_id: anchor_spl::associated_token::ID,
+        accounts: vec![
+            AccountMeta::new(context.payer.pubkey(), true),
+            AccountMeta::new(address, false),
+            AccountMeta::new_readonly(*wallet, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(token_program, false),
+        ],
+        // Create
+        data: vec![0],
+    };
+    process(context, &[instruction], &[]).await.unwrap();
+    address
+}
+
+pub fn associated_token_account(wallet: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    anchor_spl::associated_token::get_associated_token_address_with_program_id(wallet, mint, token_program)
+}
+
+pub async fn mint_to(context: &mut ProgramTestContext, mint: &Pubkey, destination: &Pubkey, amount: u64) {
+    let payer = context.payer.pubkey();
+    let token_program = token_program_of(context, mint).await;
+    let instruction =
+        spl_token_2022::instruction::mint_to(&token_program, mint, destination, &payer, &[], amount).unwrap();
+    process(context, &[instruction], &[]).await.unwrap();
+}
+
+pub async fn fund_wallet(context: &mut ProgramTestContext, wallet: &Pubkey) {
+    let payer = context.payer.pubkey();
+    let instruction = system_instruction::transfer(&payer, wallet, 1_000_000_000);
+    process(context, &[instruction], &[]).await.unwrap();
+}
+
+// Game with an allowed mint, its pool wallet and all four pools created. The mint has AMOUNT_DECIMALS decimals, so
+// amounts are base units, and a minimum deposit of one base unit.
+pub struct TestGame {
+    pub context: ProgramTestContext,
+    pub mint: Pubkey,
+}
+
+impl TestGame {
+    pub async fn start() -> TestGame {
+        let mut context = program_test().start_with_context().await;
+        let mint = create_mint(&mut context, coin_war::AMOUNT_DECIMALS).await.pubkey();
+        let payer = context.payer.pubkey();
+        set_upgrade_authority(&mut context, &payer).await;
+        let mut game = TestGame { context, mint };
+        game.initialize_config().await.unwrap();
+        game.add_mint(&mint, 1).await.unwrap();
+        for pool_name in ALL_POOLS {
+            game.create_pool(pool_name).await.unwrap();
+            game.create_pool_data(pool_name).await.unwrap();
+            game.create_share_mint(pool_name).await.unwrap();
+        }
+        game
+    }
+
+    pub fn owner(&self) -> Pubkey {
+        self.context.payer.pubkey()
+    }
+
+    pub async fn initialize_config(&mut self) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::InitializeConfig {
+            admin: self.owner(),
+            config: config_pda(),
+            program_data: program_data_pda(),
+            system_program: system_program::id(),
+        };
+        let instruction = ix(accounts, coin_war::instruction::InitializeConfig {});
+        process(&mut self.context, &[instruction], &[]).await
+    }
+
+    pub async fn add_mint(&mut self, mint: &Pubkey, minimum_deposit: u64) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::AddMint {
+            admin: self.owner(),
+            config: config_pda(),
+            pool_token_account: pool_wallet_pda(mint),
+            mint_address: *mint,
+            token_program: token_program_of(&mut self.context, mint).await,
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+        };
+        let instruction = ix(accounts, coin_war::instruction::AddMint { minimum_deposit });
+        process(&mut self.context, &[instruction], &[]).await
+    }
+
+    pub async fn set_minimum_deposit(&mut self, mint: &Pubkey, minimum_deposit: u64) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::SetMinimumDeposit { admin: self.owner(), config: config_pda() };
+        let instruction = ix(accounts, coin_war::instruction::SetMinimumDeposit { mint: *mint, minimum_deposit });
+        process(&mut self.context, &[instruction], &[]).await
+    }
+
+    pub async fn set_lock_tier(&mut self, games: u8, multiplier: f64) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::SetLockTier { admin: self.owner(), config: config_pda() };
+        let instruction = ix(accounts, coin_war::instruction::SetLockTier { games, multiplier });
+        process(&mut self.context, &[instruction], &[]).await
+    }
+
+    pub async fn config(&mut self) -> coin_war::Config {
+        get_account(&mut self.context, config_pda()).await
+    }
+
+    pub async fn set_paused(&mut self, paused: bool) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::SetPaused { admin: self.owner(), config: config_pda() };
+        let instruction = ix(accounts, coin_war::instruction::SetPaused { paused });
+        process(&mut self.context, &[instruction], &[]).await
+    }
+
+    pub async fn cancel_game(&mut self, game_id: u64) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::CancelGame {
+            admin: self.owner(),
+            config: config_pda(),
+            game: game_pda(game_id),
+        };
+        let instruction = ix(accounts, coin_war::instruction::CancelGame { game_id });
+        process(&mut self.context, &[instruction], &[]).await
+    }
+
+    pub async fn withdraw_treasury(&mut self, destination: Pubkey, amount: u64) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::WithdrawTreasury {
+            admin: self.owner(),
+            config: config_pda(),
+            pool_token_account: pool_wallet_pda(&self.mint),
+            destination,
+            token_program: spl_token::id(),
+            mint_address: self.mint,
+        };
+        let instruction = ix(accounts, coin_war::instruction::WithdrawTreasury { amount });
+        process(&mut self.context, &[instruction], &[]).await
+    }
+
+    pub async fn migrate_config(&mut self) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::MigrateConfig {
+            payer: self.owner(),
+            config: config_pda(),
+            system_program: system_program::id(),
+        };
+        let instruction = ix(accounts, coin_war::instruction::MigrateConfig {});
+        process(&mut self.context, &[instruction], &[]).await
+    }
+
+    pub async fn migrate_user(&mut self, wallet: &Pubkey) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::MigrateUser {
+            payer: self.owner(),
+            user: user_pda(wallet),
+            system_program: system_program::id(),
+        };
+        let instruction = ix(accounts, coin_war::instruction::MigrateUser { _user_key: *wallet });
+        process(&mut self.context, &[instruction], &[]).await
+    }
+
+    pub async fn migrate_pool(&mut self, pool_name: u8) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::MigratePool {
+            payer: self.owner(),
+            pool: pool_pda(pool_name),
+            system_program: system_program::id(),
+        };
+        let instruction = ix(accounts, coin_war::instruction::MigratePool { _pool_name: pool_name });
+        process(&mut self.context, &[instruction], &[]).await
+    }
+
+    pub async fn migrate_game(&mut self, game_id: u64) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::MigrateGame {
+            payer: self.owner(),
+            game: game_pda(game_id),
+            system_program: system_program::id(),
+        };
+        let instruction = ix(accounts, coin_war::instruction::MigrateGame { _game_id: game_id });
+        process(&mut self.context, &[instruction], &[]).await
+    }
+
+    // PoolData address of a pool in any layout, Pubkey::default() if it has none
+    pub async fn pool_data_address(&mut self, pool_name: u8) -> Pubkey {
+        let account = self.context.banks_client.get_account(pool_pda(pool_name)).await.unwrap().unwrap();
+        coin_war::migrate::upgrade_pool(&account.data).unwrap().pool_data
+    }
+
+    // Create a PoolData account and attach it to the pool
+    pub async fn create_pool_data(&mut self, pool_name: u8) -> Result<(), BanksClientError> {
+        let pool_data = Keypair::new();
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+        let accounts = coin_war::accounts::CreatePoolData {
+            admin: self.owner(),
+            config: config_pda(),
+            pool: pool_pda(pool_name),
+            pool_data: pool_data.pubkey(),
+        };
+        let instructions = [
+            system_instruction::create_account(
+                &self.owner(),
+                &pool_data.pubkey(),
+                rent.minimum_balance(coin_war::PoolData::LEN),
+                coin_war::PoolData::LEN as u64,
+                &coin_war::id(),
+            ),
+            ix(accounts, coin_war::instruction::CreatePoolData { pool_name }),
+        ];
+        process(&mut self.context, &instructions, &[&pool_data]).await
+    }
+
+    // Share mint of a pool in any layout, Pubkey::default() if it has none
+    pub async fn share_mint_address(&mut self, pool_name: u8) -> Pubkey {
+        let account = self.context.banks_client.get_account(pool_pda(pool_name)).await.unwrap().unwrap();
+        coin_war::migrate::upgrade_pool(&account.data).unwrap().share_mint
+    }
+
+    pub async fn create_share_mint(&mut self, pool_name: u8) -> Result<(), BanksClientError> {
+        let share_mint = Keypair::new();
+        let accounts = coin_war::accounts::CreateShareMint {
+            admin: self.owner(),
+            config: config_pda(),
+            pool: pool_pda(pool_name),
+            share_mint: share_mint.pubkey(),
+            token_program: spl_token::id(),
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+        };
+        let instruction = ix(accounts, coin_war::instruction::CreateShareMint { _pool_name: pool_name });
+        process(&mut self.context, &[instruction], &[&share_mint]).await
+    }
+
+    // Associated token account of wallet for the pool's shares
+    pub async fn user_share_account(&mut self, wallet: &Pubkey, pool_name: u8) -> Pubkey {
+        let share_mint = self.share_mint_address(pool_name).await;
+        associated_token_account(wallet, &share_mint, &spl_token::id())
+    }
+
+    // Create the share accounts of wallet for every pool with a share mint that it has none for yet
+    pub async fn create_share_accounts(&mut self, wallet: &Pubkey) {
+        for pool_name in ALL_POOLS {
+            let share_mint = self.share_mint_address(pool_name).await;
+            let user_share_account = self.user_share_account(wallet, pool_name).await;
+            if share_mint != Pubkey::default() && !account_exists(&mut self.context, user_share_account).await {
+                create_associated_token_account(&mut self.context, wallet, &share_mint).await;
+            }
+        }
+    }
+
+    pub async fn share_balance(&mut self, wallet: &Pubkey, pool_name: u8) -> u64 {
+        let user_share_account = self.user_share_account(wallet, pool_name).await;
+        token_balance(&mut self.context, user_share_account).await
+    }
+
+    pub async fn distribute_prize(&mut self, game_id: u64, pool_name: u8) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::DistributePrize {
+            admin: self.owner(),
+            config: config_pda(),
+            game: game_pda(game_id),
+            pool: pool_pda(pool_name),
+        };
+        let instruction = ix(accounts, coin_war::instruction::DistributePrize { game_id, pool_name });
+        process(&mut self.context, &[instruction], &[]).await
+    }
+
+    pub async fn issue_shares(&mut self, wallet: &Keypair, pool_name: u8) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::IssueShares {
+            initializer: wallet.pubkey(),
+            user: user_pda(&wallet.pubkey()),
+            pool: pool_pda(pool_name),
+            share_mint: self.share_mint_address(pool_name).await,
+            user_share_account: self.user_share_account(&wallet.pubkey(), pool_name).await,
+            token_program: spl_token::id(),
+        };
+        let instruction = ix(accounts, coin_war::instruction::IssueShares { pool_name });
+        process(&mut self.context, &[instruction], &[wallet]).await
+    }
+
+    pub async fn snapshot_pool(&mut self, game_id: u64, pool_name: u8) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::SnapshotPool {
+            admin: self.owner(),
+            config: config_pda(),
+            game: game_pda(game_id),
+            pool: pool_pda(pool_name),
+            pool_data: self.pool_data_address(pool_name).await,
+        };
+        let instruction = ix(accounts, coin_war::instruction::SnapshotPool { game_id, _pool_name: pool_name });
+        process(&mut self.context, &[instruction], &[]).await
+    }
+
+    // Boxed, PoolData is too large to be passed around on the stack of a test thread
+    pub async fn pool_data(&mut self, pool_name: u8) -> Box<coin_war::PoolData> {
+        let address = self.pool_data_address(pool_name).await;
+        let account = self.context.banks_client.get_account(address).await.unwrap().unwrap();
+        let mut pool_data: Box<coin_war::PoolData> = bytemuck::zeroed_box();
+        bytemuck::bytes_of_mut(&mut *pool_data).copy_from_slice(&account.data[8..]);
+        pool_data
+    }
+
+    // List made up participants in the pool until count slots are used, as if that many users had deposited
+    pub async fn fill_participants(&mut self, pool_name: u8, count: usize) {
+        let address = self.pool_data_address(pool_name).await;
+        let mut data = self.context.banks_client.get_account(address).await.unwrap().unwrap().data;
+        let pool_data: &mut coin_war::PoolData = bytemuck::from_bytes_mut(&mut data[8..]);
+        for index in pool_data.slots_used as usize..count {
+            pool_data.participants[index] = Pubkey::new_unique();
+            pool_data.participant_count += 1;
+        }
+        pool_data.slots_used = pool_data.slots_used.max(count as u32);
+        set_account_data(&mut self.context, address, data).await;
+    }
+
+    pub async fn create_pool(&mut self, pool_name: u8) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::CreatePool {
+            admin: self.owner(),
+            config: config_pda(),
+            pool: pool_pda(pool_name),
+            system_program: system_program::id(),
+        };
+        let instruction = ix(accounts, coin_war::instruction::CreatePool { pool_name });
+        process(&mut self.context, &[instruction], &[]).await
+    }
+
+    // Create a player with a User account and `amount` tokens in its user wallet
+    pub async fn create_user(&mut self, amount: u64) -> Keypair {
+        let mint = self.mint;
+        let wallet = Keypair::new();
+        self.create_user_with_mint(&wallet, &mint).await.unwrap();
+        if amount > 0 {
+            mint_to(&mut self.context, &mint, &user_wallet_pda(&wallet.pubkey()), amount).await;
+        }
+        wallet
+    }
+
+    // Create the User account of wallet with a user wallet of mint, and its share accounts
+    pub async fn create_user_with_mint(&mut self, wallet: &Keypair, mint: &Pubkey) -> Result<(), BanksClientError> {
+        fund_wallet(&mut self.context, &wallet.pubkey()).await;
+        let accounts = coin_war::accounts::CreateUser {
+            initializer: wallet.pubkey(),
+            config: config_pda(),
+            user: user_pda(&wallet.pubkey()),
+            user_token_account: user_wallet_pda(&wallet.pubkey()),
+            token_program: token_program_of(&mut self.context, mint).await,
+            mint_address: *mint,
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+        };
+        let instruction = ix(accounts, coin_war::instruction::CreateUser {});
+        process(&mut self.context, &[instruction], &[wallet]).await?;
+        self.create_share_accounts(&wallet.pubkey()).await;
+        Ok(())
+    }
+
+    // Create the User account of wallet without a user wallet, it deposits from its associated token account. Its
+    // share accounts are created too.
+    pub async fn create_user_without_wallet(&mut self, wallet: &Keypair) -> Result<(), BanksClientError> {
+        fund_wallet(&mut self.context, &wallet.pubkey()).await;
+        let accounts = coin_war::accounts::CreateUserWithoutWallet {
+            initializer: wallet.pubkey(),
+            user: user_pda(&wallet.pubkey()),
+            system_program: system_program::id(),
+        };
+        let instruction = ix(accounts, coin_war::instruction::CreateUserWithoutWallet {});
+        process(&mut self.context, &[instruction], &[wallet]).await?;
+        self.create_share_accounts(&wallet.pubkey()).await;
+        Ok(())
+    }
+
+    // Mint the user deposits and gets paid in: that of the user wallet, or else the one the balance is held in, the
+    // game's mint before the first deposit
+    pub async fn user_mint(&mut self, wallet: &Pubkey) -> Pubkey {
+        if account_exists(&mut self.context, user_wallet_pda(wallet)).await {
+            return token_account(&mut self.context, user_wallet_pda(wallet)).await.mint;
+        }
+        let mint = self.user(wallet).await.mint;
+        if mint == Pubkey::default() {
+            self.mint
+        } else {
+            mint
+        }
+    }
+
+    // The user wallet, or the associated token account of the user mint for users without one
+    pub async fn user_token_account(&mut self, wallet: &Pubkey) -> Pubkey {
+        if account_exists(&mut self.context, user_wallet_pda(wallet)).await {
+            return user_wallet_pda(wallet);
+        }
+        let mint = self.user_mint(wallet).await;
+        let token_program = token_program_of(&mut self.context, &mint).await;
+        associated_token_account(wallet, &mint, &token_program)
+    }
+
+    pub async fn deposit_instruction(
+        &mut self,
+        wallet: &Pubkey,
+        pool_name: u8,
+        amount: f64,
+        prediction: f64,
+    ) -> Instruction {
+        let user_token_account = self.user_token_account(wallet).await;
+        self.deposit_from_instruction(wallet, user_token_account, pool_name, amount, prediction).await
+    }
+
+    pub async fn deposit_from_instruction(
+        &mut self,
+        wallet: &Pubkey,
+        user_token_account: Pubkey,
+        pool_name: u8,
+        amount: f64,
+        prediction: f64,
+    ) -> Instruction {
+        let mint = token_account(&mut self.context, user_token_account).await.mint;
+        let accounts = coin_war::accounts::Deposit {
+            initializer: *wallet,
+            config: config_pda(),
+            user: user_pda(wallet),
+            user_token_account,
+            pool: pool_pda(pool_name),
+            pool_data: self.pool_data_address(pool_name).await,
+            pool_token_account: pool_wallet_pda(&mint),
+            share_mint: self.share_mint_address(pool_name).await,
+            user_share_account: self.user_share_account(wallet, pool_name).await,
+            token_program: token_program_of(&mut self.context, &mint).await,
+            share_token_program: spl_token::id(),
+            mint_address: mint,
+            system_program: system_program::id(),
+        };
+        ix(accounts, coin_war::instruction::Deposit { amount, pool_name, prediction })
+    }
+
+    pub async fn deposit(
+        &mut self,
+        wallet: &Keypair,
+        pool_name: u8,
+        amount: f64,
+        prediction: f64,
+    ) -> Result<(), BanksClientError> {
+        let instruction = self.deposit_instruction(&wallet.pubkey(), pool_name, amount, prediction).await;
+        process(&mut self.context, &[instruction], &[wallet]).await
+    }
+
+    // Deposit from a given token account rather than the one the user is paid into
+    pub async fn deposit_from(
+        &mut self,
+        wallet: &Keypair,
+        user_token_account: Pubkey,
+        pool_name: u8,
+        amount: f64,
+        prediction: f64,
+    ) -> Result<(), BanksClientError> {
+        let instruction =
+            self.deposit_from_instruction(&wallet.pubkey(), user_token_account, pool_name, amount, prediction).await;
+        process(&mut self.context, &[instruction], &[wallet]).await
+    }
+
+    pub async fn withdraw_instruction(&mut self, wallet: &Pubkey, pool_name: u8, amount: f64) -> Instruction {
+        let user_token_account = self.user_token_account(wallet).await;
+        self.withdraw_to_instruction(wallet, user_token_account, pool_name, amount).await
+    }
+
+    pub async fn withdraw_to_instruction(
+        &mut self,
+        wallet: &Pubkey,
+        user_token_account: Pubkey,
+        pool_name: u8,
+        amount: f64,
+    ) -> Instruction {
+        let user: coin_war::User = get_account(&mut self.context, user_pda(wallet)).await;
+        let mint = token_account(&mut self.context, user_token_account).await.mint;
+        let accounts = coin_war::accounts::Withdraw {
+            initializer: *wallet,
+            config: config_pda(),
+            user: user_pda(wallet),
+            user_token_account,
+            pool: pool_pda(pool_name),
+            pool_data: optional(self.pool_data_address(pool_name).await),
+            pool_token_account: pool_wallet_pda(&mint),
+            share_mint: self.share_mint_address(pool_name).await,
+            user_share_account: self.user_share_account(wallet, pool_name).await,
+            transaction: tx_pda(wallet, pool_name, user.txn_count),
+            token_program: token_program_of(&mut self.context, &mint).await,
+            share_token_program: spl_token::id(),
+            mint_address: mint,
+            system_program: system_program::id(),
+            associated_token_program: anchor_spl::associated_token::ID,
+            rent: sysvar::rent::id(),
+        };
+        ix(accounts, coin_war::instruction::Withdraw { amount, pool_name })
+    }
+
+    pub async fn withdraw(&mut self, wallet: &Keypair, pool_name: u8, amount: f64) -> Result<(), BanksClientError> {
+        let instruction = self.withdraw_instruction(&wallet.pubkey(), pool_name, amount).await;
+        process(&mut self.context, &[instruction], &[wallet]).await
+    }
+
+    // Withdraw into a given token account rather than the one the user is paid into
+    pub async fn withdraw_to(
+        &mut self,
+        wallet: &Keypair,
+        user_token_account: Pubkey,
+        pool_name: u8,
+        amount: f64,
+    ) -> Result<(), BanksClientError> {
+        let instruction = self.withdraw_to_instruction(&wallet.pubkey(), user_token_account, pool_name, amount).await;
+        process(&mut self.context, &[instruction], &[wallet]).await
+    }
+
+    pub async fn deposit_sol(
+        &mut self,
+        wallet: &Keypair,
+        pool_name: u8,
+        amount: f64,
+        prediction: f64,
+    ) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::DepositSol {
+            initializer: wallet.pubkey(),
+            config: config_pda(),
+            user: user_pda(&wallet.pubkey()),
+            pool: pool_pda(pool_name),
+            pool_data: self.pool_data_address(pool_name).await,
+            pool_token_account: pool_wallet_pda(&spl_token::native_mint::id()),
+            share_mint: self.share_mint_address(pool_name).await,
+            user_share_account: self.user_share_account(&wallet.pubkey(), pool_name).await,
+            token_program: spl_token::id(),
+            system_program: system_program::id(),
+        };
+        let instruction = ix(accounts, coin_war::instruction::DepositSol { amount, pool_name, prediction });
+        process(&mut self.context, &[instruction], &[wallet]).await
+    }
+
+    pub async fn withdraw_sol(&mut self, wallet: &Keypair, pool_name: u8, amount: f64) -> Result<(), BanksClientError> {
+        let user = self.user(&wallet.pubkey()).await;
+        let accounts = coin_war::accounts::WithdrawSol {
+            initializer: wallet.pubkey(),
+            config: config_pda(),
+            user: user_pda(&wallet.pubkey()),
+            pool: pool_pda(pool_name),
+            pool_data: optional(self.pool_data_address(pool_name).await),
+            pool_token_account: pool_wallet_pda(&spl_token::native_mint::id()),
+            share_mint: self.share_mint_address(pool_name).await,
+            user_share_account: self.user_share_account(&wallet.pubkey(), pool_name).await,
+            unwrap_account: unwrap_pda(&wallet.pubkey()),
+            transaction: tx_pda(&wallet.pubkey(), pool_name, user.txn_count),
+            mint_address: spl_token::native_mint::id(),
+            token_program: spl_token::id(),
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+        };
+        let instruction = ix(accounts, coin_war::instruction::WithdrawSol { amount, pool_name });
+        process(&mut self.context, &[instruction], &[wallet]).await
+    }
+
+    // Gift deposit out of the donor's token account, the beneficiary's share accounts must exist
+    pub async fn deposit_for_user(
+        &mut self,
+        donor: &Keypair,
+        beneficiary: &Pubkey,
+        donor_token_account: Pubkey,
+        pool_name: u8,
+        amount: f64,
+        prediction: f64,
+    ) -> Result<(), BanksClientError> {
+        let txn_count = if account_exists(&mut self.context, user_pda(beneficiary)).await {
+            self.user(beneficiary).await.txn_count
+        } else {
+            0
+        };
+        let mint = token_account(&mut self.context, donor_token_account).await.mint;
+        let accounts = coin_war::accounts::DepositForUser {
+            donor: donor.pubkey(),
+            config: config_pda(),
+            user: user_pda(beneficiary),
+            donor_token_account,
+            pool: pool_pda(pool_name),
+            pool_data: self.pool_data_address(pool_name).await,
+            pool_token_account: pool_wallet_pda(&mint),
+            share_mint: self.share_mint_address(pool_name).await,
+            beneficiary_share_account: self.user_share_account(beneficiary, pool_name).await,
+            transaction: tx_pda(beneficiary, pool_name, txn_count),
+            token_program: token_program_of(&mut self.context, &mint).await,
+            share_token_program: spl_token::id(),
+            mint_address: mint,
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+        };
+        let data = coin_war::instruction::DepositForUser { beneficiary: *beneficiary, amount, pool_name, prediction };
+        process(&mut self.context, &[ix(accounts, data)], &[donor]).await
+    }
+
+    pub async fn approve_delegate(&mut self, wallet: &Keypair, delegate: &Pubkey) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::ApproveDelegate {
+            initializer: wallet.pubkey(),
+            user: user_pda(&wallet.pubkey()),
+            delegation: delegation_pda(&wallet.pubkey(), delegate),
+            system_program: system_program::id(),
+        };
+        let instruction = ix(accounts, coin_war::instruction::ApproveDelegate { delegate: *delegate });
+        process(&mut self.context, &[instruction], &[wallet]).await
+    }
+
+    pub async fn revoke_delegate(&mut self, wallet: &Keypair, delegate: &Pubkey) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::RevokeDelegate {
+            initializer: wallet.pubkey(),
+            user: user_pda(&wallet.pubkey()),
+            delegation: delegation_pda(&wallet.pubkey(), delegate),
+        };
+        let instruction = ix(accounts, coin_war::instruction::RevokeDelegate { _delegate: *delegate });
+        process(&mut self.context, &[instruction], &[wallet]).await
+    }
+
+    // Deposit for beneficiary out of a token account of the delegate, the shares go to the delegate's share account
+    pub async fn deposit_for(
+        &mut self,
+        delegate: &Keypair,
+        beneficiary: &Pubkey,
+        delegate_token_account: Pubkey,
+        pool_name: u8,
+        amount: f64,
+        prediction: f64,
+    ) -> Result<(), BanksClientError> {
+        let mint = token_account(&mut self.context, delegate_token_account).await.mint;
+        let accounts = coin_war::accounts::DepositFor {
+            delegate: delegate.pubkey(),
+            config: config_pda(),
+            user: user_pda(beneficiary),
+            delegation: delegation_pda(beneficiary, &delegate.pubkey()),
+            delegate_token_account,
+            pool: pool_pda(pool_name),
+            pool_data: self.pool_data_address(pool_name).await,
+            pool_token_account: pool_wallet_pda(&mint),
+            share_mint: self.share_mint_address(pool_name).await,
+            delegate_share_account: self.user_share_account(&delegate.pubkey(), pool_name).await,
+            token_program: token_program_of(&mut self.context, &mint).await,
+            share_token_program: spl_token::id(),
+            mint_address: mint,
+        };
+        let data = coin_war::instruction::DepositFor { beneficiary: *beneficiary, amount, pool_name, prediction };
+        process(&mut self.context, &[ix(accounts, data)], &[delegate]).await
+    }
+
+    // Withdraw what the delegate deposited for beneficiary into destination, the test payer pays for the record
+    pub async fn delegate_withdraw(
+        &mut self,
+        delegate: &Keypair,
+        beneficiary: &Pubkey,
+        destination: Pubkey,
+        pool_name: u8,
+        amount: f64,
+    ) -> Result<(), BanksClientError> {
+        let user = self.user(beneficiary).await;
+        let mint = token_account(&mut self.context, destination).await.mint;
+        let accounts = coin_war::accounts::WithdrawTo {
+            delegate: delegate.pubkey(),
+            payer: self.owner(),
+            config: config_pda(),
+            user: user_pda(beneficiary),
+            delegation: delegation_pda(beneficiary, &delegate.pubkey()),
+            destination,
+            pool: pool_pda(pool_name),
+            pool_data: optional(self.pool_data_address(pool_name).await),
+            pool_token_account: pool_wallet_pda(&mint),
+            share_mint: self.share_mint_address(pool_name).await,
+            delegate_share_account: self.user_share_account(&delegate.pubkey(), pool_name).await,
+            transaction: tx_pda(beneficiary, pool_name, user.txn_count),
+            token_program: token_program_of(&mut self.context, &mint).await,
+            share_token_program: spl_token::id(),
+            mint_address: mint,
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+        };
+        let data = coin_war::instruction::WithdrawTo { beneficiary: *beneficiary, amount, pool_name };
+        process(&mut self.context, &[ix(accounts, data)], &[delegate]).await
+    }
+
+    pub async fn request_withdrawal(
+        &mut self,
+        wallet: &Keypair,
+        game_id: u64,
+        pool_name: u8,
+        amount: f64,
+    ) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::RequestWithdrawal {
+            initializer: wallet.pubkey(),
+            config: config_pda(),
+            user: user_pda(&wallet.pubkey()),
+            game: game_pda(game_id),
+            pool: pool_pda(pool_name),
+            pool_data: optional(self.pool_data_address(pool_name).await),
+            share_mint: self.share_mint_address(pool_name).await,
+            user_share_account: self.user_share_account(&wallet.pubkey(), pool_name).await,
+            share_token_program: spl_token::id(),
+            mint_address: self.user_mint(&wallet.pubkey()).await,
+        };
+        let data = coin_war::instruction::RequestWithdrawal { _game_id: game_id, amount, pool_name };
+        let instruction = ix(accounts, data);
+        process(&mut self.context, &[instruction], &[wallet]).await
+    }
+
+    pub async fn cancel_withdrawal_request(
+        &mut self,
+        wallet: &Keypair,
+        pool_name: u8,
+        prediction: f64,
+    ) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::CancelWithdrawalRequest {
+            initializer: wallet.pubkey(),
+            config: config_pda(),
+            user: user_pda(&wallet.pubkey()),
+            pool: pool_pda(pool_name),
+            pool_data: self.pool_data_address(pool_name).await,
+            share_mint: self.share_mint_address(pool_name).await,
+            user_share_account: self.user_share_account(&wallet.pubkey(), pool_name).await,
+            share_token_program: spl_token::id(),
+            mint_address: self.user_mint(&wallet.pubkey()).await,
+        };
+        let instruction = ix(accounts, coin_war::instruction::CancelWithdrawalRequest { pool_name, prediction });
+        process(&mut self.context, &[instruction], &[wallet]).await
+    }
+
+    pub async fn lock_deposit(
+        &mut self,
+        wallet: &Keypair,
+        game_id: u64,
+        amount: f64,
+        pool_name: u8,
+        games: u8,
+    ) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::LockDeposit {
+            initializer: wallet.pubkey(),
+            config: config_pda(),
+            user: user_pda(&wallet.pubkey()),
+            game: game_pda(game_id),
+            pool: pool_pda(pool_name),
+        };
+        let data = coin_war::instruction::LockDeposit { _game_id: game_id, amount, pool_name, games };
+        let instruction = ix(accounts, data);
+        process(&mut self.context, &[instruction], &[wallet]).await
+    }
+
+    // Signed by the owner, anyone can unlock
+    pub async fn unlock_deposit(&mut self, wallet: &Pubkey, pool_name: u8) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::UnlockDeposit {
+            signer: self.owner(),
+            config: config_pda(),
+            user: user_pda(wallet),
+            pool: pool_pda(pool_name),
+        };
+        let instruction = ix(accounts, coin_war::instruction::UnlockDeposit { user_key: *wallet, pool_name });
+        process(&mut self.context, &[instruction], &[]).await
+    }
+
+    pub async fn complete_withdrawal(&mut self, wallet: &Keypair, pool_name: u8) -> Result<(), BanksClientError> {
+        let user = self.user(&wallet.pubkey()).await;
+        let user_token_account = self.user_token_account(&wallet.pubkey()).await;
+        let mint = token_account(&mut self.context, user_token_account).await.mint;
+        let accounts = coin_war::accounts::CompleteWithdrawal {
+            initializer: wallet.pubkey(),
+            config: config_pda(),
+            user: user_pda(&wallet.pubkey()),
+            user_token_account,
+            pool: pool_pda(pool_name),
+            pool_token_account: pool_wallet_pda(&mint),
+            transaction: tx_pda(&wallet.pubkey(), pool_name, user.txn_count),
+            token_program: token_program_of(&mut self.context, &mint).await,
+            mint_address: mint,
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+        };
+        let instruction = ix(accounts, coin_war::instruction::CompleteWithdrawal { pool_name });
+        process(&mut self.context, &[instruction], &[wallet]).await
+    }
+
+    pub async fn make_prediction(
+        &mut self,
+        wallet: &Keypair,
+        pool_name: u8,
+        prediction: f64,
+    ) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::MakePrediction {
+            owner: wallet.pubkey(),
+            user: user_pda(&wallet.pubkey()),
+            pool: pool_pda(pool_name),
+        };
+        let instruction = ix(accounts, coin_war::instruction::MakePrediction { pool_name, prediction });
+        process(&mut self.context, &[instruction], &[wallet]).await
+    }
+
+    pub async fn set_payout_preference(
+        &mut self,
+        wallet: &Keypair,
+        auto_compound: bool,
+    ) -> Result<(), BanksClientError> {
+        let accounts =
+            coin_war::accounts::SetPayoutPreference { owner: wallet.pubkey(), user: user_pda(&wallet.pubkey()) };
+        let instruction = ix(accounts, coin_war::instruction::SetPayoutPreference { auto_compound });
+        process(&mut self.context, &[instruction], &[wallet]).await
+    }
+
+    pub async fn select_winning_pool(
+        &mut self,
+        pool_names: Vec<u8>,
+        pool_predictions: Vec<f64>,
+        pool_coin_prices: Vec<f64>,
+    ) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::SelectWinningPool { clock: sysvar::clock::id() };
+        let instruction = ix(
+            accounts,
+            coin_war::instruction::SelectWinningPool { pool_names, pool_predictions, pool_coin_prices },
+        );
+        process(&mut self.context, &[instruction], &[]).await
+    }
+
+    pub async fn start_game(&mut self, game_id: u64) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::StartGame {
+            admin: self.owner(),
+            config: config_pda(),
+            game: game_pda(game_id),
+            system_program: system_program::id(),
+        };
+        let instruction = ix(accounts, coin_war::instruction::StartGame { game_id });
+        process(&mut self.context, &[instruction], &[]).await
+    }
+
+    pub async fn settle_game(&mut self, game_id: u64, winning_pool: u8) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::SettleGame {
+            admin: self.owner(),
+            config: config_pda(),
+            game: game_pda(game_id),
+            pool: pool_pda(winning_pool),
+        };
+        let instruction = ix(accounts, coin_war::instruction::SettleGame { game_id, winning_pool });
+        process(&mut self.context, &[instruction], &[]).await
+    }
+
+    // Fund the prize of a game with amount tokens of a new sponsor
+    pub async fn sponsor_prize(&mut self, game_id: u64, amount: u64) -> Result<(), BanksClientError> {
+        let sponsor = Keypair::new();
+        fund_wallet(&mut self.context, &sponsor.pubkey()).await;
+        let mint = self.mint;
+        let sponsor_token_account = create_token_account(&mut self.context, &mint, &sponsor.pubkey()).await;
+        mint_to(&mut self.context, &mint, &sponsor_token_account, amount).await;
+        self.fund_prize(&sponsor, sponsor_token_account, game_id, amount).await
+    }
+
+    pub async fn fund_prize(
+        &mut self,
+        sponsor: &Keypair,
+        sponsor_token_account: Pubkey,
+        game_id: u64,
+        amount: u64,
+    ) -> Result<(), BanksClientError> {
+        let mint = self.mint;
+        self.fund_prize_in(sponsor, sponsor_token_account, &mint, game_id, amount).await
+    }
+
+    pub async fn fund_prize_in(
+        &mut self,
+        sponsor: &Keypair,
+        sponsor_token_account: Pubkey,
+        mint: &Pubkey,
+        game_id: u64,
+        amount: u64,
+    ) -> Result<(), BanksClientError> {
+        let game: coin_war::Game = get_account(&mut self.context, game_pda(game_id)).await;
+        let accounts = coin_war::accounts::FundPrize {
+            sponsor: sponsor.pubkey(),
+            config: config_pda(),
+            game: game_pda(game_id),
+            sponsorship: sponsorship_pda(game_id, game.sponsor_count),
+            sponsor_token_account,
+            pool_token_account: pool_wallet_pda(mint),
+            token_program: spl_token::id(),
+            mint_address: *mint,
+            system_program: system_program::id(),
+        };
+        let instruction = ix(accounts, coin_war::instruction::FundPrize { game_id, amount });
+        process(&mut self.context, &[instruction], &[sponsor]).await
+    }
+
+    pub async fn pay_winning_pool_user(
+        &mut self,
+        wallet: &Pubkey,
+        pool_name: u8,
+        game_id: u64,
+    ) -> Result<(), BanksClientError> {
+        let mint = self.user_mint(wallet).await;
+        let accounts = coin_war::accounts::PayWinner {
+            owner: self.owner(),
+            config: config_pda(),
+            game: game_pda(game_id),
+            user: user_pda(wallet),
+            user_token_account: self.user_token_account(wallet).await,
+            pool: pool_pda(pool_name),
+            pool_token_account: pool_wallet_pda(&mint),
+            token_program: token_program_of(&mut self.context, &mint).await,
+            mint_address: mint,
+            system_program: system_program::id(),
+        };
+        let instruction = ix(
+            accounts,
+            coin_war::instruction::PayWinningPoolUser { user_key: *wallet, pool_name, game_id },
+        );
+        process(&mut self.context, &[instruction], &[]).await
+    }
+
+    pub async fn pay_winners_batch(
+        &mut self,
+        wallets: &[Pubkey],
+        pool_name: u8,
+        game_id: u64,
+    ) -> Result<(), BanksClientError> {
+        let accounts = coin_war::accounts::PayWinnersBatch {
+            owner: self.owner(),
+            config: config_pda(),
+            game: game_pda(game_id),
+            pool: pool_pda(pool_name),
+            pool_token_account: pool_wallet_pda(&self.mint),
+            token_program: spl_token::id(),
+            mint_address: self.mint,
+            system_program: system_program::id(),
+        };
+        let mut instruction = ix(
+            accounts,
+            coin_war::instruction::PayWinnersBatch { game_id, user_keys: wallets.to_vec(), pool_name },
+        );
+        for wallet in wallets {
+            let user_token_account = self.user_token_account(wallet).await;
+            instruction.accounts.push(AccountMeta::new(user_pda(wallet), false));
+            instruction.accounts.push(AccountMeta::new(user_token_account, false));
+        }
+        process(&mut self.context, &[instruction], &[]).await
+    }
+
+    pub async fn user(&mut self, wallet: &Pubkey) -> coin_war::User {
+        get_account(&mut self.context, user_pda(wallet)).await
+    }
+
+    pub async fn pool(&mut self, pool_name: u8) -> coin_war::Pool {
+        get_account(&mut self.context, pool_pda(pool_name)).await
+    }
+
+    // Open a mock lending reserve for the mint paying rate_bps a day, with liquidity to pay the interest from, and
+    // the pool wallet's obligation in it
+    pub async fn open_lending_reserve(&mut self, rate_bps: u16, liquidity: u64) {
+        let mint = self.mint;
+        let accounts = mock_lending::accounts::InitReserve {
+            payer: self.owner(),
+            reserve: lending_reserve_pda(&mint),
+            reserve_vault: lending_reserve_vault_pda(&mint),
+            mint,
+            token_program: spl_token::id(),
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+        };
+        let init_reserve = Instruction {
+            program_id: mock_lending::id(),
+            accounts: accounts.to_account_metas(None),
+            data: mock_lending::instruction::InitReserve { rate_bps }.data(),
+        };
+        let accounts = mock_lending::accounts::InitObligation {
+            payer: self.owner(),
+            owner: pool_wallet_pda(&mint),
+            reserve: lending_reserve_pda(&mint),
+            obligation: lending_obligation_pda(&mint),
+            system_program: system_program::id(),
+        };
+        let init_obligation = Instruction {
+            program_id: mock_lending::id(),
+            accounts: accounts.to_account_metas(None),
+            data: mock_lending::instruction::InitObligation {}.data(),
+        };
+        process(&mut self.context, &[init_reserve, init_obligation], &[]).await.unwrap();
+        mint_to(&mut self.context, &mint, &lending_reserve_vault_pda(&mint), liquidity).await;
+    }
+
+    pub async fn deposit_idle_funds(&mut self, pool_name: u8, amount: u64) -> Result<(), BanksClientError> {
+        let mint = self.mint;
+        let accounts = coin_war::accounts::ManageYield {
+            admin: self.owner(),
+            config: config_pda(),
+            pool: pool_pda(pool_name),
+            pool_token_account: pool_wallet_pda(&mint),
+            reserve: lending_reserve_pda(&mint),
+            reserve_vault: lending_reserve_vault_pda(&mint),
+            obligation: lending_obligation_pda(&mint),
+            lending_program: mock_lending::id(),
+            token_program: spl_token::id(),
+            mint_address: mint,
+        };
+        let instruction = ix(accounts, coin_war::instruction::DepositIdleFunds { _pool_name: pool_name, amount });
+        process(&mut self.context, &[instruction], &[]).await
+    }
+
+    pub async fn harvest_yield(&mut self, game_id: u64) -> Result<(), BanksClientError> {
+        let mint = self.mint;
+        let accounts = coin_war::accounts::HarvestYield {
+            admin: self.owner(),
+            config: config_pda(),
+            game: game_pda(game_id),
+            pool_token_account: pool_wallet_pda(&mint),
+            reserve: lending_reserve_pda(&mint),
+            reserve_vault: lending_reserve_vault_pda(&mint),
+            obligation: lending_obligation_pda(&mint),
+            lending_program: mock_lending::id(),
+            token_program: spl_token::id(),
+            mint_address: mint,
+        };
+        let instruction = ix(accounts, coin_war::instruction::HarvestYield { _game_id: game_id });
+        process(&mut self.context, &[instruction], &[]).await
+    }
+
+    pub async fn game(&mut self, game_id: u64) -> coin_war::Game {
+        get_account(&mut self.context, game_pda(game_id)).await
+    }
+
+    pub async fn user_wallet_balance(&mut self, wallet: &Pubkey) -> u64 {
+        token_balance(&mut self.context, user_wallet_pda(wallet)).await
+    }
+
+    pub async fn pool_wallet_balance(&mut self) -> u64 {
+        token_balance(&mut self.context, pool_wallet_pda(&self.mint)).await
+    }
+}
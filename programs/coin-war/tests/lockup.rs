@@ -0,0 +1,169 @@
+use anchor_lang::prelude::Pubkey;
+use coin_war::{accounting, lockup, math, Config, ErrorCode, Pool, User, MAX_LOCK_TIERS};
+
+fn new_user() -> User {
+    User {
+        pool: 0,
+        last_prediction: 0.0,
+        balance: 0.0,
+        last_active: 0,
+        game_history_count: 0,
+        current_average_balance: 0.0,
+        current_weighted_balance: 0.0,
+        current_weighted_days: 0,
+        txn_count: 0,
+        last_paid_game_id: 0,
+        version: 1,
+        participant_slot: 0,
+        mint: Pubkey::default(),
+        share_rate: 0.0,
+        shares: 0,
+        delegated_balance: 0.0,
+        pending_withdrawal: 0.0,
+        withdrawal_pool: 0,
+        withdrawal_release_time: 0,
+        locked_amount: 0.0,
+        lock_multiplier: 0.0,
+        lock_release_time: 0,
+        auto_compound: false,
+        settled_weight: 0.0,
+        settled_game_id: 0,
+    }
+}
+
+fn new_pool() -> Pool {
+    Pool {
+        is_initialized: true,
+        last_update_timestamp: 0,
+        total_deposit: 0.0,
+        user_count: 0,
+        name: 1,
+        average_prediction: 0.0,
+        invested: 0.0,
+        version: 1,
+        pool_data: Pubkey::default(),
+        share_mint: Pubkey::default(),
+        share_rate: 0.0,
+        pending_prize: 0.0,
+        pending_withdrawals: 0.0,
+        lock_boost: 0.0,
+        won_game_id: 0,
+    }
+}
+
+fn config() -> Config {
+    Config {
+        admin: Pubkey::new_unique(),
+        paused: false,
+        mints: Vec::new(),
+        lock_tiers: Vec::new(),
+        version: 0,
+        settled_until: 0,
+    }
+}
+
+#[test]
+fn lock_tiers_are_set_per_length_and_removed_with_a_zero_multiplier() {
+    let mut config = config();
+    config.set_lock_tier(4, 1.5).unwrap();
+    config.set_lock_tier(4, 2.0).unwrap();
+    assert_eq!((config.lock_tiers.len(), config.lock_tier(4).unwrap().multiplier), (1, 2.0));
+    assert_eq!(config.set_lock_tier(0, 2.0).unwrap_err(), ErrorCode::InvalidLockTier.into());
+    assert_eq!(config.set_lock_tier(2, 0.5).unwrap_err(), ErrorCode::InvalidLockTier.into());
+    assert_eq!(config.set_lock_tier(2, f64::NAN).unwrap_err(), ErrorCode::InvalidLockTier.into());
+
+    for games in 1..MAX_LOCK_TIERS as u8 {
+        config.set_lock_tier(games, 1.1).unwrap();
+    }
+    assert_eq!(config.set_lock_tier(9, 1.1).unwrap_err(), ErrorCode::TooManyLockTiers.into());
+    config.set_lock_tier(4, 0.0).unwrap();
+    assert_eq!(config.lock_tier(4).err().unwrap(), ErrorCode::NoLockTier.into());
+}
+
+#[test]
+fn locked_deposits_weigh_more_and_the_shares_still_add_up_to_the_prize() {
+    let mut config = config();
+    config.set_lock_tier(4, 2.0).unwrap();
+    let tier = *config.lock_tier(4).unwrap();
+    let mut pool = new_pool();
+    let mut alice = new_user();
+    let mut bob = new_user();
+    accounting::apply_deposit(&mut alice, &mut pool, 100.0, 30.0).unwrap();
+    accounting::apply_deposit(&mut bob, &mut pool, 100.0, 30.0).unwrap();
+
+    assert_eq!(
+        lockup::apply_lock(&mut alice, &mut pool, 150.0, &tier, 10).unwrap_err(),
+        ErrorCode::InsufficientBalance.into()
+    );
+    lockup::apply_lock(&mut alice, &mut pool, 50.0, &tier, 10).unwrap();
+    assert_eq!(
+        lockup::apply_lock(&mut alice, &mut pool, 50.0, &tier, 10).unwrap_err(),
+        ErrorCode::AlreadyLocked.into()
+    );
+    assert_eq!((alice.prize_weight(&pool), bob.prize_weight(&pool), pool.prize_weight()), (150.0, 100.0, 250.0));
+    assert_eq!(math::prize_share(alice.prize_weight(&pool), pool.prize_weight(), 100.0), 60.0);
+    assert_eq!(math::prize_share(bob.prize_weight(&pool), pool.prize_weight(), 100.0), 40.0);
+
+    // the locked amount stays until the release, once the game ending at 10 is settled
+    accounting::apply_withdraw(&mut alice, &mut pool, 60.0).unwrap();
+    assert_eq!(alice.check_lock(9, 10).unwrap_err(), ErrorCode::DepositLocked.into());
+    assert_eq!(alice.check_lock(10, 0).unwrap_err(), ErrorCode::DepositLocked.into());
+    assert!(alice.check_lock(10, 10).is_ok());
+    assert_eq!(lockup::apply_unlock(&mut alice, &mut pool, 9, 10).unwrap_err(), ErrorCode::DepositLocked.into());
+    assert_eq!(lockup::apply_unlock(&mut alice, &mut pool, 10, 0).unwrap_err(), ErrorCode::DepositLocked.into());
+    assert_eq!(lockup::apply_unlock(&mut alice, &mut pool, 10, 10).unwrap(), 50.0);
+    assert_eq!((alice.locked_amount, alice.lock_boost(), pool.lock_boost), (0.0, 0.0, 0.0));
+    assert_eq!(lockup::apply_unlock(&mut alice, &mut pool, 10, 10).unwrap_err(), ErrorCode::NoLock.into());
+}
+
+#[test]
+fn locks_are_released_at_the_end_of_their_last_game() {
+    let game_duration = coin_war::GAME_DURATION_IN_DAYS * 24 * 60 * 60;
+    let end_time = 1_000;
+    assert_eq!(lockup::release_time(end_time, 1), end_time);
+    assert_eq!(lockup::release_time(end_time, 3), end_time + 2 * game_duration);
+
+    // once that game is settled, so its boost is in the winning weight, or a game later if it never is
+    assert!(!lockup::is_released(end_time, end_time, end_time - 1));
+    assert!(lockup::is_released(end_time, end_time, end_time));
+    assert!(lockup::is_released(end_time, end_time + 1, end_time + game_duration));
+    assert!(!lockup::is_released(end_time, end_time + game_duration - 1, 0));
+    assert!(lockup::is_released(end_time, end_time + game_duration, 0));
+}
+
+#[test]
+fn locks_are_taken_a_while_before_the_game_ends() {
+    let end_time = 1_000_000;
+    assert!(lockup::check_lock_age(end_time - lockup::MIN_LOCK_AGE_IN_SECS, end_time).is_ok());
+    let error = lockup::check_lock_age(end_time - lockup::MIN_LOCK_AGE_IN_SECS + 1, end_time).unwrap_err();
+    assert_eq!(error, ErrorCode::LockTooLate.into());
+}
+
+#[test]
+fn prizes_distributed_before_a_lock_can_be_locked_and_boost_on_the_same_basis() {
+    let mut config = config();
+    config.set_lock_tier(4, 2.0).unwrap();
+    let tier = *config.lock_tier(4).unwrap();
+    let mut pool = new_pool();
+    let mut alice = new_user();
+    let mut bob = new_user();
+    accounting::apply_deposit(&mut alice, &mut pool, 100.0, 30.0).unwrap();
+    accounting::apply_deposit(&mut bob, &mut pool, 100.0, 30.0).unwrap();
+    pool.distribute(100.0).unwrap();
+    assert_eq!((alice.balance, alice.own_balance_at_rate(&pool)), (100.0, 150.0));
+
+    assert_eq!(
+        lockup::apply_lock(&mut alice, &mut pool, 151.0, &tier, 10).unwrap_err(),
+        ErrorCode::InsufficientBalance.into()
+    );
+    lockup::apply_lock(&mut alice, &mut pool, 150.0, &tier, 10).unwrap();
+    assert_eq!((alice.prize_weight(&pool), bob.prize_weight(&pool), pool.prize_weight()), (300.0, 150.0, 450.0));
+    assert_eq!(math::prize_share(alice.prize_weight(&pool), pool.prize_weight(), 90.0), 60.0);
+    assert_eq!(math::prize_share(bob.prize_weight(&pool), pool.prize_weight(), 90.0), 30.0);
+
+    // once the prize accrues the locked amount is all of the balance, which has to stay until the release
+    accounting::apply_accrual(&mut alice, &mut pool);
+    assert_eq!((alice.balance, alice.prize_weight(&pool), pool.prize_weight()), (150.0, 300.0, 450.0));
+    accounting::apply_withdraw(&mut alice, &mut pool, 1.0).unwrap();
+    assert_eq!(alice.check_lock(9, 10).unwrap_err(), ErrorCode::DepositLocked.into());
+}
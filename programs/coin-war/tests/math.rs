@@ -0,0 +1,73 @@
+use coin_war::math::{self, WeightedBalance};
+use coin_war::GAME_DURATION_IN_DAYS;
+
+#[test]
+fn weighted_balance_for_a_full_game() {
+    let weighted = WeightedBalance::full_game(100.0);
+    assert_eq!(weighted.average_balance, 100.0);
+    assert_eq!(weighted.weighted_balance, 100.0 * GAME_DURATION_IN_DAYS as f64);
+    assert_eq!(weighted.weighted_days, GAME_DURATION_IN_DAYS);
+}
+
+#[test]
+fn deposits_are_weighted_by_days_left() {
+    // 100 held for the whole game, then 100 more for the last day
+    let weighted = WeightedBalance::full_game(100.0).add_deposit(100.0, 1);
+    assert_eq!(weighted.weighted_balance, 600.0);
+    assert_eq!(weighted.weighted_days, 6);
+    assert_eq!(weighted.average_balance, 100.0);
+
+    // a first deposit counts fully
+    let empty = WeightedBalance { average_balance: 0.0, weighted_balance: 0.0, weighted_days: 0 };
+    assert_eq!(empty.add_deposit(50.0, 4).average_balance, 50.0);
+}
+
+#[test]
+fn replace_prediction_keeps_the_mean() {
+    // first user joins
+    assert_eq!(math::replace_prediction(0.0, 0, 1, 0.0, 10.0), 10.0);
+    // second user joins
+    assert_eq!(math::replace_prediction(10.0, 1, 2, 0.0, 20.0), 15.0);
+    // first user changes their prediction
+    assert_eq!(math::replace_prediction(15.0, 2, 2, 10.0, 30.0), 25.0);
+    // second user leaves
+    assert_eq!(math::replace_prediction(25.0, 2, 1, 20.0, 0.0), 30.0);
+    // last user leaves
+    assert_eq!(math::replace_prediction(30.0, 1, 0, 30.0, 0.0), 0.0);
+}
+
+#[test]
+fn prize_is_split_by_share_of_the_pool() {
+    assert_eq!(math::prize_share(300.0, 1_000.0, 1_000.0), 300.0);
+    assert_eq!(math::prize_share(1_000.0, 1_000.0, 50.0), 50.0);
+    assert_eq!(math::prize_share(0.0, 1_000.0, 50.0), 0.0);
+    // shares are whole amounts and never add up to more than the prize
+    assert_eq!(math::prize_share(1.0, 3.0, 100.0), 33.0);
+    assert_eq!(math::prize_share(366.0, 4_212.0, 12_375.0) + math::prize_share(3_846.0, 4_212.0, 12_375.0), 12_374.0);
+    // nothing to split in an empty pool
+    assert_eq!(math::prize_share(0.0, 0.0, 50.0), 0.0);
+}
+
+#[test]
+fn closest_prediction_wins() {
+    let predictions = [30.0, 300.0, 1.0, 2_000.0];
+    assert_eq!(math::select_winning_pool(&predictions, &[35.0, 280.0, 0.9, 1_900.0]), 3);
+    assert_eq!(math::select_winning_pool(&predictions, &[30.0, 280.0, 0.5, 1_900.0]), 1);
+    // ties go to the first pool
+    assert_eq!(math::select_winning_pool(&[1.0, 1.0, 1.0, 1.0], &[2.0, 2.0, 2.0, 2.0]), 1);
+}
+
+#[test]
+fn amounts_convert_to_base_units_of_any_mint() {
+    // amounts are in AMOUNT_DECIMALS (6) units
+    assert_eq!(math::to_base_units(25.0, 6), 25);
+    assert_eq!(math::to_base_units(25.0, 9), 25_000);
+    assert_eq!(math::to_base_units(25_000.0, 3), 25);
+    assert_eq!(math::from_base_units(25_000, 9), 25.0);
+    assert_eq!(math::from_base_units(25, 3), 25_000.0);
+
+    // rounded down to whole base units
+    assert_eq!(math::to_base_units(25_999.0, 3), 25);
+    assert_eq!(math::to_base_units(1.5, 6), 1);
+    assert_eq!(math::to_base_units(-1.0, 6), 0);
+}
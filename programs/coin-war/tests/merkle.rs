@@ -0,0 +1,36 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::keccak;
+use coin_war::merkle;
+
+#[test]
+fn proofs_verify_whatever_the_order_of_the_pair() {
+    let claimants: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+    let leaves: Vec<[u8; 32]> =
+        (0..).zip(&claimants).map(|(index, claimant)| merkle::leaf(index, claimant, 100)).collect();
+    let left = merkle::node(&leaves[0], &leaves[1]);
+    let right = merkle::node(&leaves[2], &leaves[3]);
+    let root = merkle::node(&left, &right);
+    assert_eq!(merkle::node(&right, &left), root);
+
+    assert!(merkle::verify_proof(&[leaves[1], right], root, leaves[0]));
+    assert!(merkle::verify_proof(&[leaves[2], left], root, leaves[3]));
+    assert!(!merkle::verify_proof(&[leaves[1], right], root, merkle::leaf(0, &claimants[0], 101)));
+    assert!(!merkle::verify_proof(&[leaves[1], right], root, merkle::leaf(1, &claimants[0], 100)));
+}
+
+#[test]
+fn leaves_and_inner_nodes_are_hashed_apart() {
+    // the leaf prefix is 0 and the node prefix 1, so no leaf a claim can produce hashes the way an inner node does
+    let claimant = Pubkey::new_unique();
+    let leaf = merkle::leaf(7, &claimant, 100);
+    let expected = keccak::hashv(&[&[0], &7u64.to_le_bytes(), claimant.as_ref(), &100u64.to_le_bytes()]).0;
+    assert_eq!(leaf, expected);
+
+    let other = merkle::leaf(8, &claimant, 100);
+    let (first, second) = if leaf <= other { (leaf, other) } else { (other, leaf) };
+    assert_eq!(merkle::node(&leaf, &other), keccak::hashv(&[&[1], &first, &second]).0);
+    assert_ne!(merkle::node(&leaf, &other), keccak::hashv(&[&first, &second]).0);
+
+    // a one leaf tree has the leaf as its root
+    assert!(merkle::verify_proof(&[], leaf, leaf));
+}
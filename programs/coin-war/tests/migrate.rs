@@ -0,0 +1,189 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountSerialize, AnchorSerialize, Discriminator};
+use coin_war::migrate::{self, CONFIG_VERSION, GAME_VERSION, POOL_VERSION, TRANSACTION_VERSION, USER_VERSION};
+use coin_war::space::RESERVED;
+use coin_war::{AllowedMint, Config, ErrorCode};
+
+// Account data as created by earlier versions of the program, zero padding included. The name says which version
+// is stored and which field the layout ends with: baseline is the original layout, later ones appended up to the
+// named field. Every version before the current one has a fixture, see covers_every_version.
+fn fixture(hex: &str) -> Vec<u8> {
+    let hex = hex.trim();
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect()
+}
+
+fn serialize(account: &impl AccountSerialize) -> Vec<u8> {
+    let mut data = Vec::new();
+    account.try_serialize(&mut data).unwrap();
+    data
+}
+
+fn covers_every_version<T>(fixtures: &[(&str, u8, T)], current: u8) {
+    assert!((0..current).all(|version| fixtures.iter().any(|fixture| fixture.1 == version)));
+}
+
+#[test]
+fn users_from_every_layout() {
+    let fixtures = [
+        (include_str!("fixtures/user_v0_baseline.hex"), 0, 0),
+        (include_str!("fixtures/user_v0_last_paid_game_id.hex"), 0, 4),
+        (include_str!("fixtures/user_v1_version.hex"), 1, 4),
+        (include_str!("fixtures/user_v2_participant_slot.hex"), 2, 4),
+        (include_str!("fixtures/user_v3_mint.hex"), 3, 4),
+        (include_str!("fixtures/user_v4_shares.hex"), 4, 4),
+        (include_str!("fixtures/user_v5_delegated_balance.hex"), 5, 4),
+        (include_str!("fixtures/user_v6_withdrawal_release_time.hex"), 6, 4),
+        (include_str!("fixtures/user_v7_lock_release_time.hex"), 7, 4),
+        (include_str!("fixtures/user_v8_auto_compound.hex"), 8, 4),
+    ];
+    covers_every_version(&fixtures, USER_VERSION);
+
+    for (hex, version, last_paid_game_id) in fixtures {
+        let user = migrate::upgrade_user(&fixture(hex)).unwrap();
+        assert_eq!((user.pool, user.last_prediction, user.balance), (1, 31.5, 250.0));
+        assert_eq!((user.last_active, user.game_history_count), (1650000000, 3));
+        assert_eq!((user.current_average_balance, user.current_weighted_balance), (250.0, 1250.0));
+        assert_eq!((user.current_weighted_days, user.txn_count), (5, 2));
+        assert_eq!(user.version, USER_VERSION);
+
+        // fields of the stored layout are kept, later ones read as zero. No mint recorded takes any mint until the
+        // next deposit, no shares issued yet get them from issue_shares.
+        let since = |added: u8| version >= added;
+        assert_eq!(user.last_paid_game_id, last_paid_game_id);
+        assert_eq!(user.participant_slot, if since(2) { 3 } else { 0 });
+        assert_eq!(user.mint, if since(3) { Pubkey::new_from_array([0x11; 32]) } else { Pubkey::default() });
+        assert_eq!((user.share_rate(), user.shares), if since(4) { (1.25, 200) } else { (1.0, 0) });
+        assert_eq!(user.delegated_balance, if since(5) { 50.0 } else { 0.0 });
+        assert_eq!(
+            (user.pending_withdrawal, user.withdrawal_pool, user.withdrawal_release_time),
+            if since(6) { (20.0, 1, 1650432000) } else { (0.0, 0, 0) }
+        );
+        assert_eq!(
+            (user.locked_amount, user.lock_multiplier, user.lock_release_time),
+            if since(7) { (100.0, 1.5, 1651000000) } else { (0.0, 0.0, 0) }
+        );
+        assert_eq!(user.auto_compound, since(8));
+        assert_eq!((user.settled_weight, user.settled_game_id), (0.0, 0));
+    }
+}
+
+#[test]
+fn pools_from_every_layout() {
+    let fixtures = [
+        (include_str!("fixtures/pool_v0_baseline.hex"), 0, 0.0),
+        (include_str!("fixtures/pool_v0_invested.hex"), 0, 200.0),
+        (include_str!("fixtures/pool_v1_version.hex"), 1, 200.0),
+        (include_str!("fixtures/pool_v2_pool_data.hex"), 2, 200.0),
+        (include_str!("fixtures/pool_v3_pending_prize.hex"), 3, 200.0),
+        (include_str!("fixtures/pool_v4_pending_withdrawals.hex"), 4, 200.0),
+        (include_str!("fixtures/pool_v5_lock_boost.hex"), 5, 200.0),
+    ];
+    covers_every_version(&fixtures, POOL_VERSION);
+
+    for (hex, version, invested) in fixtures {
+        let pool = migrate::upgrade_pool(&fixture(hex)).unwrap();
+        assert!(pool.is_initialized);
+        assert_eq!((pool.last_update_timestamp, pool.total_deposit, pool.user_count), (1650000000, 1000.0, 4));
+        assert_eq!((pool.name, pool.average_prediction), (1, 31.5));
+        assert_eq!(pool.version, POOL_VERSION);
+
+        let since = |added: u8| version >= added;
+        assert_eq!(pool.invested, invested);
+        assert_eq!(pool.pool_data, if since(2) { Pubkey::new_from_array([0x22; 32]) } else { Pubkey::default() });
+        assert_eq!(pool.share_mint, if since(3) { Pubkey::new_from_array([0x33; 32]) } else { Pubkey::default() });
+        // a zero share_rate reads as one
+        assert_eq!((pool.exchange_rate(), pool.pending_prize), if since(3) { (1.25, 10.0) } else { (1.0, 0.0) });
+        assert_eq!(pool.pending_withdrawals, if since(4) { 20.0 } else { 0.0 });
+        assert_eq!(pool.lock_boost, if since(5) { 50.0 } else { 0.0 });
+        assert_eq!(pool.won_game_id, 0);
+    }
+}
+
+#[test]
+fn games_from_every_layout() {
+    let fixtures = [
+        (include_str!("fixtures/game_v0_baseline.hex"), 0, (0.0, 0.0, 0)),
+        (include_str!("fixtures/game_v0_total_paid.hex"), 0, (12.0, 0.0, 0)),
+        (include_str!("fixtures/game_v0_sponsorship.hex"), 0, (12.0, 10.0, 1)),
+        (include_str!("fixtures/game_v1_version.hex"), 1, (12.0, 10.0, 1)),
+        (include_str!("fixtures/game_v2_compounded_prize.hex"), 2, (12.0, 10.0, 1)),
+        (include_str!("fixtures/game_v3_winning_weight.hex"), 3, (12.0, 10.0, 1)),
+        (include_str!("fixtures/game_v4_harvested_mints.hex"), 4, (12.0, 10.0, 1)),
+    ];
+    covers_every_version(&fixtures, GAME_VERSION);
+
+    for (hex, version, sponsorship) in fixtures {
+        let game = migrate::upgrade_game(&fixture(hex)).unwrap();
+        assert_eq!((game.game_id, game.start_time, game.end_time), (7, 1650000000, 1650432000));
+        assert_eq!((game.winning_pool, game.winning_amount, game.total_prize), (2, 0.0, 40.0));
+        assert_eq!((game.total_paid, game.sponsored_prize, game.sponsor_count), sponsorship);
+        assert_eq!(game.compounded_prize, if version >= 2 { 5.0 } else { 0.0 });
+        assert_eq!(game.winning_weight, if version >= 3 { 900.0 } else { 0.0 });
+        assert_eq!(game.harvested_mints, if version >= 4 { 1 } else { 0 });
+        assert_eq!(game.prize_mint, Pubkey::default());
+        assert_eq!(game.version, GAME_VERSION);
+    }
+}
+
+#[test]
+fn transactions_from_every_layout() {
+    let fixtures = [
+        (include_str!("fixtures/transaction_v0_baseline.hex"), 0, ()),
+        (include_str!("fixtures/transaction_v1_version.hex"), 1, ()),
+    ];
+    covers_every_version(&fixtures, TRANSACTION_VERSION);
+
+    for (hex, _, ()) in fixtures {
+        let transaction = migrate::upgrade_transaction(&fixture(hex)).unwrap();
+        assert_eq!((transaction.timestamp, transaction.amount, transaction.transaction_type), (1650000000, 50.0, 2));
+        assert_eq!(transaction.donor, Pubkey::default());
+        assert_eq!(transaction.version, TRANSACTION_VERSION);
+    }
+}
+
+#[test]
+fn configs_from_before_the_mint_allow_list() {
+    let admin = Pubkey::new_unique();
+    let mut data = Config::discriminator().to_vec();
+    data.extend_from_slice(admin.as_ref());
+    data.push(1);
+    data.resize(data.len() + RESERVED, 0);
+
+    let config = migrate::upgrade_config(&data).unwrap();
+    assert_eq!((config.admin, config.paused), (admin, true));
+    assert!(config.mints.is_empty());
+    assert_eq!(config.version, CONFIG_VERSION);
+}
+
+#[test]
+fn configs_from_before_the_lock_tiers() {
+    let mint = AllowedMint { mint: Pubkey::new_unique(), decimals: 6, minimum_deposit: 1, deposits: 10, invested: 0 };
+    let mut data = Config::discriminator().to_vec();
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+    data.push(0);
+    data.extend(vec![mint].try_to_vec().unwrap());
+    data.resize(data.len() + RESERVED, 0);
+
+    let config = migrate::upgrade_config(&data).unwrap();
+    assert_eq!(config.mints.len(), 1);
+    assert!(config.lock_tiers.is_empty());
+    assert_eq!(config.version, CONFIG_VERSION);
+}
+
+#[test]
+fn upgrades_are_idempotent() {
+    let pool = migrate::upgrade_pool(&fixture(include_str!("fixtures/pool_v0_baseline.hex"))).unwrap();
+    let data = serialize(&pool);
+    assert_eq!(serialize(&migrate::upgrade_pool(&data).unwrap()), data);
+}
+
+#[test]
+fn newer_versions_are_rejected() {
+    let mut user = migrate::upgrade_user(&fixture(include_str!("fixtures/user_v0_baseline.hex"))).unwrap();
+    user.version = USER_VERSION + 1;
+    let error = migrate::upgrade_user(&serialize(&user)).err().unwrap();
+    assert_eq!(error, ErrorCode::UnsupportedAccountVersion.into());
+
+    // and so is data of another account type
+    assert!(migrate::upgrade_user(&fixture(include_str!("fixtures/pool_v0_invested.hex"))).is_err());
+}
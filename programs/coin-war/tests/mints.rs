@@ -0,0 +1,131 @@
+use anchor_lang::prelude::Pubkey;
+use coin_war::{AllowedMint, Config, ErrorCode, Game, MAX_MINTS};
+
+fn config() -> Config {
+    Config {
+        admin: Pubkey::new_unique(),
+        paused: false,
+        mints: Vec::new(),
+        lock_tiers: Vec::new(),
+        version: 0,
+        settled_until: 0,
+    }
+}
+
+fn game() -> Game {
+    Game {
+        game_id: 1,
+        start_time: 0,
+        end_time: 0,
+        winning_pool: 0,
+        winning_amount: 0.0,
+        total_prize: 0.0,
+        total_paid: 0.0,
+        sponsored_prize: 0.0,
+        sponsor_count: 0,
+        version: 5,
+        compounded_prize: 0.0,
+        winning_weight: 0.0,
+        harvested_mints: 0,
+        prize_mint: Pubkey::default(),
+    }
+}
+
+fn allowed_mint(decimals: u8, minimum_deposit: u64) -> AllowedMint {
+    AllowedMint { mint: Pubkey::new_unique(), decimals, minimum_deposit, deposits: 0, invested: 0 }
+}
+
+#[test]
+fn only_allowed_mints_are_found() {
+    let mut config = config();
+    let usdc = Pubkey::new_unique();
+    config.add_mint(usdc, 6, 1_000_000).unwrap();
+
+    assert_eq!(config.allowed_mint(&usdc).unwrap().decimals, 6);
+    config.allowed_mint_mut(&usdc).unwrap().minimum_deposit = 5;
+    assert!(config.allowed_mint(&usdc).unwrap().minimum_deposit == 5);
+    let error = config.allowed_mint(&Pubkey::new_unique()).err().unwrap();
+    assert_eq!(error, ErrorCode::MintNotAllowed.into());
+}
+
+#[test]
+fn mints_are_added_once_and_up_to_the_limit() {
+    let mut config = config();
+    let usdc = Pubkey::new_unique();
+    config.add_mint(usdc, 6, 1).unwrap();
+    assert_eq!(config.add_mint(usdc, 6, 1).unwrap_err(), ErrorCode::MintAlreadyAllowed.into());
+
+    for _ in 1..MAX_MINTS {
+        config.add_mint(Pubkey::new_unique(), 9, 1).unwrap();
+    }
+    assert_eq!(config.add_mint(Pubkey::new_unique(), 9, 1).unwrap_err(), ErrorCode::TooManyMints.into());
+}
+
+#[test]
+fn deposits_have_to_reach_the_minimum_of_their_mint() {
+    // 1 token of a 9 decimal mint
+    let sol = allowed_mint(9, 1_000_000_000);
+    assert_eq!(sol.deposit_units(1_000_000.0).unwrap(), 1_000_000_000);
+    assert_eq!(sol.deposit_units(999_999.0).unwrap_err(), ErrorCode::DepositInsufficient.into());
+
+    // 2 tokens of a 6 decimal mint
+    let usdc = allowed_mint(6, 2_000_000);
+    assert_eq!(usdc.deposit_units(2_000_000.0).unwrap(), 2_000_000);
+    assert_eq!(usdc.deposit_units(1_000_000.0).unwrap_err(), ErrorCode::DepositInsufficient.into());
+}
+
+#[test]
+fn deposits_and_withdrawals_move_whole_base_units() {
+    // a 2 decimal mint has no base unit for amounts below 10^4
+    let cents = allowed_mint(2, 0);
+    assert_eq!(cents.to_base_units(30_000.0).unwrap(), 3);
+    for amount in [0.0, 5_000.0, 35_000.0, f64::NAN, 1e30] {
+        assert_eq!(cents.to_base_units(amount).unwrap_err(), ErrorCode::InvalidAmount.into());
+    }
+
+    // payouts are rounded down instead
+    assert_eq!(cents.payout_units(35_000.0), 3);
+}
+
+#[test]
+fn received_deposits_are_credited_in_whole_amounts() {
+    // a 9 decimal mint after a transfer fee, the last 999 base units don't make up an amount
+    let sol = allowed_mint(9, 1);
+    assert_eq!(sol.received(2_999_999_999), (2_999_999.0, 2_999_999_000));
+    assert_eq!(sol.received(999), (0.0, 0));
+    assert_eq!(allowed_mint(6, 1).received(9_801), (9_801.0, 9_801));
+}
+
+#[test]
+fn treasury_is_what_depositors_are_not_owed() {
+    let mut usdc = allowed_mint(6, 1);
+    usdc.deposits = 1_000;
+    usdc.invested = 400;
+    // 600 deposits left in the pool wallet plus 50 prize
+    assert_eq!(usdc.treasury(650), 50);
+    // never negative while funds are still lent out
+    assert_eq!(usdc.treasury(500), 0);
+}
+
+#[test]
+fn prizes_are_paid_out_of_the_treasury() {
+    let mut usdc = allowed_mint(6, 1);
+    usdc.deposits = 1_000_000;
+    assert_eq!(usdc.prize_units(500_000.0, 1_500_000), Ok(500_000));
+    assert_eq!(usdc.prize_units(500_000.0, 1_400_000).unwrap_err(), ErrorCode::InsufficientBalance.into());
+}
+
+#[test]
+fn prizes_are_held_in_the_mint_they_were_first_funded_in() {
+    let (usdc, usdt) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut game = game();
+    // games that were never funded can be paid in any mint
+    assert!(game.check_prize_mint(&usdt).is_ok());
+
+    game.add_prize(&usdc, 10.0).unwrap();
+    game.add_prize(&usdc, 5.0).unwrap();
+    assert_eq!((game.prize_mint, game.total_prize), (usdc, 15.0));
+    assert_eq!(game.add_prize(&usdt, 1.0).unwrap_err(), ErrorCode::PrizeMintMismatch.into());
+    assert_eq!(game.check_prize_mint(&usdt).unwrap_err(), ErrorCode::PrizeMintMismatch.into());
+    assert_eq!(game.total_prize, 15.0);
+}
@@ -0,0 +1,90 @@
+use anchor_lang::prelude::Pubkey;
+use coin_war::{ErrorCode, PoolData, PoolSnapshot, MAX_PARTICIPANTS, SNAPSHOT_COUNT};
+
+fn empty_pool_data() -> Box<PoolData> {
+    Box::new(bytemuck::Zeroable::zeroed())
+}
+
+fn snapshot(game_id: u64) -> PoolSnapshot {
+    PoolSnapshot { game_id, total_deposit: 100.0 * game_id as f64, user_count: game_id, average_prediction: 30.0 }
+}
+
+#[test]
+fn freed_slots_are_reused_last_in_first_out() {
+    let mut pool_data = empty_pool_data();
+    let wallets: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+    for (slot, wallet) in (1..).zip(&wallets) {
+        assert_eq!(pool_data.add_participant(*wallet).unwrap(), slot);
+    }
+
+    pool_data.remove_participant(2, wallets[1]).unwrap();
+    pool_data.remove_participant(4, wallets[3]).unwrap();
+    assert_eq!((pool_data.participant_count, pool_data.slots_used, pool_data.free_slot_count), (2, 4, 2));
+    assert_eq!(pool_data.participants[1], Pubkey::default());
+
+    let newcomer = Pubkey::new_unique();
+    assert_eq!(pool_data.add_participant(newcomer).unwrap(), 4);
+    assert_eq!(pool_data.add_participant(newcomer).unwrap(), 2);
+    assert_eq!(pool_data.add_participant(newcomer).unwrap(), 5);
+    assert_eq!((pool_data.participant_count, pool_data.slots_used, pool_data.free_slot_count), (5, 5, 0));
+}
+
+#[test]
+fn participants_are_only_removed_from_their_own_slot() {
+    let mut pool_data = empty_pool_data();
+    let alice = Pubkey::new_unique();
+    let bob = Pubkey::new_unique();
+    pool_data.add_participant(alice).unwrap();
+    pool_data.add_participant(bob).unwrap();
+
+    for (slot, wallet) in [(0, alice), (1, bob), (3, bob)] {
+        assert_eq!(pool_data.remove_participant(slot, wallet).unwrap_err(), ErrorCode::InvalidPoolData.into());
+    }
+    assert!(pool_data.participant_count == 2);
+}
+
+#[test]
+fn full_pools_take_no_more_participants() {
+    let mut pool_data = empty_pool_data();
+    for _ in 0..MAX_PARTICIPANTS {
+        pool_data.add_participant(Pubkey::new_unique()).unwrap();
+    }
+    assert_eq!(pool_data.add_participant(Pubkey::new_unique()), None);
+    assert_eq!(pool_data.participant_count as usize, MAX_PARTICIPANTS);
+
+    // until someone leaves
+    let wallet = pool_data.participants[9];
+    pool_data.remove_participant(10, wallet).unwrap();
+    assert_eq!(pool_data.add_participant(Pubkey::new_unique()).unwrap(), 10);
+}
+
+#[test]
+fn snapshots_keep_the_latest_games() {
+    let mut pool_data = empty_pool_data();
+    assert!(pool_data.latest_snapshot().is_none());
+
+    let games = SNAPSHOT_COUNT as u64 + 10;
+    for game_id in 1..=games {
+        pool_data.record_snapshot(snapshot(game_id)).unwrap();
+        let latest = pool_data.latest_snapshot().unwrap();
+        assert_eq!((latest.game_id, latest.user_count), (game_id, game_id));
+    }
+
+    // the oldest games were overwritten
+    let mut game_ids: Vec<u64> = pool_data.snapshots.iter().map(|snapshot| snapshot.game_id).collect();
+    game_ids.sort_unstable();
+    assert_eq!(game_ids, (11..=games).collect::<Vec<u64>>());
+}
+
+#[test]
+fn games_are_snapshotted_in_order_and_once() {
+    let mut pool_data = empty_pool_data();
+    pool_data.record_snapshot(snapshot(3)).unwrap();
+    for game_id in [3, 2] {
+        assert_eq!(pool_data.record_snapshot(snapshot(game_id)).unwrap_err(), ErrorCode::SnapshotExists.into());
+    }
+    // games without a snapshot can be skipped
+    pool_data.record_snapshot(snapshot(5)).unwrap();
+    let latest = pool_data.latest_snapshot().unwrap();
+    assert_eq!((latest.game_id, latest.total_deposit), (5, 500.0));
+}
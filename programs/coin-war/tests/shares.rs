@@ -0,0 +1,108 @@
+use anchor_lang::prelude::Pubkey;
+use coin_war::shares::{shares_for_deposit, shares_for_withdrawal};
+use coin_war::{accounting, Delegation, ErrorCode, Pool, User};
+
+fn new_user() -> User {
+    User {
+        pool: 0,
+        last_prediction: 0.0,
+        balance: 0.0,
+        last_active: 0,
+        game_history_count: 0,
+        current_average_balance: 0.0,
+        current_weighted_balance: 0.0,
+        current_weighted_days: 0,
+        txn_count: 0,
+        last_paid_game_id: 0,
+        version: 1,
+        participant_slot: 0,
+        mint: Pubkey::default(),
+        share_rate: 0.0,
+        shares: 0,
+        delegated_balance: 0.0,
+        pending_withdrawal: 0.0,
+        withdrawal_pool: 0,
+        withdrawal_release_time: 0,
+        locked_amount: 0.0,
+        lock_multiplier: 0.0,
+        lock_release_time: 0,
+        auto_compound: false,
+        settled_weight: 0.0,
+        settled_game_id: 0,
+    }
+}
+
+fn new_pool() -> Pool {
+    Pool {
+        is_initialized: true,
+        last_update_timestamp: 0,
+        total_deposit: 0.0,
+        user_count: 0,
+        name: 1,
+        average_prediction: 0.0,
+        invested: 0.0,
+        version: 1,
+        pool_data: Pubkey::default(),
+        share_mint: Pubkey::default(),
+        share_rate: 0.0,
+        pending_prize: 0.0,
+        pending_withdrawals: 0.0,
+        lock_boost: 0.0,
+        won_game_id: 0,
+    }
+}
+
+#[test]
+fn shares_are_rounded_in_favour_of_the_pool() {
+    assert_eq!(shares_for_deposit(100.0, 1.0), 100);
+    assert_eq!(shares_for_deposit(100.0, 1.5), 66);
+    assert_eq!(shares_for_withdrawal(100.0, 50.0, 1.5, 200), 67);
+    // never more than were issued, and all of them for the last of the balance
+    assert_eq!(shares_for_withdrawal(100.0, 50.0, 1.5, 20), 20);
+    assert_eq!(shares_for_withdrawal(10.0, 0.0, 1.5, 200), 200);
+}
+
+#[test]
+fn distributed_prize_accrues_by_share_of_the_pool() {
+    let mut pool = new_pool();
+    let mut alice = new_user();
+    let mut bob = new_user();
+    accounting::apply_deposit(&mut alice, &mut pool, 300.0, 30.0).unwrap();
+    accounting::apply_deposit(&mut bob, &mut pool, 100.0, 30.0).unwrap();
+
+    pool.distribute(200.0).unwrap();
+    assert_eq!((pool.exchange_rate(), pool.underlying()), (1.5, 600.0));
+    assert_eq!((alice.balance_at_rate(&pool), bob.balance_at_rate(&pool)), (450.0, 150.0));
+
+    // balances catch up one at a time, the deposits only grow by what was accrued
+    assert_eq!(accounting::apply_accrual(&mut alice, &mut pool), 150.0);
+    assert_eq!((pool.total_deposit, pool.pending_prize), (550.0, 50.0));
+    assert_eq!(accounting::apply_accrual(&mut bob, &mut pool), 50.0);
+    assert_eq!((pool.total_deposit, pool.pending_prize), (600.0, 0.0));
+    assert_eq!(accounting::apply_accrual(&mut alice, &mut pool), 0.0);
+    assert_eq!(alice.balance_at_rate(&pool), 450.0);
+}
+
+#[test]
+fn prize_needs_deposits_to_be_distributed_to() {
+    assert_eq!(new_pool().distribute(100.0).unwrap_err(), ErrorCode::InvalidPrizeAmount.into());
+}
+
+#[test]
+fn delegated_deposits_are_kept_apart_from_the_users_own() {
+    let mut pool = new_pool();
+    let mut user = new_user();
+    let mut delegation =
+        Delegation { user: Pubkey::new_unique(), delegate: Pubkey::new_unique(), deposited: 0.0, shares: 0 };
+    accounting::apply_deposit(&mut user, &mut pool, 100.0, 30.0).unwrap();
+    user.shares += shares_for_deposit(100.0, pool.exchange_rate());
+    accounting::apply_deposit(&mut user, &mut pool, 50.0, 30.0).unwrap();
+    delegation.add_deposit(&mut user, 50.0, shares_for_deposit(50.0, pool.exchange_rate()));
+    assert_eq!((user.balance, user.own_balance(), user.shares, delegation.shares), (150.0, 100.0, 100, 50));
+    assert_eq!(user.unissued_shares(), 0);
+
+    assert_eq!(delegation.take_withdrawal(&mut user, 60.0, 1.0).unwrap_err(), ErrorCode::DelegationExceeded.into());
+    accounting::apply_withdraw(&mut user, &mut pool, 50.0).unwrap();
+    assert_eq!(delegation.take_withdrawal(&mut user, 50.0, 1.0).unwrap(), 50);
+    assert_eq!((user.balance, user.own_balance(), delegation.deposited, delegation.shares), (100.0, 100.0, 0.0, 0));
+}
@@ -0,0 +1,130 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountSerialize;
+use coin_war::space::RESERVED;
+use coin_war::{
+    AllowedMint, Config, Delegation, Distributor, Game, LockTier, Pool, PoolData, Sponsorship, Transaction, User,
+    MAX_LOCK_TIERS, MAX_MINTS,
+};
+
+fn serialized_len(account: &impl AccountSerialize) -> usize {
+    let mut data = Vec::new();
+    account.try_serialize(&mut data).unwrap();
+    data.len()
+}
+
+#[test]
+fn accounts_fit_their_space_exactly() {
+    let user = User {
+        pool: 1,
+        last_prediction: 30.0,
+        balance: 100.0,
+        last_active: 1,
+        game_history_count: 1,
+        current_average_balance: 100.0,
+        current_weighted_balance: 500.0,
+        current_weighted_days: 5,
+        txn_count: 1,
+        last_paid_game_id: 1,
+        version: 1,
+        participant_slot: 1,
+        mint: Pubkey::new_unique(),
+        share_rate: 1.5,
+        shares: u64::MAX,
+        delegated_balance: 10.0,
+        pending_withdrawal: f64::MAX,
+        withdrawal_pool: u8::MAX,
+        withdrawal_release_time: i64::MAX,
+        locked_amount: f64::MAX,
+        lock_multiplier: 1.5,
+        lock_release_time: i64::MAX,
+        auto_compound: true,
+        settled_weight: 150.0,
+        settled_game_id: u64::MAX,
+    };
+    assert_eq!(serialized_len(&user) + RESERVED, User::LEN);
+
+    let pool = Pool {
+        is_initialized: true,
+        last_update_timestamp: 1,
+        total_deposit: 100.0,
+        user_count: 1,
+        name: 1,
+        average_prediction: 30.0,
+        invested: 0.0,
+        version: 1,
+        pool_data: Pubkey::new_unique(),
+        share_mint: Pubkey::new_unique(),
+        share_rate: 1.5,
+        pending_prize: f64::MAX,
+        pending_withdrawals: f64::MAX,
+        lock_boost: f64::MAX,
+        won_game_id: u64::MAX,
+    };
+    assert_eq!(serialized_len(&pool) + RESERVED, Pool::LEN);
+
+    let game = Game {
+        game_id: 1,
+        start_time: 1,
+        end_time: 2,
+        winning_pool: 1,
+        winning_amount: 0.0,
+        total_prize: 10.0,
+        total_paid: 0.0,
+        sponsored_prize: 0.0,
+        sponsor_count: 0,
+        version: 1,
+        compounded_prize: 0.0,
+        winning_weight: f64::MAX,
+        harvested_mints: u8::MAX,
+        prize_mint: Pubkey::new_unique(),
+    };
+    assert_eq!(serialized_len(&game) + RESERVED, Game::LEN);
+
+    let mint = AllowedMint { mint: Pubkey::new_unique(), decimals: 6, minimum_deposit: 1, deposits: 10, invested: 0 };
+    let tier = LockTier { games: 4, multiplier: 1.5 };
+    let config = Config {
+        admin: Pubkey::new_unique(),
+        paused: false,
+        mints: vec![mint; MAX_MINTS],
+        lock_tiers: vec![tier; MAX_LOCK_TIERS],
+        version: 2,
+        settled_until: 1,
+    };
+    assert_eq!(serialized_len(&config) + RESERVED, Config::LEN);
+
+    // records are written once and get no reserve
+    let transaction =
+        Transaction { timestamp: 1, amount: 10.0, transaction_type: 3, version: 1, donor: Pubkey::new_unique() };
+    assert_eq!(serialized_len(&transaction), Transaction::LEN);
+
+    let sponsorship =
+        Sponsorship { game_id: 1, sponsor: Pubkey::new_unique(), amount: 10, timestamp: 1, mint: Pubkey::new_unique() };
+    assert_eq!(serialized_len(&sponsorship), Sponsorship::LEN);
+
+    let delegation =
+        Delegation { user: Pubkey::new_unique(), delegate: Pubkey::new_unique(), deposited: 10.0, shares: u64::MAX };
+    assert_eq!(serialized_len(&delegation) + RESERVED, Delegation::LEN);
+}
+
+#[test]
+fn pool_data_is_laid_out_without_gaps() {
+    // zero-copy accounts are stored as laid out in memory, so padding would end up on chain
+    let fields = 64 * 32 + 4096 * 32 + 4096 * 4 + 8 + 4 + 4 + 4 + 1 + 3;
+    assert_eq!(PoolData::LEN, 8 + fields);
+}
+
+#[test]
+fn distributor_space_grows_with_the_bitmap() {
+    for max_num_nodes in [0, 1, 8, 9, 1_000] {
+        let distributor = Distributor {
+            game_id: 1,
+            merkle_root: [0; 32],
+            max_total_claim: 100,
+            max_num_nodes,
+            total_amount_claimed: 0,
+            num_nodes_claimed: 0,
+            claimed_bitmap: vec![0; Distributor::bitmap_len(max_num_nodes)],
+        };
+        assert_eq!(serialized_len(&distributor), Distributor::space(max_num_nodes));
+    }
+}
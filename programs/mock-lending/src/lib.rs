@@ -0,0 +1,211 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+declare_id!("5yJZYvoRacdNNKvYxwtadX2B1qgzRb7Ut6uasSgWTWTE");
+
+/* Mock lending program - a stand in for a real lending market so coin-war can be tested with an actual yield
+ * source. Deposits sit in a reserve vault and earn a flat daily rate for as long as they are deposited, harvest pays
+ * out what was earned since the last harvest from whatever extra liquidity has been sent to the vault.
+ */
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+#[program]
+pub mod mock_lending {
+    use super::*;
+
+    // Create the reserve for a mint. Deposits earn rate_bps of their amount per day.
+    pub fn init_reserve(ctx: Context<InitReserve>, rate_bps: u16) -> Result<()> {
+        let reserve = &mut ctx.accounts.reserve;
+        reserve.mint = ctx.accounts.mint.key();
+        reserve.rate_bps = rate_bps;
+        reserve.total_deposits = 0;
+
+        Ok(())
+    }
+
+    pub fn init_obligation(ctx: Context<InitObligation>) -> Result<()> {
+        let obligation = &mut ctx.accounts.obligation;
+        obligation.reserve = ctx.accounts.reserve.key();
+        obligation.owner = ctx.accounts.owner.key();
+        obligation.deposited = 0;
+        obligation.accrued_interest = 0;
+        obligation.last_accrual = Clock::get()?.unix_timestamp;
+
+        Ok(())
+    }
+
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.source.to_account_info(),
+            to: ctx.accounts.reserve_vault.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        };
+        token::transfer(CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts), amount)?;
+
+        ctx.accounts.obligation.accrue(ctx.accounts.reserve.rate_bps)?;
+        ctx.accounts.obligation.deposited += amount;
+        ctx.accounts.reserve.total_deposits += amount;
+
+        Ok(())
+    }
+
+    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        require!(ctx.accounts.obligation.deposited >= amount, ErrorCode::InsufficientDeposit);
+        transfer_out_of_reserve(ctx.accounts, *ctx.bumps.get("reserve").unwrap(), amount)?;
+
+        ctx.accounts.obligation.accrue(ctx.accounts.reserve.rate_bps)?;
+        ctx.accounts.obligation.deposited -= amount;
+        ctx.accounts.reserve.total_deposits -= amount;
+
+        Ok(())
+    }
+
+    // Pay out the interest the obligation earned since it was last harvested. The principal stays deposited.
+    pub fn harvest(ctx: Context<Withdraw>) -> Result<()> {
+        ctx.accounts.obligation.accrue(ctx.accounts.reserve.rate_bps)?;
+        let interest = ctx.accounts.obligation.accrued_interest;
+        let available = ctx.accounts.reserve_vault.amount - ctx.accounts.reserve.total_deposits;
+        require!(interest <= available, ErrorCode::InsufficientLiquidity);
+        transfer_out_of_reserve(ctx.accounts, *ctx.bumps.get("reserve").unwrap(), interest)?;
+        ctx.accounts.obligation.accrued_interest = 0;
+
+        Ok(())
+    }
+}
+
+impl Obligation {
+    // Add the interest earned on the deposited amount since the last accrual, before it changes or is paid out
+    fn accrue(&mut self, rate_bps: u16) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let elapsed = (now - self.last_accrual).max(0) as u128;
+        let interest = self.deposited as u128 * rate_bps as u128 * elapsed / (10_000 * SECONDS_PER_DAY as u128);
+        self.accrued_interest += interest as u64;
+        self.last_accrual = now;
+        Ok(())
+    }
+}
+
+// The reserve PDA owns the vault, so transfers out of it are signed with the reserve seeds
+fn transfer_out_of_reserve(accounts: &Withdraw, bump: u8, amount: u64) -> Result<()> {
+    let mint = accounts.reserve.mint;
+    let inner = vec![b"reserve".as_ref(), mint.as_ref(), std::slice::from_ref(&bump)];
+    let outer = vec![inner.as_slice()];
+    let cpi_accounts = Transfer {
+        from: accounts.reserve_vault.to_account_info(),
+        to: accounts.destination.to_account_info(),
+        authority: accounts.reserve.to_account_info(),
+    };
+    token::transfer(
+        CpiContext::new_with_signer(accounts.token_program.to_account_info(), cpi_accounts, outer.as_slice()),
+        amount,
+    )
+}
+
+#[derive(Accounts)]
+pub struct InitReserve<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(init, payer = payer, space = Reserve::LEN, seeds = [b"reserve".as_ref(), mint.key().as_ref()], bump)]
+    pub reserve: Account<'info, Reserve>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"reserve_vault".as_ref(), mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = reserve,
+    )]
+    pub reserve_vault: Account<'info, TokenAccount>,
+    pub mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct InitObligation<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: any account (usually a PDA of the depositing program) can own an obligation
+    pub owner: UncheckedAccount<'info>,
+    pub reserve: Account<'info, Reserve>,
+    #[account(
+        init,
+        payer = payer,
+        space = Obligation::LEN,
+        seeds = [b"obligation".as_ref(), reserve.key().as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub obligation: Account<'info, Obligation>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    pub owner: Signer<'info>,
+    #[account(mut, seeds = [b"reserve".as_ref(), reserve.mint.as_ref()], bump)]
+    pub reserve: Account<'info, Reserve>,
+    #[account(mut, seeds = [b"reserve_vault".as_ref(), reserve.mint.as_ref()], bump)]
+    pub reserve_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"obligation".as_ref(), reserve.key().as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub obligation: Account<'info, Obligation>,
+    #[account(mut, constraint = source.mint == reserve.mint)]
+    pub source: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    pub owner: Signer<'info>,
+    #[account(mut, seeds = [b"reserve".as_ref(), reserve.mint.as_ref()], bump)]
+    pub reserve: Account<'info, Reserve>,
+    #[account(mut, seeds = [b"reserve_vault".as_ref(), reserve.mint.as_ref()], bump)]
+    pub reserve_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"obligation".as_ref(), reserve.key().as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub obligation: Account<'info, Obligation>,
+    #[account(mut, constraint = destination.mint == reserve.mint)]
+    pub destination: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[account]
+pub struct Reserve {
+    pub mint: Pubkey,
+    pub rate_bps: u16,
+    pub total_deposits: u64,
+}
+
+#[account]
+pub struct Obligation {
+    pub reserve: Pubkey,
+    pub owner: Pubkey,
+    pub deposited: u64,
+    // earned and not harvested yet, up to last_accrual
+    pub accrued_interest: u64,
+    pub last_accrual: i64,
+}
+
+impl Reserve {
+    pub const LEN: usize = 8 + 32 + 2 + 8;
+}
+
+impl Obligation {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8;
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Obligation does not have enough deposited.")]
+    InsufficientDeposit,
+    #[msg("Reserve does not have enough liquidity to pay interest.")]
+    InsufficientLiquidity,
+}
@@ -0,0 +1,247 @@
+use coin_war::{accounting, math, Pool, User, AMOUNT_DECIMALS, GAME_DURATION_IN_DAYS};
+use rand::Rng;
+
+use crate::prices::{normal, DailyPrices};
+
+/* The game as played over a price series. Each game runs for game_duration_days days of prices. The interest the
+ * deposits earn over the game is split between the treasury, the prize kept in the pools for the next game and the
+ * prize of this game. One lucky winner of the winning pool gets the jackpot and the rest of the prize is split by
+ * prize weight like pay_winning_pool_user does. Players and pools are User and Pool accounts kept by the program's
+ * own accounting module.
+ */
+
+pub const POOL_COUNT: usize = 4;
+// Amounts are in AMOUNT_DECIMALS units like in the program, this many make a token
+pub const TOKEN: f64 = 10u64.pow(AMOUNT_DECIMALS as u32) as f64;
+// The program sets the minimum per mint, this is a typical one
+pub const MINIMUM_DEPOSIT: f64 = TOKEN;
+
+#[derive(Clone, Debug)]
+pub struct Params {
+    pub game_duration_days: i64,
+    pub minimum_deposit: f64,
+    // percent of the interest, after the treasury's part, that stays in the pools as prize for the next game
+    pub retention_percent: f64,
+    // percent of the prize going to one lucky winner in the winning pool
+    pub jackpot_percent: f64,
+    // percent of the interest kept by the treasury
+    pub treasury_percent: f64,
+    // yearly interest the yield source pays on all deposits
+    pub apy: f64,
+}
+
+impl Default for Params {
+    // what the program does today
+    fn default() -> Params {
+        Params {
+            game_duration_days: GAME_DURATION_IN_DAYS,
+            minimum_deposit: MINIMUM_DEPOSIT,
+            retention_percent: 20.0,
+            jackpot_percent: 5.0,
+            treasury_percent: 0.0,
+            apy: 5.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Behaviour {
+    pub players: usize,
+    // each deposit is drawn uniformly between these
+    pub deposit_min: f64,
+    pub deposit_max: f64,
+    // chance a player with a balance deposits more in a game. Players without a balance always deposit.
+    pub deposit_probability: f64,
+    // standard deviation of predictions, relative to the coin price at the start of the game
+    pub prediction_noise: f64,
+    // chance a player withdraws everything during a game, forfeiting that game's prize
+    pub withdraw_probability: f64,
+}
+
+impl Default for Behaviour {
+    fn default() -> Behaviour {
+        Behaviour {
+            players: 100,
+            deposit_min: 10.0 * TOKEN,
+            deposit_max: 1_000.0 * TOKEN,
+            deposit_probability: 0.2,
+            prediction_noise: 0.05,
+            withdraw_probability: 0.05,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PlayerResult {
+    // pool of the player at the end, 0 if the player left
+    pub pool: u8,
+    pub deposited: f64,
+    pub withdrawn: f64,
+    pub winnings: f64,
+    pub games_won: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct GameResult {
+    pub winning_pool: u8,
+    pub interest: f64,
+    pub treasury: f64,
+    // prize paid out this game, jackpot included
+    pub prize: f64,
+    pub jackpot: f64,
+    // prize kept for the next game
+    pub retained: f64,
+    pub pool_deposits: [f64; POOL_COUNT],
+}
+
+#[derive(Clone, Debug)]
+pub struct Outcome {
+    pub params: Params,
+    pub games: Vec<GameResult>,
+    pub players: Vec<PlayerResult>,
+    // prize still held by the pools after the last game
+    pub carried_prize: f64,
+}
+
+struct Player {
+    user: User,
+    result: PlayerResult,
+}
+
+fn pool_index(pool: u8) -> usize {
+    pool as usize - 1
+}
+
+fn new_pool(name: u8) -> Pool {
+    let mut pool = Pool::default();
+    coin_war::init_pool(&mut pool, name, 0);
+    pool
+}
+
+fn new_user() -> User {
+    let mut user = User::default();
+    coin_war::init_user(&mut user);
+    user
+}
+
+// Players only deposit whole amounts with finite predictions in a pool they are allowed in, so the accounting never
+// rejects them
+impl Player {
+    fn new() -> Player {
+        Player { user: new_user(), result: PlayerResult::default() }
+    }
+
+    fn in_game(&self) -> bool {
+        self.user.balance > 0.0
+    }
+
+    fn deposit(&mut self, pools: &mut [Pool; POOL_COUNT], pool: u8, amount: f64, prediction: f64) {
+        let pool = &mut pools[pool_index(pool)];
+        let amount = accounting::apply_deposit(&mut self.user, pool, amount, prediction).expect("deposit");
+        self.result.deposited += amount;
+    }
+
+    fn predict(&mut self, pools: &mut [Pool; POOL_COUNT], prediction: f64) {
+        let pool = &mut pools[pool_index(self.user.pool)];
+        accounting::apply_prediction(&mut self.user, pool, prediction).expect("prediction");
+    }
+
+    fn withdraw_all(&mut self, pools: &mut [Pool; POOL_COUNT]) {
+        let pool = &mut pools[pool_index(self.user.pool)];
+        let balance = self.user.balance;
+        self.result.withdrawn += accounting::apply_withdraw(&mut self.user, pool, balance).expect("withdrawal");
+    }
+}
+
+// Play as many whole games as the price series covers
+pub fn simulate(params: &Params, behaviour: &Behaviour, prices: &[DailyPrices], rng: &mut impl Rng) -> Outcome {
+    let days = params.game_duration_days.max(1) as usize;
+    let game_count = prices.len().saturating_sub(1) / days;
+    let mut pools: [Pool; POOL_COUNT] = std::array::from_fn(|i| new_pool(i as u8 + 1));
+    let mut players: Vec<Player> = (0..behaviour.players).map(|_| Player::new()).collect();
+    let mut games = Vec::new();
+    let mut carried_prize = 0.0;
+
+    for game in 0..game_count {
+        let start_prices = prices[game * days];
+        let end_prices = prices[(game + 1) * days];
+
+        // New money and predictions come in
+        for player in players.iter_mut() {
+            let deposits = !player.in_game() || rng.gen_bool(behaviour.deposit_probability);
+            let pool = if player.in_game() { player.user.pool } else { rng.gen_range(1..=POOL_COUNT as u8) };
+            let start_price = start_prices[pool_index(pool)];
+            let prediction = start_price * (1.0 + behaviour.prediction_noise * normal(rng));
+            // deposits are whole AMOUNT_DECIMALS units, like the program requires
+            let amount = rng.gen_range(behaviour.deposit_min..=behaviour.deposit_max).round();
+            if deposits && amount >= params.minimum_deposit {
+                player.deposit(&mut pools, pool, amount, prediction);
+            } else if player.in_game() {
+                player.predict(&mut pools, prediction);
+            }
+        }
+
+        // Some leave during the game and get nothing for it
+        for player in players.iter_mut() {
+            if player.in_game() && rng.gen_bool(behaviour.withdraw_probability) {
+                player.withdraw_all(&mut pools);
+            }
+        }
+
+        // Interest earned on what stayed in the pools
+        let total_deposit: f64 = pools.iter().map(|pool| pool.total_deposit).sum();
+        let interest = total_deposit * params.apy / 100.0 * days as f64 / 365.0;
+        let treasury = interest * params.treasury_percent / 100.0;
+        let retained = (interest - treasury) * params.retention_percent / 100.0;
+        let mut prize = interest - treasury - retained + carried_prize;
+        carried_prize = retained;
+
+        let predictions: Vec<f64> = pools.iter().map(|pool| pool.average_prediction).collect();
+        let winning_pool = math::select_winning_pool(&predictions, &end_prices);
+        let pool = &pools[pool_index(winning_pool)];
+        let winners: Vec<usize> =
+            (0..players.len()).filter(|&i| players[i].in_game() && players[i].user.pool == winning_pool).collect();
+
+        // Nobody to pay, the prize stays in the pools
+        let mut jackpot = 0.0;
+        if winners.is_empty() {
+            carried_prize += prize;
+            prize = 0.0;
+        } else {
+            jackpot = prize * params.jackpot_percent / 100.0;
+            let lucky = winners[rng.gen_range(0..winners.len())];
+            players[lucky].result.winnings += jackpot;
+            let mut paid = jackpot;
+            for &i in &winners {
+                let weight = players[i].user.prize_weight(pool);
+                let share = math::prize_share(weight, pool.prize_weight(), prize - jackpot);
+                players[i].result.winnings += share;
+                players[i].result.games_won += 1;
+                paid += share;
+            }
+            // shares are rounded down, what they leave of the prize stays in the pools
+            carried_prize += prize - paid;
+            prize = paid;
+        }
+
+        games.push(GameResult {
+            winning_pool,
+            interest,
+            treasury,
+            prize,
+            jackpot,
+            retained,
+            pool_deposits: std::array::from_fn(|i| pools[i].total_deposit),
+        });
+    }
+
+    Outcome {
+        params: params.clone(),
+        games,
+        players: players
+            .into_iter()
+            .map(|player| PlayerResult { pool: if player.in_game() { player.user.pool } else { 0 }, ..player.result })
+            .collect(),
+        carried_prize,
+    }
+}
@@ -0,0 +1,12 @@
+/* Economics simulator for coin-war. Price series for the four coins, synthetic or historical, are replayed game by
+ * game with simulated players depositing, predicting and withdrawing, and winners are picked and paid with the
+ * program's own math module. The report shows how game parameters like the game duration, minimum deposit,
+ * retention and jackpot percentages affect win rates, returns, treasury income and fairness before they are
+ * changed on chain.
+ */
+
+pub mod game;
+pub mod prices;
+pub mod report;
+
+pub use game::{simulate, Behaviour, Outcome, Params};
@@ -0,0 +1,114 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use coin_war_simulator::game::TOKEN;
+use coin_war_simulator::{prices, report, simulate, Behaviour, Params};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+#[derive(Parser)]
+#[clap(name = "coin-war-sim", about = "Simulate coin-war games offline to tune game parameters")]
+struct Cli {
+    /// Days per game
+    #[clap(long)]
+    game_duration_days: Option<i64>,
+    /// Smallest deposit accepted, in tokens
+    #[clap(long)]
+    minimum_deposit: Option<f64>,
+    /// Percent of the interest kept in the pools as prize for the next game
+    #[clap(long)]
+    retention_percent: Option<f64>,
+    /// Percent of the prize going to one lucky winner
+    #[clap(long)]
+    jackpot_percent: Option<f64>,
+    /// Percent of the interest kept by the treasury
+    #[clap(long)]
+    treasury_percent: Option<f64>,
+    /// Yearly interest earned on the deposits, in percent
+    #[clap(long)]
+    apy: Option<f64>,
+
+    #[clap(long, default_value_t = 100)]
+    players: usize,
+    /// Smallest and largest deposit of the players, in tokens
+    #[clap(long, default_value_t = 10.0)]
+    deposit_min: f64,
+    #[clap(long, default_value_t = 1_000.0)]
+    deposit_max: f64,
+    /// Chance a player with a balance deposits more in a game
+    #[clap(long, default_value_t = 0.2)]
+    deposit_probability: f64,
+    /// Standard deviation of predictions relative to the price at the start of the game
+    #[clap(long, default_value_t = 0.05)]
+    prediction_noise: f64,
+    /// Chance a player withdraws everything during a game
+    #[clap(long, default_value_t = 0.05)]
+    withdraw_probability: f64,
+
+    /// CSV of daily prices: date,solana,bnb,polygon,ethereum. Synthetic prices are used without it.
+    #[clap(long)]
+    prices: Option<PathBuf>,
+    /// Days of synthetic prices
+    #[clap(long, default_value_t = 365)]
+    days: usize,
+    /// Daily volatility of synthetic prices
+    #[clap(long, default_value_t = 0.04)]
+    volatility: f64,
+    /// Starting synthetic prices of Solana, BNB, Polygon and Ethereum
+    #[clap(long, number_of_values = 4, default_values = &["40", "300", "1", "1800"])]
+    start_prices: Vec<f64>,
+    /// Seed of the random generator, to reproduce a run
+    #[clap(long, default_value_t = 0)]
+    seed: u64,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let defaults = Params::default();
+    let params = Params {
+        game_duration_days: cli.game_duration_days.unwrap_or(defaults.game_duration_days),
+        minimum_deposit: cli.minimum_deposit.map(|tokens| tokens * TOKEN).unwrap_or(defaults.minimum_deposit),
+        retention_percent: cli.retention_percent.unwrap_or(defaults.retention_percent),
+        jackpot_percent: cli.jackpot_percent.unwrap_or(defaults.jackpot_percent),
+        treasury_percent: cli.treasury_percent.unwrap_or(defaults.treasury_percent),
+        apy: cli.apy.unwrap_or(defaults.apy),
+    };
+    let behaviour = Behaviour {
+        players: cli.players,
+        deposit_min: cli.deposit_min * TOKEN,
+        deposit_max: cli.deposit_max * TOKEN,
+        deposit_probability: cli.deposit_probability,
+        prediction_noise: cli.prediction_noise,
+        withdraw_probability: cli.withdraw_probability,
+    };
+    for probability in [behaviour.deposit_probability, behaviour.withdraw_probability] {
+        if !(0.0..=1.0).contains(&probability) {
+            return Err(anyhow!("probabilities have to be between 0 and 1"));
+        }
+    }
+    if behaviour.deposit_min > behaviour.deposit_max {
+        return Err(anyhow!("--deposit-min is above --deposit-max"));
+    }
+    if params.game_duration_days < 1 {
+        return Err(anyhow!("games have to last at least a day"));
+    }
+
+    let mut rng = StdRng::seed_from_u64(cli.seed);
+    let prices = match cli.prices {
+        Some(path) => prices::read_csv(BufReader::new(File::open(path)?))?,
+        None => {
+            let start = [cli.start_prices[0], cli.start_prices[1], cli.start_prices[2], cli.start_prices[3]];
+            prices::synthetic(&mut rng, start, cli.volatility, cli.days)
+        }
+    };
+
+    let outcome = simulate(&params, &behaviour, &prices, &mut rng);
+    if outcome.games.is_empty() {
+        return Err(anyhow!("{} days of prices don't cover a single game", prices.len()));
+    }
+    println!("{}", report::summarize(&outcome));
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use std::io::BufRead;
+
+use anyhow::{anyhow, Result};
+use rand::Rng;
+
+// Closing prices of one day, in the order of the Pools enum: Solana, BNB, Polygon, Ethereum
+pub type DailyPrices = [f64; 4];
+
+// Standard normal sample, Box-Muller
+pub fn normal(rng: &mut impl Rng) -> f64 {
+    let u1: f64 = rng.gen_range(f64::EPSILON..1.0);
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+// Geometric random walk over days, starting at start. volatility is the daily standard deviation of the returns.
+pub fn synthetic(rng: &mut impl Rng, start: DailyPrices, volatility: f64, days: usize) -> Vec<DailyPrices> {
+    let mut prices = vec![start];
+    for _ in 1..days {
+        let mut next = *prices.last().unwrap();
+        for price in next.iter_mut() {
+            *price *= (volatility * normal(rng) - volatility * volatility / 2.0).exp();
+        }
+        prices.push(next);
+    }
+    prices
+}
+
+// Historical prices as CSV with a header and one row per day: date,solana,bnb,polygon,ethereum. The first column
+// is only for reference and is ignored.
+pub fn read_csv(reader: impl BufRead) -> Result<Vec<DailyPrices>> {
+    let mut prices = Vec::new();
+    for (number, line) in reader.lines().enumerate().skip(1) {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let columns: Vec<&str> = line.split(',').map(str::trim).collect();
+        if columns.len() != 5 {
+            return Err(anyhow!("line {}: expected 5 columns, got {}", number + 1, columns.len()));
+        }
+        let mut day = [0.0; 4];
+        for (price, column) in day.iter_mut().zip(&columns[1..]) {
+            *price = column.parse().map_err(|_| anyhow!("line {}: invalid price {}", number + 1, column))?;
+        }
+        prices.push(day);
+    }
+    Ok(prices)
+}
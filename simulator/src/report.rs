@@ -0,0 +1,117 @@
+use std::fmt;
+
+use crate::game::{Outcome, POOL_COUNT, TOKEN};
+
+const POOL_NAMES: [&str; POOL_COUNT] = ["Solana", "BNB", "Polygon", "Ethereum"];
+
+#[derive(Clone, Debug)]
+pub struct Report {
+    pub games: usize,
+    // share of the games each pool won
+    pub win_rates: [f64; POOL_COUNT],
+    // average share of all deposits each pool held
+    pub deposit_shares: [f64; POOL_COUNT],
+    // in AMOUNT_DECIMALS units like the outcome, shown in tokens
+    pub total_interest: f64,
+    pub treasury_income: f64,
+    pub total_prize: f64,
+    pub total_jackpot: f64,
+    pub carried_prize: f64,
+    // prize paid per game relative to the deposits, and the same annualized
+    pub return_per_game: f64,
+    pub annual_return: f64,
+    // share of the players that won at least one game
+    pub players_with_winnings: f64,
+    // Gini coefficient of winnings per deposited token over the players, 0 when everyone earned the same rate
+    pub gini: f64,
+    // chi-square statistic of the win counts against every pool winning a quarter of the games. With four pools,
+    // anything above 7.81 is unlikely to be chance at the 5% level.
+    pub win_chi_square: f64,
+}
+
+// Gini coefficient of values, which are all expected to be >= 0
+pub fn gini(values: &[f64]) -> f64 {
+    let total: f64 = values.iter().sum();
+    if values.is_empty() || total <= 0.0 {
+        return 0.0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let n = sorted.len() as f64;
+    let weighted_sum: f64 = sorted.iter().enumerate().map(|(i, value)| (i + 1) as f64 * value).sum();
+    2.0 * weighted_sum / (n * total) - (n + 1.0) / n
+}
+
+pub fn summarize(outcome: &Outcome) -> Report {
+    let games = outcome.games.len();
+    let mut wins = [0usize; POOL_COUNT];
+    let mut deposit_shares = [0.0; POOL_COUNT];
+    let mut deposits_per_game = 0.0;
+    for game in &outcome.games {
+        wins[game.winning_pool as usize - 1] += 1;
+        let total: f64 = game.pool_deposits.iter().sum();
+        deposits_per_game += total;
+        if total > 0.0 {
+            for (share, deposit) in deposit_shares.iter_mut().zip(game.pool_deposits) {
+                *share += deposit / total / games as f64;
+            }
+        }
+    }
+
+    let total_prize: f64 = outcome.games.iter().map(|game| game.prize).sum();
+    let return_per_game = if deposits_per_game > 0.0 { total_prize / deposits_per_game } else { 0.0 };
+    let games_per_year = 365.0 / outcome.params.game_duration_days.max(1) as f64;
+
+    let expected_wins = games as f64 / POOL_COUNT as f64;
+    let win_chi_square = if games > 0 {
+        wins.iter().map(|&won| (won as f64 - expected_wins).powi(2) / expected_wins).sum()
+    } else {
+        0.0
+    };
+
+    let depositors: Vec<_> = outcome.players.iter().filter(|player| player.deposited > 0.0).collect();
+    let winning_rates: Vec<f64> = depositors.iter().map(|player| player.winnings / player.deposited).collect();
+    let winners = depositors.iter().filter(|player| player.games_won > 0).count();
+
+    Report {
+        games,
+        win_rates: wins.map(|won| if games > 0 { won as f64 / games as f64 } else { 0.0 }),
+        deposit_shares,
+        total_interest: outcome.games.iter().map(|game| game.interest).sum(),
+        treasury_income: outcome.games.iter().map(|game| game.treasury).sum(),
+        total_prize,
+        total_jackpot: outcome.games.iter().map(|game| game.jackpot).sum(),
+        carried_prize: outcome.carried_prize,
+        return_per_game,
+        annual_return: return_per_game * games_per_year,
+        players_with_winnings: if depositors.is_empty() { 0.0 } else { winners as f64 / depositors.len() as f64 },
+        gini: gini(&winning_rates),
+        win_chi_square,
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "games played          {}", self.games)?;
+        writeln!(f, "pool       win rate   deposit share")?;
+        for (i, name) in POOL_NAMES.iter().enumerate() {
+            writeln!(
+                f,
+                "{:<10} {:>7.1}%   {:>12.1}%",
+                name,
+                self.win_rates[i] * 100.0,
+                self.deposit_shares[i] * 100.0
+            )?;
+        }
+        writeln!(f, "interest earned       {:.2}", self.total_interest / TOKEN)?;
+        writeln!(f, "treasury income       {:.2}", self.treasury_income / TOKEN)?;
+        let (prize, jackpot) = (self.total_prize / TOKEN, self.total_jackpot / TOKEN);
+        writeln!(f, "prize paid            {:.2} (jackpots {:.2})", prize, jackpot)?;
+        writeln!(f, "prize carried over    {:.2}", self.carried_prize / TOKEN)?;
+        writeln!(f, "return per game       {:.4}%", self.return_per_game * 100.0)?;
+        writeln!(f, "annual return         {:.2}%", self.annual_return * 100.0)?;
+        writeln!(f, "players who won       {:.1}%", self.players_with_winnings * 100.0)?;
+        writeln!(f, "winnings gini         {:.3}", self.gini)?;
+        write!(f, "win count chi-square  {:.2}", self.win_chi_square)
+    }
+}
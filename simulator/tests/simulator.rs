@@ -0,0 +1,56 @@
+use coin_war_simulator::game::{Behaviour, Params, POOL_COUNT};
+use coin_war_simulator::{prices, report, simulate};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+const START: prices::DailyPrices = [40.0, 300.0, 1.0, 1_800.0];
+
+#[test]
+fn interest_is_fully_accounted_for() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let params = Params { treasury_percent: 10.0, ..Params::default() };
+    let series = prices::synthetic(&mut rng, START, 0.04, 200);
+    let outcome = simulate(&params, &Behaviour::default(), &series, &mut rng);
+    assert!(!outcome.games.is_empty());
+
+    let summary = report::summarize(&outcome);
+    let paid: f64 = outcome.players.iter().map(|player| player.winnings).sum();
+    assert!(
+        (summary.treasury_income + summary.total_prize + summary.carried_prize - summary.total_interest).abs() < 1e-6
+    );
+    assert!((paid - summary.total_prize).abs() < 1e-6);
+    assert!((summary.win_rates.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn flat_prices_always_go_to_the_first_pool() {
+    // Every pool predicts its price exactly, so the tie goes to Solana in every game
+    let mut rng = StdRng::seed_from_u64(1);
+    let behaviour = Behaviour { prediction_noise: 0.0, withdraw_probability: 0.0, ..Behaviour::default() };
+    let series = vec![START; 100];
+    let outcome = simulate(&Params::default(), &behaviour, &series, &mut rng);
+
+    let summary = report::summarize(&outcome);
+    assert_eq!(summary.win_rates[0], 1.0);
+    assert_eq!(summary.win_chi_square, 3.0 * outcome.games.len() as f64);
+    assert!(summary.deposit_shares.iter().all(|&share| share > 0.0 && share < 1.0));
+    assert_eq!(outcome.games.len(), 99 / Params::default().game_duration_days as usize);
+    assert_eq!(outcome.games[0].pool_deposits.len(), POOL_COUNT);
+}
+
+#[test]
+fn gini_of_equal_and_concentrated_winnings() {
+    assert_eq!(report::gini(&[1.0, 1.0, 1.0]), 0.0);
+    assert_eq!(report::gini(&[0.0, 0.0, 0.0, 4.0]), 0.75);
+    assert_eq!(report::gini(&[]), 0.0);
+}
+
+#[test]
+fn price_csv_is_parsed() {
+    let csv = "date,solana,bnb,polygon,ethereum\n2022-06-01,40.1,300,0.6,1800.5\n\n2022-06-02, 41,301,0.61,1810\n";
+    let series = prices::read_csv(csv.as_bytes()).unwrap();
+    assert_eq!(series, vec![[40.1, 300.0, 0.6, 1800.5], [41.0, 301.0, 0.61, 1810.0]]);
+
+    assert!(prices::read_csv("date,solana,bnb,polygon,ethereum\n2022-06-01,40,300,0.6\n".as_bytes()).is_err());
+    assert!(prices::read_csv("date,solana,bnb,polygon,ethereum\n2022-06-01,40,x,0.6,1800\n".as_bytes()).is_err());
+}